@@ -8,9 +8,16 @@ fn main() {
     use action_core::{Action, Parse, env};
     use std::collections::HashMap;
 
-    #[derive(Action)]
+    #[derive(Action, Debug)]
     #[action = "./examples/myaction.yml"]
-    struct MyAction {}
+    #[action(load)]
+    #[allow(dead_code)]
+    struct MyAction {
+        path: String,
+        dry_run: bool,
+        #[input(name = "extra-args")]
+        args: Option<String>,
+    }
 
     // parse all values into a map
     let inputs: HashMap<MyActionInput, Option<String>> = MyAction::parse();
@@ -20,6 +27,14 @@ fn main() {
     let resolve_versions: Option<bool> = MyAction::resolve_versions::<bool>().unwrap();
     dbg!(&resolve_versions);
 
+    // introspect the manifest (action.yml) of this action at runtime
+    let manifest = MyAction::manifest();
+    dbg!(&manifest.runs);
+
+    // load all fields from their inputs, falling back to their defaults
+    let action: MyAction = MyAction::load().unwrap();
+    dbg!(&action);
+
     {
         use action_core::input::ParseInput;
         // parse single value as type T using string name
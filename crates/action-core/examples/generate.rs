@@ -0,0 +1,46 @@
+#[cfg(not(feature = "derive"))]
+fn main() {
+    panic!(r#"feature "derive" must be enabled for this example"#);
+}
+
+#[cfg(feature = "derive")]
+fn main() {
+    use action_core::Action;
+
+    /// Publish crates on crates.io
+    #[derive(Action, Debug)]
+    #[action(
+        generate = "./examples/generated.yml",
+        check,
+        load,
+        name = "publish crates"
+    )]
+    #[action(branding(icon = "box", color = "orange"))]
+    #[action(runs(using = "node20", main = "dist/index.js"))]
+    #[action(output(name = "version", description = "Version that was published"))]
+    #[allow(dead_code)]
+    struct PublishAction {
+        /// Github API token
+        #[input(default = "${{ github.token }}")]
+        token: String,
+        /// Path to package or workspace
+        #[input(default = ".")]
+        path: String,
+        /// Additional arguments for cargo publish
+        extra_args: Option<String>,
+        /// Cargo registry token (e.g. crates.io)
+        registry_token: String,
+        /// This will perform all checks without publishing the package
+        #[input(
+            default = "false",
+            deprecation_message = "use `extra-args: --dry-run` instead"
+        )]
+        dry_run: bool,
+    }
+
+    // the manifest at ./examples/generated.yml is checked when this example is compiled,
+    // and written when it is compiled with ACTION_DERIVE_WRITE=1
+    println!("{}", PublishAction::description());
+    let action = PublishAction::load();
+    dbg!(&action);
+}
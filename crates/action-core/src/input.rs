@@ -97,6 +97,180 @@ impl Parse for usize {
     }
 }
 
+/// Location inside of an input value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Location {
+    /// Line number (starting at 1).
+    pub line: usize,
+    /// Column number (starting at 1).
+    pub column: usize,
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+#[cfg(feature = "json")]
+#[derive(thiserror::Error, Debug)]
+#[error("invalid JSON at {location}")]
+pub struct JsonError {
+    pub location: Location,
+    #[source]
+    pub source: serde_json::Error,
+}
+
+/// Input value that is decoded from JSON.
+#[cfg(feature = "json")]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Json<T>(pub T);
+
+#[cfg(feature = "json")]
+impl<T> Parse for Json<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    type Error = JsonError;
+
+    fn parse(value: OsString) -> Result<Self, Self::Error> {
+        serde_json::from_str(&value.to_string_lossy())
+            .map(Self)
+            .map_err(|source| JsonError {
+                location: Location {
+                    line: source.line(),
+                    column: source.column(),
+                },
+                source,
+            })
+    }
+}
+
+#[cfg(feature = "yaml")]
+#[derive(thiserror::Error, Debug)]
+#[error(
+    "invalid YAML{}",
+    .location.map(|location| format!(" at {location}")).unwrap_or_default()
+)]
+pub struct YamlError {
+    pub location: Option<Location>,
+    #[source]
+    pub source: serde_yaml::Error,
+}
+
+/// Input value that is decoded from YAML.
+#[cfg(feature = "yaml")]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Yaml<T>(pub T);
+
+#[cfg(feature = "yaml")]
+impl<T> Parse for Yaml<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    type Error = YamlError;
+
+    fn parse(value: OsString) -> Result<Self, Self::Error> {
+        serde_yaml::from_str(&value.to_string_lossy())
+            .map(Self)
+            .map_err(|source| YamlError {
+                location: source.location().map(|location| Location {
+                    line: location.line(),
+                    column: location.column(),
+                }),
+                source,
+            })
+    }
+}
+
+/// Options for reading an input.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InputOptions {
+    /// Whether the input is required.
+    ///
+    /// If required and not present, an [`InputError::Missing`] error is returned.
+    pub required: bool,
+    /// Whether leading and trailing whitespace is trimmed.
+    ///
+    /// Defaults to `true`.
+    pub trim_whitespace: bool,
+    /// Split list inputs into items on newlines.
+    ///
+    /// Defaults to `true`.
+    pub split_newline: bool,
+    /// Split list inputs into items on commas.
+    ///
+    /// Defaults to `false`.
+    pub split_comma: bool,
+    /// Skip lines of list inputs that start with `#`.
+    ///
+    /// Defaults to `false`.
+    pub skip_comments: bool,
+}
+
+impl Default for InputOptions {
+    fn default() -> Self {
+        Self {
+            required: false,
+            trim_whitespace: true,
+            split_newline: true,
+            split_comma: false,
+            skip_comments: false,
+        }
+    }
+}
+
+impl InputOptions {
+    /// Splits a list input into its items.
+    ///
+    /// Empty items are skipped.
+    #[must_use]
+    pub fn split<'a>(&self, value: &'a str) -> Vec<&'a str> {
+        let lines: Vec<&'a str> = if self.split_newline {
+            value.lines().collect()
+        } else {
+            vec![value]
+        };
+        lines
+            .into_iter()
+            .filter(|line| !(self.skip_comments && line.trim_start().starts_with('#')))
+            .flat_map(|line| {
+                if self.split_comma {
+                    line.split(',').collect()
+                } else {
+                    vec![line]
+                }
+            })
+            .map(|item| {
+                if self.trim_whitespace {
+                    item.trim()
+                } else {
+                    item
+                }
+            })
+            .filter(|item| !item.is_empty())
+            .collect()
+    }
+}
+
+/// Type-erased error, e.g. of an input decoded by the `Action` derive.
+#[derive(thiserror::Error, Debug)]
+#[error(transparent)]
+pub struct BoxError(pub Box<dyn std::error::Error + Send + Sync>);
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq, Clone)]
+pub enum InputError<E = std::convert::Infallible> {
+    #[error("input required and not supplied: {name:?}")]
+    Missing { name: OsString },
+    #[error("invalid value for input {name:?}")]
+    Parse {
+        name: OsString,
+        #[source]
+        source: E,
+    },
+}
+
 pub trait SetInput {
     /// Sets an input.
     fn set_input(&self, name: impl AsRef<OsStr>, value: impl AsRef<OsStr>);
@@ -114,6 +288,55 @@ where
 pub trait GetInput {
     /// Gets the raw value of an input.
     fn get_input(&self, name: impl AsRef<OsStr>) -> Option<OsString>;
+
+    /// Gets the value of an input according to `options`.
+    ///
+    /// # Errors
+    /// If the input is required but not present.
+    fn get_input_with(
+        &self,
+        name: impl AsRef<OsStr>,
+        options: &InputOptions,
+    ) -> Result<Option<String>, InputError> {
+        let value = self.get_input(name.as_ref()).map(|value| {
+            let value = value.to_string_lossy();
+            if options.trim_whitespace {
+                value.trim().to_string()
+            } else {
+                value.to_string()
+            }
+        });
+        match value.and_then(not_empty) {
+            None if options.required => Err(InputError::Missing {
+                name: name.as_ref().to_os_string(),
+            }),
+            value => Ok(value),
+        }
+    }
+
+    /// Gets the values of a multiline input.
+    ///
+    /// Each line is trimmed if `options.trim_whitespace` is set and empty lines are skipped.
+    /// Returns an empty list if the input is not present.
+    ///
+    /// # Errors
+    /// If the input is required but not present.
+    fn get_multiline_input(
+        &self,
+        name: impl AsRef<OsStr>,
+        options: &InputOptions,
+    ) -> Result<Vec<String>, InputError> {
+        let options = InputOptions {
+            split_newline: true,
+            ..*options
+        };
+        let value = self.get_input_with(name, &options)?.unwrap_or_default();
+        Ok(options
+            .split(&value)
+            .into_iter()
+            .map(ToOwned::to_owned)
+            .collect())
+    }
 }
 
 impl<E> GetInput for E
@@ -135,6 +358,68 @@ pub trait ParseInput {
     fn parse_input<T>(&self, name: impl AsRef<OsStr>) -> Result<Option<T>, <T as Parse>::Error>
     where
         T: Parse;
+
+    /// Parse the value of an input according to `options`.
+    ///
+    /// # Errors
+    /// If the input is required but not present, or cannot be parsed.
+    fn parse_input_with<T>(
+        &self,
+        name: impl AsRef<OsStr>,
+        options: &InputOptions,
+    ) -> Result<Option<T>, InputError<<T as Parse>::Error>>
+    where
+        T: Parse;
+
+    /// Parse the items of a list input.
+    ///
+    /// The input is split into items according to `options`,
+    /// and each item is parsed as T.
+    /// Returns an empty list if the input is not present.
+    ///
+    /// # Errors
+    /// If the input is required but not present, or any item cannot be parsed.
+    fn parse_list_input<T>(
+        &self,
+        name: impl AsRef<OsStr>,
+        options: &InputOptions,
+    ) -> Result<Vec<T>, InputError<<T as Parse>::Error>>
+    where
+        T: Parse;
+
+    /// Parse the value of an input as JSON.
+    ///
+    /// # Errors
+    /// If the input is required but not present, or is not valid JSON for T.
+    #[cfg(feature = "json")]
+    fn parse_json_input<T>(
+        &self,
+        name: impl AsRef<OsStr>,
+        options: &InputOptions,
+    ) -> Result<Option<T>, InputError<JsonError>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let value = self.parse_input_with::<Json<T>>(name, options)?;
+        Ok(value.map(|Json(value)| value))
+    }
+
+    /// Parse the value of an input as YAML.
+    ///
+    /// # Errors
+    /// If the input is required but not present, or is not valid YAML for T.
+    #[cfg(feature = "yaml")]
+    fn parse_yaml_input<T>(
+        &self,
+        name: impl AsRef<OsStr>,
+        options: &InputOptions,
+    ) -> Result<Option<T>, InputError<YamlError>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let value = self.parse_input_with::<Yaml<T>>(name, options)?;
+        Ok(value.map(|Yaml(value)| value))
+    }
 }
 
 impl<E> ParseInput for E
@@ -150,12 +435,65 @@ where
             None => Ok(None),
         }
     }
+
+    fn parse_input_with<T>(
+        &self,
+        name: impl AsRef<OsStr>,
+        options: &InputOptions,
+    ) -> Result<Option<T>, InputError<<T as Parse>::Error>>
+    where
+        T: Parse,
+    {
+        let name = name.as_ref();
+        match self.get_input_with(name, options).map_err(missing)? {
+            Some(input) => T::parse(input.into())
+                .map(Some)
+                .map_err(|source| InputError::Parse {
+                    name: name.to_os_string(),
+                    source,
+                }),
+            None => Ok(None),
+        }
+    }
+
+    fn parse_list_input<T>(
+        &self,
+        name: impl AsRef<OsStr>,
+        options: &InputOptions,
+    ) -> Result<Vec<T>, InputError<<T as Parse>::Error>>
+    where
+        T: Parse,
+    {
+        let name = name.as_ref();
+        let value = self
+            .get_input_with(name, options)
+            .map_err(missing)?
+            .unwrap_or_default();
+        options
+            .split(&value)
+            .into_iter()
+            .map(|item| {
+                T::parse(item.into()).map_err(|source| InputError::Parse {
+                    name: name.to_os_string(),
+                    source,
+                })
+            })
+            .collect()
+    }
+}
+
+fn missing<E>(err: InputError) -> InputError<E> {
+    match err {
+        InputError::Missing { name } => InputError::Missing { name },
+        InputError::Parse { source, .. } => match source {},
+    }
 }
 
 /// Gets the values of an multiline input.
 ///
 /// # Errors
 /// If the environment variable is not present.
+#[deprecated(note = "use `GetInput::get_multiline_input` instead")]
 pub fn get_multiline(env: &impl env::Read, name: impl AsRef<OsStr>) -> Option<Vec<String>> {
     let value = env.get_input(name)?;
     let lines = value
@@ -168,7 +506,7 @@ pub fn get_multiline(env: &impl env::Read, name: impl AsRef<OsStr>) -> Option<Ve
 
 #[cfg(test)]
 mod tests {
-    use super::{GetInput, ParseInput, SetInput};
+    use super::{GetInput, InputError, InputOptions, ParseError, ParseInput, SetInput};
     use crate::env::{EnvMap, Read};
     use similar_asserts::assert_eq as sim_assert_eq;
 
@@ -201,4 +539,251 @@ mod tests {
         env.set_input(input_name, " ");
         sim_assert_eq!(env.parse_input::<String>(input_name), Ok(Some(" ".into())));
     }
+
+    #[test]
+    fn test_get_input_with_options() {
+        let env = EnvMap::default();
+        let input_name = "some-input";
+        let required = InputOptions {
+            required: true,
+            ..InputOptions::default()
+        };
+        sim_assert_eq!(
+            env.get_input_with(input_name, &required),
+            Err(InputError::Missing {
+                name: input_name.into()
+            })
+        );
+        env.set_input(input_name, "  value ");
+        sim_assert_eq!(
+            env.get_input_with(input_name, &required),
+            Ok(Some("value".into()))
+        );
+        let untrimmed = InputOptions {
+            trim_whitespace: false,
+            ..InputOptions::default()
+        };
+        sim_assert_eq!(
+            env.get_input_with(input_name, &untrimmed),
+            Ok(Some("  value ".into()))
+        );
+        env.set_input(input_name, "   ");
+        sim_assert_eq!(
+            env.get_input_with(input_name, &InputOptions::default()),
+            Ok(None)
+        );
+        sim_assert_eq!(
+            env.get_input_with(input_name, &required),
+            Err(InputError::Missing {
+                name: input_name.into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_get_multiline_input() {
+        let env = EnvMap::default();
+        let input_name = "some-input";
+        sim_assert_eq!(
+            env.get_multiline_input(input_name, &InputOptions::default()),
+            Ok(vec![])
+        );
+        env.set_input(
+            input_name,
+            "first\n\n  second  \r\n# comment\nthird, fourth\n",
+        );
+        sim_assert_eq!(
+            env.get_multiline_input(input_name, &InputOptions::default()),
+            Ok(vec![
+                "first".to_string(),
+                "second".to_string(),
+                "# comment".to_string(),
+                "third, fourth".to_string(),
+            ])
+        );
+        let options = InputOptions {
+            trim_whitespace: false,
+            skip_comments: true,
+            ..InputOptions::default()
+        };
+        sim_assert_eq!(
+            env.get_multiline_input(input_name, &options),
+            Ok(vec![
+                "first".to_string(),
+                "  second  ".to_string(),
+                "third, fourth".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_list_input() {
+        let env = EnvMap::default();
+        let input_name = "some-input";
+        let options = InputOptions {
+            split_comma: true,
+            skip_comments: true,
+            ..InputOptions::default()
+        };
+        env.set_input(input_name, "1, 2,3\n# 4\n\n5,");
+        sim_assert_eq!(
+            env.parse_list_input::<usize>(input_name, &options),
+            Ok(vec![1, 2, 3, 5])
+        );
+        env.set_input(input_name, "true\nmaybe");
+        sim_assert_eq!(
+            env.parse_list_input::<bool>(input_name, &options),
+            Err(InputError::Parse {
+                name: input_name.into(),
+                source: ParseError::Bool("maybe".into()),
+            })
+        );
+        sim_assert_eq!(
+            env.parse_input_with::<bool>(
+                "other-input",
+                &InputOptions {
+                    required: true,
+                    ..InputOptions::default()
+                }
+            ),
+            Err(InputError::Missing {
+                name: "other-input".into()
+            })
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_parse_json_input() {
+        use super::Location;
+
+        #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+        struct Config {
+            os: Vec<String>,
+        }
+
+        let env = EnvMap::default();
+        let input_name = "some-input";
+        env.set_input(input_name, r#"{"os": ["linux", "macos"]}"#);
+        sim_assert_eq!(
+            env.parse_json_input::<Config>(input_name, &InputOptions::default())
+                .unwrap(),
+            Some(Config {
+                os: vec!["linux".into(), "macos".into()]
+            })
+        );
+
+        env.set_input(input_name, "{\n  \"os\": [\"linux\",]\n}");
+        let err = env
+            .parse_json_input::<Config>(input_name, &InputOptions::default())
+            .unwrap_err();
+        let InputError::Parse { name, source } = err else {
+            panic!("expected parse error, got {err:?}");
+        };
+        sim_assert_eq!(name, input_name);
+        sim_assert_eq!(
+            source.location,
+            Location {
+                line: 2,
+                column: 18
+            }
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_parse_yaml_input() {
+        use std::collections::HashMap;
+
+        let env = EnvMap::default();
+        let input_name = "some-input";
+        env.set_input(input_name, "bug: red\nfeature: green\n");
+        sim_assert_eq!(
+            env.parse_yaml_input::<HashMap<String, String>>(input_name, &InputOptions::default())
+                .unwrap(),
+            Some(HashMap::from_iter([
+                ("bug".to_string(), "red".to_string()),
+                ("feature".to_string(), "green".to_string()),
+            ]))
+        );
+
+        env.set_input(input_name, "bug: red\nfeature: [green\n");
+        let err = env
+            .parse_yaml_input::<HashMap<String, String>>(input_name, &InputOptions::default())
+            .unwrap_err();
+        let InputError::Parse { name, source } = err else {
+            panic!("expected parse error, got {err:?}");
+        };
+        sim_assert_eq!(name, input_name);
+        sim_assert_eq!(source.location.map(|location| location.line), Some(2));
+        assert!(source.to_string().starts_with("invalid YAML at line 2"));
+    }
+
+    #[cfg(all(feature = "derive", feature = "json", feature = "yaml"))]
+    #[test]
+    fn test_load_structured_inputs() {
+        use std::collections::HashMap;
+
+        #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+        struct Matrix {
+            os: Vec<String>,
+        }
+
+        #[derive(crate::Action, Debug, PartialEq, Eq)]
+        #[action = "./fixtures/structured.yml"]
+        #[action(load)]
+        struct StructuredAction {
+            #[input(json)]
+            matrix: Matrix,
+            #[input(yaml)]
+            labels: Option<HashMap<String, String>>,
+            #[input(skip)]
+            attempts: usize,
+        }
+
+        let env = EnvMap::default();
+        env.set_input("matrix", r#"{"os": ["linux", "macos"]}"#);
+        sim_assert_eq!(
+            StructuredAction::load_from(&env).unwrap(),
+            StructuredAction {
+                matrix: Matrix {
+                    os: vec!["linux".into(), "macos".into()]
+                },
+                labels: Some(HashMap::from_iter([("bug".to_string(), "red".to_string())])),
+                attempts: 0,
+            }
+        );
+
+        env.set_input("labels", "feature: green\n");
+        sim_assert_eq!(
+            StructuredAction::load_from(&env).unwrap().labels,
+            Some(HashMap::from_iter([(
+                "feature".to_string(),
+                "green".to_string()
+            )]))
+        );
+
+        env.set_input("matrix", r#"{"os": "linux"}"#);
+        let err = StructuredAction::load_from(&env).unwrap_err();
+        assert!(matches!(err, InputError::Parse { ref name, .. } if name == "matrix"));
+
+        let env = EnvMap::default();
+        let err = StructuredAction::load_from(&env).unwrap_err();
+        assert!(matches!(err, InputError::Missing { ref name } if name == "matrix"));
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derive_without_load() {
+        // fields do not need to match inputs unless `#[action(load)]` is requested
+        #[derive(crate::Action)]
+        #[action = "./fixtures/structured.yml"]
+        #[allow(dead_code)]
+        struct UnannotatedAction {
+            cache: String,
+            verbose: bool,
+        }
+
+        sim_assert_eq!(UnannotatedAction::inputs().len(), 2);
+    }
 }
@@ -1,14 +1,24 @@
 pub mod env;
 pub mod input;
+pub mod limits;
+#[cfg(feature = "manifest")]
+pub use action_model::action as manifest;
 pub mod summary;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod utils;
 
+use limits::Limits;
 use std::collections::HashMap;
 use std::path::Path;
 
 #[cfg(feature = "derive")]
 pub use action_derive::Action;
 
+// the derive macro refers to `::action_core`, also in the tests of this crate
+#[cfg(all(test, feature = "derive"))]
+extern crate self as action_core;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash)]
 pub enum LogLevel {
     Debug,
@@ -53,18 +63,33 @@ pub fn prepare_kv_message(key: &str, value: &str) -> Result<String, ValueError>
 /// Sets env variable for this action and future actions in the job.
 ///
 /// # Errors
-/// If the file command fails.
+/// If the value exceeds the default [`Limits`] or the file command fails.
 pub fn export_var(
     env: &(impl env::Read + env::Write),
     name: impl AsRef<str>,
     value: impl Into<String>,
+) -> Result<(), CommandError> {
+    export_var_with(env, name, value, &Limits::default())
+}
+
+/// Sets env variable for this action and future actions in the job.
+///
+/// # Errors
+/// If the value exceeds the `limits` or the file command fails.
+pub fn export_var_with(
+    env: &(impl env::Read + env::Write),
+    name: impl AsRef<str>,
+    value: impl Into<String>,
+    limits: &Limits,
 ) -> Result<(), CommandError> {
     let value = value.into();
-    env.set(name.as_ref(), &value);
+    let value = limits.check_value(name.as_ref(), &value)?;
+    env.set(name.as_ref(), value.as_ref());
 
     if env.get("GITHUB_ENV").is_some() {
         let message = prepare_kv_message(name.as_ref(), &value)?;
-        issue_file_command("ENV", message)?;
+        limits.check_file_command(env, "ENV", &message)?;
+        issue_file_command_with(env, "ENV", message)?;
         return Ok(());
     }
 
@@ -76,20 +101,112 @@ pub fn export_var(
     Ok(())
 }
 
+/// Sets the value of an output.
+///
+/// # Errors
+/// If the value exceeds the default [`Limits`] or the file command fails.
+pub fn set_output(
+    env: &impl env::Read,
+    name: impl AsRef<str>,
+    value: impl Into<String>,
+) -> Result<(), CommandError> {
+    set_output_with(env, name, value, &Limits::default())
+}
+
+/// Sets the value of an output.
+///
+/// # Errors
+/// If the value exceeds the `limits` or the file command fails.
+pub fn set_output_with(
+    env: &impl env::Read,
+    name: impl AsRef<str>,
+    value: impl Into<String>,
+    limits: &Limits,
+) -> Result<(), CommandError> {
+    let value = value.into();
+    let value = limits.check_value(name.as_ref(), &value)?;
+
+    if env.get("GITHUB_OUTPUT").is_some() {
+        let message = prepare_kv_message(name.as_ref(), &value)?;
+        limits.check_file_command(env, "OUTPUT", &message)?;
+        issue_file_command_with(env, "OUTPUT", message)?;
+        return Ok(());
+    }
+
+    println!();
+    issue(
+        &CommandBuilder::new("set-output", value)
+            .property("name", name.as_ref())
+            .build(),
+    );
+    Ok(())
+}
+
+/// Sets the value of an output to the JSON serialization of `value`.
+///
+/// # Errors
+/// If the value cannot be serialized, exceeds the default [`Limits`] or the file command fails.
+#[cfg(feature = "json")]
+pub fn set_output_json<T>(
+    env: &impl env::Read,
+    name: impl AsRef<str>,
+    value: &T,
+) -> Result<(), CommandError>
+where
+    T: serde::Serialize + ?Sized,
+{
+    set_output_json_with(env, name, value, &Limits::default())
+}
+
+/// Sets the value of an output to the JSON serialization of `value`.
+///
+/// # Errors
+/// If the value cannot be serialized, exceeds the `limits` or the file command fails.
+#[cfg(feature = "json")]
+pub fn set_output_json_with<T>(
+    env: &impl env::Read,
+    name: impl AsRef<str>,
+    value: &T,
+    limits: &Limits,
+) -> Result<(), CommandError>
+where
+    T: serde::Serialize + ?Sized,
+{
+    let value = serde_json::to_string(value)?;
+    set_output_with(env, name, value, limits)
+}
+
+/// Secrets registered by this process.
+static SECRETS: parking_lot::Mutex<Vec<String>> = parking_lot::Mutex::new(Vec::new());
+
 /// Registers a secret which will get masked from logs.
 pub fn set_secret(secret: impl Into<String>) {
+    let secret = secret.into();
+    {
+        let mut secrets = SECRETS.lock();
+        if !secret.is_empty() && !secrets.contains(&secret) {
+            secrets.push(secret.clone());
+        }
+    }
     issue(&CommandBuilder::new("add-mask", secret).build());
 }
 
+/// Returns the secrets registered with [`set_secret`] by this process,
+/// e.g. to redact them from data written outside of the log.
+#[must_use]
+pub fn secrets() -> Vec<String> {
+    SECRETS.lock().clone()
+}
+
 /// Prepends a path to the `PATH` environment variable.
 ///
 /// # Errors
 /// If the paths can not be joined.
 fn prepend_to_path(
-    env: &impl env::Write,
+    env: &(impl env::Read + env::Write),
     path: impl AsRef<Path>,
 ) -> Result<(), std::env::JoinPathsError> {
-    if let Some(old_path) = std::env::var_os("PATH") {
+    if let Some(old_path) = env.get("PATH") {
         let paths = [path.as_ref().to_path_buf()]
             .into_iter()
             .chain(std::env::split_paths(&old_path));
@@ -220,11 +337,15 @@ impl std::fmt::Display for Command {
             }
             write!(f, "{k}={}", utils::escape_property(v))?;
         }
-        write!(f, "{}{}", CMD_STRING, self.message)
+        write!(f, "{}{}", CMD_STRING, utils::escape_data(&self.message))
     }
 }
 
 pub fn issue(cmd: &Command) {
+    #[cfg(feature = "testing")]
+    if testing::capture(cmd) {
+        return;
+    }
     println!("{cmd}");
 }
 
@@ -255,6 +376,13 @@ pub enum CommandError {
 
     #[error(transparent)]
     Value(#[from] ValueError),
+
+    #[error(transparent)]
+    Limit(#[from] limits::LimitError),
+
+    #[cfg(feature = "json")]
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
 }
 
 /// Issue a file command.
@@ -264,11 +392,23 @@ pub enum CommandError {
 pub fn issue_file_command(
     command: impl AsRef<str>,
     message: impl AsRef<str>,
+) -> Result<(), FileCommandError> {
+    issue_file_command_with(&env::OsEnv, command, message)
+}
+
+/// Issue a file command to the file named by the `GITHUB_{command}` variable of `env`.
+///
+/// # Errors
+/// When no env variable for the file command exists or writing fails.
+pub fn issue_file_command_with(
+    env: &impl env::Read,
+    command: impl AsRef<str>,
+    message: impl AsRef<str>,
 ) -> Result<(), FileCommandError> {
     use std::io::Write;
     let key = format!("GITHUB_{}", command.as_ref());
-    let file_path = std::env::var(key).map_err(|source| FileCommandError::Missing {
-        source,
+    let file_path = env.get(key).ok_or_else(|| FileCommandError::Missing {
+        source: std::env::VarError::NotPresent,
         cmd: command.as_ref().to_string(),
     })?;
     let file = std::fs::OpenOptions::new().append(true).open(file_path)?;
@@ -300,7 +440,7 @@ pub fn add_path(
     prepend_to_path(env, path.as_ref())?;
 
     if env.get("GITHUB_PATH").is_some() {
-        issue_file_command("PATH", &path_string)?;
+        issue_file_command_with(env, "PATH", &path_string)?;
     } else {
         issue(&CommandBuilder::new("add-path", path_string).build());
     }
@@ -316,7 +456,7 @@ pub fn add_path(
 //     issue();
 // }
 
-#[derive(Default, Debug, Hash, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, Hash, PartialEq, Eq)]
 pub struct AnnotationProperties {
     pub title: Option<String>,
     pub file: Option<String>,
@@ -455,15 +595,32 @@ pub fn end_group() {
 /// Saves state for current action, the state can only be retrieved by this action's post job execution.
 ///
 /// # Errors
-/// If the file command fails.
+/// If the value exceeds the default [`Limits`] or the file command fails.
 pub fn save_state(
     env: &impl env::Read,
     name: impl AsRef<str>,
     value: impl Into<String>,
 ) -> Result<(), CommandError> {
+    save_state_with(env, name, value, &Limits::default())
+}
+
+/// Saves state for current action, the state can only be retrieved by this action's post job execution.
+///
+/// # Errors
+/// If the value exceeds the `limits` or the file command fails.
+pub fn save_state_with(
+    env: &impl env::Read,
+    name: impl AsRef<str>,
+    value: impl Into<String>,
+    limits: &Limits,
+) -> Result<(), CommandError> {
+    let value = value.into();
+    let value = limits.check_value(name.as_ref(), &value)?;
+
     if env.get("GITHUB_STATE").is_some() {
-        let message = prepare_kv_message(name.as_ref(), &value.into())?;
-        issue_file_command("STATE", message)?;
+        let message = prepare_kv_message(name.as_ref(), &value)?;
+        limits.check_file_command(env, "STATE", &message)?;
+        issue_file_command_with(env, "STATE", message)?;
         return Ok(());
     }
 
@@ -478,7 +635,14 @@ pub fn save_state(
 /// Gets the value of an state set by this action's main execution.
 #[must_use]
 pub fn get_state(name: impl AsRef<str>) -> Option<String> {
-    std::env::var(format!("STATE_{}", name.as_ref())).ok()
+    get_state_with(&env::OsEnv, name)
+}
+
+/// Gets the value of an state set by this action's main execution from `env`.
+#[must_use]
+pub fn get_state_with(env: &impl env::Read, name: impl AsRef<str>) -> Option<String> {
+    env.get(format!("STATE_{}", name.as_ref()))
+        .map(|value| value.to_string_lossy().to_string())
 }
 
 /// Wrap an asynchronous function call in a group.
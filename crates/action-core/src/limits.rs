@@ -0,0 +1,328 @@
+use crate::env;
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+/// Maximum size of a single output value in bytes.
+pub const MAX_VALUE_SIZE: usize = 1024 * 1024;
+
+/// Maximum size of a file command file in bytes.
+///
+/// This is the 50 MiB GitHub allows for all outputs of a workflow run,
+/// which also bounds what a single step can write to one of its files.
+pub const MAX_FILE_SIZE: usize = 50 * 1024 * 1024;
+
+/// Maximum length of the part of a spill file name taken from the name of the value.
+const MAX_SPILL_NAME_LEN: usize = 64;
+
+/// What to do with a value that exceeds [`Limits::max_value_size`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Overflow {
+    /// Fail with a [`LimitError::ValueTooLarge`] error.
+    Error,
+    /// Write the value to a file in the given directory and use its path as the value.
+    ///
+    /// The file is named after the value, with characters other than ASCII letters,
+    /// digits, `-` and `_` replaced, followed by a random suffix.
+    Spill(PathBuf),
+}
+
+/// Size limits for values written with file commands.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Limits {
+    /// Maximum size of a single value in bytes.
+    pub max_value_size: usize,
+    /// Maximum size of each file command file (`GITHUB_OUTPUT`, `GITHUB_ENV`, `GITHUB_STATE`)
+    /// in bytes.
+    ///
+    /// The runner creates these files for every step, so the limit applies
+    /// per file and per step, not to everything written by the job.
+    pub max_file_size: usize,
+    /// What to do with a value that exceeds `max_value_size`.
+    pub overflow: Overflow,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_value_size: MAX_VALUE_SIZE,
+            max_file_size: MAX_FILE_SIZE,
+            overflow: Overflow::Error,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum LimitError {
+    #[error("value of {name:?} is {size} bytes, exceeding the limit of {limit} bytes")]
+    ValueTooLarge {
+        name: String,
+        size: usize,
+        limit: usize,
+    },
+    #[error("file command {cmd} would grow to {size} bytes, exceeding the limit of {limit} bytes")]
+    FileTooLarge {
+        cmd: String,
+        size: u64,
+        limit: usize,
+    },
+    #[error("failed to spill value of {name:?} to {path:?}")]
+    Spill {
+        name: String,
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// Name of the file a value named `name` is spilled to, which stays within its directory.
+fn spill_file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .take(MAX_SPILL_NAME_LEN)
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{name}-{}", uuid::Uuid::new_v4())
+}
+
+impl Limits {
+    /// Checks the size of a value.
+    ///
+    /// Returns the value that should be written,
+    /// which is the path of the spilled value if it was too large.
+    ///
+    /// # Errors
+    /// If the value is too large and cannot be spilled.
+    pub fn check_value<'a>(&self, name: &str, value: &'a str) -> Result<Cow<'a, str>, LimitError> {
+        if value.len() <= self.max_value_size {
+            return Ok(Cow::Borrowed(value));
+        }
+        match &self.overflow {
+            Overflow::Error => Err(LimitError::ValueTooLarge {
+                name: name.to_string(),
+                size: value.len(),
+                limit: self.max_value_size,
+            }),
+            Overflow::Spill(dir) => {
+                let path = dir.join(spill_file_name(name));
+                std::fs::create_dir_all(dir)
+                    .and_then(|()| std::fs::write(&path, value))
+                    .map_err(|source| LimitError::Spill {
+                        name: name.to_string(),
+                        path: path.clone(),
+                        source,
+                    })?;
+                Ok(Cow::Owned(path.to_string_lossy().to_string()))
+            }
+        }
+    }
+
+    /// Checks that appending a message to the file of a file command stays within limits.
+    ///
+    /// # Errors
+    /// If the file would exceed `max_file_size`.
+    pub fn check_file_command(
+        &self,
+        env: &impl env::Read,
+        command: impl AsRef<str>,
+        message: impl AsRef<str>,
+    ) -> Result<(), LimitError> {
+        let key = format!("GITHUB_{}", command.as_ref());
+        let current = env
+            .get(key)
+            .and_then(|path| std::fs::metadata(path).ok())
+            .map_or(0, |metadata| metadata.len());
+        let size = current + message.as_ref().len() as u64 + 1;
+        if size > self.max_file_size as u64 {
+            return Err(LimitError::FileTooLarge {
+                cmd: command.as_ref().to_string(),
+                size,
+                limit: self.max_file_size,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LimitError, Limits, MAX_FILE_SIZE, Overflow};
+    use crate::env::{EnvMap, Read};
+    use crate::{CommandError, export_var_with, save_state_with, set_output_with};
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use std::path::Path;
+
+    #[test]
+    fn test_value_within_limit() {
+        let limits = Limits {
+            max_value_size: 5,
+            ..Limits::default()
+        };
+        sim_assert_eq!(limits.check_value("name", "12345").unwrap(), "12345");
+    }
+
+    #[test]
+    fn test_value_too_large() {
+        let limits = Limits {
+            max_value_size: 5,
+            ..Limits::default()
+        };
+        let err = limits.check_value("name", "123456").unwrap_err();
+        assert!(matches!(
+            err,
+            LimitError::ValueTooLarge {
+                size: 6,
+                limit: 5,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_value_spilled() {
+        let dir = tempfile::tempdir().unwrap();
+        let limits = Limits {
+            max_value_size: 5,
+            overflow: Overflow::Spill(dir.path().to_path_buf()),
+            ..Limits::default()
+        };
+        let path = limits.check_value("name", "123456").unwrap();
+        assert!(path.starts_with(&*dir.path().to_string_lossy()));
+        sim_assert_eq!(std::fs::read_to_string(&*path).unwrap(), "123456");
+    }
+
+    #[test]
+    fn test_spill_file_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let limits = Limits {
+            max_value_size: 5,
+            overflow: Overflow::Spill(dir.path().to_path_buf()),
+            ..Limits::default()
+        };
+        let path = limits.check_value("../../etc/passwd x", "123456").unwrap();
+        let path = Path::new(&*path);
+        sim_assert_eq!(path.parent(), Some(dir.path()));
+        let file_name = path.file_name().unwrap().to_string_lossy();
+        assert!(file_name.starts_with("______etc_passwd_x-"), "{file_name}");
+
+        let path = limits.check_value(&"a".repeat(1000), "123456").unwrap();
+        let file_name = Path::new(&*path).file_name().unwrap().to_string_lossy();
+        sim_assert_eq!(file_name.len(), 64 + 1 + 36);
+    }
+
+    #[test]
+    fn test_file_command_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("output");
+        // sparse file just below the limit of the file size
+        let file = std::fs::File::create(&output).unwrap();
+        file.set_len(MAX_FILE_SIZE as u64 - 10).unwrap();
+        let env = EnvMap::from_iter([("GITHUB_OUTPUT", output.clone())]);
+
+        let limits = Limits::default();
+        limits
+            .check_file_command(&env, "OUTPUT", "a".repeat(9))
+            .unwrap();
+        let err = limits
+            .check_file_command(&env, "OUTPUT", "a".repeat(10))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            LimitError::FileTooLarge { size, limit: MAX_FILE_SIZE, .. }
+                if size == MAX_FILE_SIZE as u64 + 1
+        ));
+
+        let err = set_output_with(&env, "name", "value", &limits).unwrap_err();
+        assert!(matches!(
+            err,
+            CommandError::Limit(LimitError::FileTooLarge { .. })
+        ));
+        sim_assert_eq!(
+            std::fs::metadata(&output).unwrap().len(),
+            MAX_FILE_SIZE as u64 - 10
+        );
+    }
+
+    #[test]
+    fn test_env_and_state_file_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("output");
+        let github_env = dir.path().join("env");
+        let state = dir.path().join("state");
+        std::fs::write(&output, "").unwrap();
+        std::fs::write(&github_env, "a".repeat(200)).unwrap();
+        std::fs::write(&state, "a".repeat(200)).unwrap();
+        let env = EnvMap::from_iter([
+            ("GITHUB_OUTPUT", output),
+            ("GITHUB_ENV", github_env),
+            ("GITHUB_STATE", state),
+        ]);
+        let limits = Limits {
+            max_file_size: 256,
+            ..Limits::default()
+        };
+
+        // every file command file is limited on its own
+        set_output_with(&env, "name", "value", &limits).unwrap();
+        let err = export_var_with(&env, "NAME", "value", &limits).unwrap_err();
+        assert!(matches!(
+            err,
+            CommandError::Limit(LimitError::FileTooLarge { ref cmd, limit: 256, .. }) if cmd == "ENV"
+        ));
+        let err = save_state_with(&env, "name", "value", &limits).unwrap_err();
+        assert!(matches!(
+            err,
+            CommandError::Limit(LimitError::FileTooLarge { ref cmd, limit: 256, .. }) if cmd == "STATE"
+        ));
+    }
+
+    /// Value of `name` in a file command file.
+    fn file_command_value(path: &Path, name: &str) -> String {
+        let content = std::fs::read_to_string(path).unwrap();
+        let mut lines = content
+            .lines()
+            .skip_while(|line| !line.starts_with(&format!("{name}<<")));
+        lines.nth(1).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_spill_file_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let spill = dir.path().join("spill");
+        let output = dir.path().join("output");
+        let github_env = dir.path().join("env");
+        std::fs::write(&output, "").unwrap();
+        std::fs::write(&github_env, "").unwrap();
+        let env = EnvMap::from_iter([
+            ("GITHUB_OUTPUT", output.clone()),
+            ("GITHUB_ENV", github_env.clone()),
+        ]);
+        let limits = Limits {
+            max_value_size: 5,
+            overflow: Overflow::Spill(spill.clone()),
+            ..Limits::default()
+        };
+
+        set_output_with(&env, "report", "large output", &limits).unwrap();
+        let path = file_command_value(&output, "report");
+        assert!(Path::new(&path).starts_with(&spill));
+        sim_assert_eq!(std::fs::read_to_string(&path).unwrap(), "large output");
+
+        export_var_with(&env, "REPORT", "large variable", &limits).unwrap();
+        let path = file_command_value(&github_env, "REPORT");
+        sim_assert_eq!(
+            env.get("REPORT")
+                .map(|value| value.to_string_lossy().into_owned()),
+            Some(path.clone())
+        );
+        sim_assert_eq!(std::fs::read_to_string(&path).unwrap(), "large variable");
+
+        set_output_with(&env, "small", "value", &limits).unwrap();
+        sim_assert_eq!(file_command_value(&output, "small"), "value");
+    }
+}
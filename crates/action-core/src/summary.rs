@@ -1,3 +1,5 @@
+use crate::{FileCommandError, env};
+
 pub const ENV_VAR: &str = "GITHUB_STEP_SUMMARY";
 pub const DOCS_URL: &str = "https://docs.github.com/actions/using-workflows/workflow-commands-for-github-actions#adding-a-job-summary";
 
@@ -46,12 +48,269 @@ impl Default for TableCell {
 #[derive(Default, Debug, PartialEq, Eq, Hash, Clone)]
 pub struct ImageOptions {
     /// The width of the image in pixels.
-    width: Option<usize>,
+    pub width: Option<usize>,
 
     /// The height of the image in pixels.
-    height: Option<usize>,
+    pub height: Option<usize>,
+}
+
+/// Builder for the markdown and HTML of a job summary.
+///
+/// The content is buffered until it is written to the summary file of the step,
+/// or rendered with [`Summary::stringify`] to be used elsewhere, e.g. in a comment.
+#[derive(Default, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Summary {
+    buffer: String,
+}
+
+/// Wraps `content` in an HTML element, which is self-closing if there is no content.
+fn wrap(tag: &str, content: Option<&str>, attrs: &[(&str, String)]) -> String {
+    let attrs: String = attrs
+        .iter()
+        .map(|(key, value)| format!(" {key}=\"{value}\""))
+        .collect();
+    match content {
+        Some(content) if !content.is_empty() => format!("<{tag}{attrs}>{content}</{tag}>"),
+        _ => format!("<{tag}{attrs}>"),
+    }
+}
+
+impl Summary {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the buffer to the summary file of the step and empties the buffer.
+    ///
+    /// The buffer is appended unless `overwrite` is set.
+    ///
+    /// # Errors
+    /// If `GITHUB_STEP_SUMMARY` is not set or the file cannot be written.
+    pub fn write(
+        &mut self,
+        env: &impl env::Read,
+        overwrite: bool,
+    ) -> Result<&mut Self, FileCommandError> {
+        use std::io::Write;
+        let path = env.get(ENV_VAR).ok_or_else(|| FileCommandError::Missing {
+            source: std::env::VarError::NotPresent,
+            cmd: "STEP_SUMMARY".to_string(),
+        })?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(!overwrite)
+            .truncate(overwrite)
+            .open(path)?;
+        file.write_all(self.buffer.as_bytes())?;
+        Ok(self.empty_buffer())
+    }
+
+    /// Empties the buffer and the summary file of the step.
+    ///
+    /// # Errors
+    /// If `GITHUB_STEP_SUMMARY` is not set or the file cannot be written.
+    pub fn clear(&mut self, env: &impl env::Read) -> Result<&mut Self, FileCommandError> {
+        self.empty_buffer().write(env, true)
+    }
+
+    /// Content of the buffer.
+    #[must_use]
+    pub fn stringify(&self) -> &str {
+        &self.buffer
+    }
+
+    #[must_use]
+    pub fn is_empty_buffer(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn empty_buffer(&mut self) -> &mut Self {
+        self.buffer.clear();
+        self
+    }
+
+    /// Adds raw text, optionally followed by a line break.
+    pub fn add_raw(&mut self, text: impl AsRef<str>, add_eol: bool) -> &mut Self {
+        self.buffer.push_str(text.as_ref());
+        if add_eol {
+            self.add_eol();
+        }
+        self
+    }
+
+    pub fn add_eol(&mut self) -> &mut Self {
+        self.add_raw("\n", false)
+    }
+
+    /// Adds a code block, highlighted as `lang` if set.
+    pub fn add_code_block(&mut self, code: impl AsRef<str>, lang: Option<&str>) -> &mut Self {
+        let attrs: Vec<_> = lang
+            .map(|lang| ("lang", lang.to_string()))
+            .into_iter()
+            .collect();
+        let element = wrap("pre", Some(&wrap("code", Some(code.as_ref()), &[])), &attrs);
+        self.add_raw(element, true)
+    }
+
+    pub fn add_list<I>(&mut self, items: I, ordered: bool) -> &mut Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let tag = if ordered { "ol" } else { "ul" };
+        let items: String = items
+            .into_iter()
+            .map(|item| wrap("li", Some(item.as_ref()), &[]))
+            .collect();
+        self.add_raw(wrap(tag, Some(&items), &[]), true)
+    }
+
+    pub fn add_table(&mut self, rows: &[Vec<TableCell>]) -> &mut Self {
+        let body: String = rows
+            .iter()
+            .map(|row| {
+                let cells: String = row
+                    .iter()
+                    .map(|cell| {
+                        let tag = if cell.header { "th" } else { "td" };
+                        let mut attrs = Vec::new();
+                        if cell.colspan != 1 {
+                            attrs.push(("colspan", cell.colspan.to_string()));
+                        }
+                        if cell.rowspan != 1 {
+                            attrs.push(("rowspan", cell.rowspan.to_string()));
+                        }
+                        wrap(tag, Some(&cell.data), &attrs)
+                    })
+                    .collect();
+                wrap("tr", Some(&cells), &[])
+            })
+            .collect();
+        self.add_raw(wrap("table", Some(&body), &[]), true)
+    }
+
+    /// Adds a collapsible section.
+    pub fn add_details(&mut self, label: impl AsRef<str>, content: impl AsRef<str>) -> &mut Self {
+        let content = wrap("summary", Some(label.as_ref()), &[]) + content.as_ref();
+        self.add_raw(wrap("details", Some(&content), &[]), true)
+    }
+
+    pub fn add_image(
+        &mut self,
+        src: impl Into<String>,
+        alt: impl Into<String>,
+        options: &ImageOptions,
+    ) -> &mut Self {
+        let mut attrs = vec![("src", src.into()), ("alt", alt.into())];
+        attrs.extend(options.width.map(|width| ("width", width.to_string())));
+        attrs.extend(options.height.map(|height| ("height", height.to_string())));
+        self.add_raw(wrap("img", None, &attrs), true)
+    }
+
+    /// Adds a heading of `level` 1 to 6, other levels are rendered as level 1.
+    pub fn add_heading(&mut self, text: impl AsRef<str>, level: usize) -> &mut Self {
+        let tag = format!("h{}", if (1..=6).contains(&level) { level } else { 1 });
+        self.add_raw(wrap(&tag, Some(text.as_ref()), &[]), true)
+    }
+
+    pub fn add_separator(&mut self) -> &mut Self {
+        self.add_raw(wrap("hr", None, &[]), true)
+    }
+
+    pub fn add_break(&mut self) -> &mut Self {
+        self.add_raw(wrap("br", None, &[]), true)
+    }
+
+    pub fn add_quote(&mut self, text: impl AsRef<str>, cite: Option<&str>) -> &mut Self {
+        let attrs: Vec<_> = cite
+            .map(|cite| ("cite", cite.to_string()))
+            .into_iter()
+            .collect();
+        self.add_raw(wrap("blockquote", Some(text.as_ref()), &attrs), true)
+    }
+
+    pub fn add_link(&mut self, text: impl AsRef<str>, href: impl Into<String>) -> &mut Self {
+        self.add_raw(
+            wrap("a", Some(text.as_ref()), &[("href", href.into())]),
+            true,
+        )
+    }
 }
 
-// todo: finish porting the summary stuff
-// finish the proc macro, and test it!
-// continue with the cache stuff?
+#[cfg(test)]
+mod tests {
+    use super::{ImageOptions, Summary, TableCell};
+    use crate::env::EnvMap;
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    #[test]
+    fn test_stringify() {
+        let mut summary = Summary::new();
+        summary
+            .add_heading("Results", 2)
+            .add_code_block("cargo test", Some("sh"))
+            .add_list(["a", "b"], true)
+            .add_table(&[
+                vec![
+                    TableCell::header("File".into()),
+                    TableCell::header("Result".into()),
+                ],
+                vec![
+                    TableCell::new("lib.rs".into()),
+                    TableCell {
+                        colspan: 2,
+                        ..TableCell::new("ok".into())
+                    },
+                ],
+            ])
+            .add_details("Details", "hidden")
+            .add_image(
+                "https://example.com/a.png",
+                "chart",
+                &ImageOptions {
+                    width: Some(32),
+                    height: None,
+                },
+            )
+            .add_separator()
+            .add_quote("quote", Some("https://example.com"))
+            .add_link("docs", "https://example.com/docs");
+        sim_assert_eq!(
+            summary.stringify(),
+            [
+                "<h2>Results</h2>",
+                r#"<pre lang="sh"><code>cargo test</code></pre>"#,
+                "<ol><li>a</li><li>b</li></ol>",
+                r#"<table><tr><th>File</th><th>Result</th></tr><tr><td>lib.rs</td><td colspan="2">ok</td></tr></table>"#,
+                "<details><summary>Details</summary>hidden</details>",
+                r#"<img src="https://example.com/a.png" alt="chart" width="32">"#,
+                "<hr>",
+                r#"<blockquote cite="https://example.com">quote</blockquote>"#,
+                r#"<a href="https://example.com/docs">docs</a>"#,
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_write() -> color_eyre::eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("summary.md");
+        let env = EnvMap::from_iter([("GITHUB_STEP_SUMMARY", path.as_os_str())]);
+        let mut summary = Summary::new();
+        summary.add_raw("# Hello", true).write(&env, false)?;
+        summary.add_raw("world", true).write(&env, false)?;
+        sim_assert_eq!(std::fs::read_to_string(&path)?, "# Hello\nworld\n");
+        assert!(summary.is_empty_buffer());
+
+        summary.add_raw("replaced", false).write(&env, true)?;
+        sim_assert_eq!(std::fs::read_to_string(&path)?, "replaced");
+        summary.clear(&env)?;
+        sim_assert_eq!(std::fs::read_to_string(&path)?, "");
+        assert!(summary.write(&EnvMap::default(), false).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,515 @@
+//! Hermetic simulation of a runner for testing actions.
+//!
+//! A [`Runner`] creates a temporary workspace with the files used by file commands,
+//! runs an action function or binary against it and collects everything the action
+//! reported into an [`Outcome`].
+
+use crate::{AnnotationProperties, Command, LogLevel, env::EnvMap, input, utils};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+thread_local! {
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Captures a command issued on this thread while a [`Runner`] is running a function.
+///
+/// Returns `false` if the command is not captured.
+pub(crate) fn capture(cmd: &Command) -> bool {
+    CAPTURED.with_borrow_mut(|captured| match captured {
+        Some(captured) => {
+            captured.push(cmd.to_string());
+            true
+        }
+        None => false,
+    })
+}
+
+/// An annotation reported by an action.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Annotation {
+    pub level: LogLevel,
+    pub message: String,
+    pub properties: AnnotationProperties,
+}
+
+/// Everything an action reported to the runner.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// Exit code of the action.
+    pub exit_code: i32,
+    /// Outputs set by the action.
+    pub outputs: HashMap<String, String>,
+    /// Environment variables exported by the action.
+    pub env: HashMap<String, String>,
+    /// Paths added to `PATH` by the action, in the order they were added.
+    pub paths: Vec<PathBuf>,
+    /// State saved by the action.
+    pub state: HashMap<String, String>,
+    /// Error, warning and notice annotations.
+    pub annotations: Vec<Annotation>,
+    /// Debug messages.
+    pub debug: Vec<String>,
+    /// Secrets registered to be masked.
+    pub secrets: Vec<String>,
+    /// Markdown written to the job summary.
+    pub summary: String,
+    /// Lines written to stdout that are not workflow commands.
+    pub stdout: Vec<String>,
+}
+
+impl Outcome {
+    /// Whether the action exited successfully.
+    #[must_use]
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+
+    fn parse_stdout<'a>(&mut self, lines: impl IntoIterator<Item = &'a str>) {
+        for line in lines {
+            match parse_command(line) {
+                Some((command, props, message)) => self.apply_command(&command, props, message),
+                None => self.stdout.push(line.to_string()),
+            }
+        }
+    }
+
+    fn apply_command(
+        &mut self,
+        command: &str,
+        mut props: HashMap<String, String>,
+        message: String,
+    ) {
+        let level = match command {
+            "set-output" => {
+                self.outputs
+                    .insert(props.remove("name").unwrap_or_default(), message);
+                return;
+            }
+            "set-env" => {
+                self.env
+                    .insert(props.remove("name").unwrap_or_default(), message);
+                return;
+            }
+            "save-state" => {
+                self.state
+                    .insert(props.remove("name").unwrap_or_default(), message);
+                return;
+            }
+            "add-path" => {
+                self.paths.push(message.into());
+                return;
+            }
+            "add-mask" => {
+                self.secrets.push(message);
+                return;
+            }
+            "debug" => {
+                self.debug.push(message);
+                return;
+            }
+            "error" => LogLevel::Error,
+            "warning" => LogLevel::Warning,
+            "notice" => LogLevel::Notice,
+            _ => return,
+        };
+        let parse_usize = |key: &str| props.get(key).and_then(|value| value.parse().ok());
+        let properties = AnnotationProperties {
+            title: props.get("title").cloned(),
+            file: props.get("file").cloned(),
+            start_line: parse_usize("line"),
+            end_line: parse_usize("endLine"),
+            start_column: parse_usize("col"),
+            end_column: parse_usize("endColumn"),
+        };
+        self.annotations.push(Annotation {
+            level,
+            message,
+            properties,
+        });
+    }
+}
+
+/// Parses a workflow command of the form `::command key=value,...::message`.
+fn parse_command(line: &str) -> Option<(String, HashMap<String, String>, String)> {
+    let line = line.strip_prefix("::")?;
+    let (head, message) = line.split_once("::")?;
+    let (command, props) = head.split_once(' ').unwrap_or((head, ""));
+    let props = props
+        .split(',')
+        .filter_map(|prop| prop.split_once('='))
+        .map(|(key, value)| (key.to_string(), utils::unescape_property(value)))
+        .collect();
+    Some((command.to_string(), props, utils::unescape_data(message)))
+}
+
+/// Parses the key value pairs written to a file command file.
+///
+/// Supports both the `name<<delimiter` heredoc and the `name=value` format,
+/// a `<<` after the first `=` is part of the value.
+fn parse_file_command(content: &str) -> Vec<(String, String)> {
+    let mut values = Vec::new();
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        let heredoc = line
+            .split_once("<<")
+            .filter(|(name, _)| !name.contains('='));
+        if let Some((name, delimiter)) = heredoc {
+            let value: Vec<&str> = lines
+                .by_ref()
+                .take_while(|line| *line != delimiter)
+                .collect();
+            values.push((name.to_string(), value.join("\n")));
+        } else if let Some((name, value)) = line.split_once('=') {
+            values.push((name.to_string(), value.to_string()));
+        }
+    }
+    values
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("failed to set up runner workspace")]
+    Setup(#[source] std::io::Error),
+    #[error("failed to spawn action")]
+    Spawn(#[source] std::io::Error),
+    #[error("failed to read file command file {path:?}")]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// A simulated runner with a temporary workspace.
+#[derive(Debug)]
+pub struct Runner {
+    dir: tempfile::TempDir,
+    vars: HashMap<OsString, OsString>,
+}
+
+impl Runner {
+    /// Creates a new runner with an empty temporary workspace.
+    ///
+    /// # Errors
+    /// If the workspace cannot be created.
+    pub fn new() -> Result<Self, Error> {
+        let dir = tempfile::tempdir().map_err(Error::Setup)?;
+        let workspace = dir.path().join("workspace");
+        let temp = dir.path().join("temp");
+        let commands = dir.path().join("commands");
+        for path in [&workspace, &temp, &commands] {
+            std::fs::create_dir_all(path).map_err(Error::Setup)?;
+        }
+
+        let mut vars: HashMap<OsString, OsString> = [
+            ("CI", OsString::from("true")),
+            ("GITHUB_ACTIONS", "true".into()),
+            ("GITHUB_WORKSPACE", workspace.into()),
+            ("RUNNER_TEMP", temp.into()),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.into(), value))
+        .collect();
+
+        for command in ["OUTPUT", "ENV", "STATE", "PATH", "STEP_SUMMARY"] {
+            let path = commands.join(command.to_ascii_lowercase());
+            std::fs::write(&path, "").map_err(Error::Setup)?;
+            vars.insert(format!("GITHUB_{command}").into(), path.into());
+        }
+        Ok(Self { dir, vars })
+    }
+
+    /// Path of the workspace (`GITHUB_WORKSPACE`).
+    #[must_use]
+    pub fn workspace(&self) -> PathBuf {
+        self.dir.path().join("workspace")
+    }
+
+    /// Sets an input.
+    #[must_use]
+    pub fn input(mut self, name: impl AsRef<OsStr>, value: impl Into<OsString>) -> Self {
+        self.vars.insert(input::env_var_name(name), value.into());
+        self
+    }
+
+    /// Sets state saved by a previous run of the action.
+    #[must_use]
+    pub fn state(mut self, name: impl AsRef<str>, value: impl Into<OsString>) -> Self {
+        self.vars
+            .insert(format!("STATE_{}", name.as_ref()).into(), value.into());
+        self
+    }
+
+    /// Sets an environment variable.
+    #[must_use]
+    pub fn env(mut self, key: impl Into<OsString>, value: impl Into<OsString>) -> Self {
+        self.vars.insert(key.into(), value.into());
+        self
+    }
+
+    /// Runs an action function.
+    ///
+    /// The function receives the environment of the runner.
+    /// Commands it issues on the current thread are captured.
+    /// An error returned by the function is reported as an error annotation
+    /// and results in an exit code of 1.
+    ///
+    /// Capturing is limited to what the function does on the calling thread:
+    /// - commands issued from other threads or from tasks of a multi-threaded
+    ///   async runtime are printed to stdout instead of being captured,
+    /// - plain output such as [`info!`](crate::info) is printed and not
+    ///   collected into [`Outcome::stdout`],
+    /// - [`fail`](crate::fail) exits the process.
+    ///
+    /// Use [`Runner::run_command`] or [`Runner::run_binary`] to test these.
+    ///
+    /// # Errors
+    /// If the file command files cannot be read.
+    pub fn run<F, E>(&self, action: F) -> Result<Outcome, Error>
+    where
+        F: FnOnce(&EnvMap) -> Result<(), E>,
+        E: std::fmt::Display,
+    {
+        let mut vars = self.vars.clone();
+        if let Some(path) = std::env::var_os("PATH") {
+            vars.entry("PATH".into()).or_insert(path);
+        }
+        let env = EnvMap::new(vars);
+
+        let previous = CAPTURED.replace(Some(Vec::new()));
+        let result = action(&env);
+        let captured = CAPTURED.replace(previous).unwrap_or_default();
+
+        let mut outcome = Outcome::default();
+        outcome.parse_stdout(captured.iter().map(String::as_str));
+        if let Err(err) = result {
+            outcome.exit_code = crate::ExitCode::Failure as i32;
+            outcome.annotations.push(Annotation {
+                level: LogLevel::Error,
+                message: err.to_string(),
+                properties: AnnotationProperties::default(),
+            });
+        }
+        self.read_file_commands(&mut outcome)?;
+        Ok(outcome)
+    }
+
+    /// Runs an action binary.
+    ///
+    /// The command inherits the environment of the current process,
+    /// except for inputs and state, and runs in the workspace.
+    ///
+    /// # Errors
+    /// If the command cannot be spawned or the file command files cannot be read.
+    pub fn run_command(&self, mut command: std::process::Command) -> Result<Outcome, Error> {
+        for (key, _) in std::env::vars_os() {
+            let key_str = key.to_string_lossy();
+            if key_str.starts_with("INPUT_") || key_str.starts_with("STATE_") {
+                command.env_remove(&key);
+            }
+        }
+        let output = command
+            .envs(&self.vars)
+            .current_dir(self.workspace())
+            .output()
+            .map_err(Error::Spawn)?;
+
+        let mut outcome = Outcome {
+            exit_code: output.status.code().unwrap_or(-1),
+            ..Outcome::default()
+        };
+        outcome.parse_stdout(String::from_utf8_lossy(&output.stdout).lines());
+        self.read_file_commands(&mut outcome)?;
+        Ok(outcome)
+    }
+
+    /// Runs an action binary at `path` with arguments.
+    ///
+    /// # Errors
+    /// If the binary cannot be spawned or the file command files cannot be read.
+    pub fn run_binary(
+        &self,
+        path: impl AsRef<Path>,
+        args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+    ) -> Result<Outcome, Error> {
+        let mut command = std::process::Command::new(path.as_ref());
+        command.args(args);
+        self.run_command(command)
+    }
+
+    fn read_file_command(&self, command: &str) -> Result<String, Error> {
+        let path = self.dir.path().join("commands").join(command);
+        std::fs::read_to_string(&path).map_err(|source| Error::Read { path, source })
+    }
+
+    fn read_file_commands(&self, outcome: &mut Outcome) -> Result<(), Error> {
+        outcome
+            .outputs
+            .extend(parse_file_command(&self.read_file_command("output")?));
+        outcome
+            .env
+            .extend(parse_file_command(&self.read_file_command("env")?));
+        outcome
+            .state
+            .extend(parse_file_command(&self.read_file_command("state")?));
+        outcome.paths.extend(
+            self.read_file_command("path")?
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(PathBuf::from),
+        );
+        outcome.summary = self.read_file_command("step_summary")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Annotation, Runner, parse_command, parse_file_command};
+    use crate::{AnnotationProperties, LogLevel, input::ParseInput};
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_run_function() -> color_eyre::eyre::Result<()> {
+        let runner = Runner::new()?.input("name", "world").state("pid", "42");
+        let outcome = runner.run(|env| {
+            let name: String = env.parse_input("name")?.unwrap_or_default();
+            crate::set_output(env, "greeting", format!("hello\n{name}"))?;
+            crate::export_var(env, "GREETING", "hello")?;
+            crate::save_state(
+                env,
+                "pid",
+                crate::get_state_with(env, "pid").unwrap_or_default(),
+            )?;
+            crate::add_path(env, "/opt/tool/bin")?;
+            crate::set_secret("secret");
+            crate::warning!("careful, {name}");
+            crate::issue_level(
+                LogLevel::Error,
+                "failed: 50%",
+                Some(AnnotationProperties {
+                    file: Some("src/lib.rs".into()),
+                    start_line: Some(3),
+                    ..AnnotationProperties::default()
+                }),
+            );
+            Ok::<_, color_eyre::eyre::Report>(())
+        })?;
+
+        assert!(outcome.success());
+        sim_assert_eq!(
+            outcome.outputs,
+            HashMap::from_iter([("greeting".to_string(), "hello\nworld".to_string())])
+        );
+        sim_assert_eq!(
+            outcome.env,
+            HashMap::from_iter([("GREETING".to_string(), "hello".to_string())])
+        );
+        sim_assert_eq!(
+            outcome.state,
+            HashMap::from_iter([("pid".to_string(), "42".to_string())])
+        );
+        sim_assert_eq!(outcome.paths, vec![PathBuf::from("/opt/tool/bin")]);
+        sim_assert_eq!(outcome.secrets, vec!["secret".to_string()]);
+        sim_assert_eq!(
+            outcome.annotations,
+            vec![
+                Annotation {
+                    level: LogLevel::Warning,
+                    message: "careful, world".into(),
+                    properties: AnnotationProperties::default(),
+                },
+                Annotation {
+                    level: LogLevel::Error,
+                    message: "failed: 50%".into(),
+                    properties: AnnotationProperties {
+                        file: Some("src/lib.rs".into()),
+                        start_line: Some(3),
+                        ..AnnotationProperties::default()
+                    },
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_failing_function() -> color_eyre::eyre::Result<()> {
+        let outcome = Runner::new()?.run(|_| Err("boom"))?;
+        sim_assert_eq!(outcome.exit_code, 1);
+        sim_assert_eq!(outcome.annotations[0].message, "boom");
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_command() -> color_eyre::eyre::Result<()> {
+        let runner = Runner::new()?.input("name", "world");
+        let mut command = std::process::Command::new("sh");
+        command.arg("-c").arg(
+            r##"
+            echo "greeting=hello $INPUT_NAME" >> "$GITHUB_OUTPUT"
+            echo "# Summary" >> "$GITHUB_STEP_SUMMARY"
+            echo "::notice title=Note::done"
+            echo "plain output"
+            exit 3
+            "##,
+        );
+        let outcome = runner.run_command(command)?;
+        sim_assert_eq!(outcome.exit_code, 3);
+        sim_assert_eq!(outcome.outputs["greeting"], "hello world");
+        sim_assert_eq!(outcome.summary, "# Summary\n");
+        sim_assert_eq!(outcome.stdout, vec!["plain output".to_string()]);
+        sim_assert_eq!(
+            outcome.annotations,
+            vec![Annotation {
+                level: LogLevel::Notice,
+                message: "done".into(),
+                properties: AnnotationProperties {
+                    title: Some("Note".into()),
+                    ..AnnotationProperties::default()
+                },
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_round_trip() {
+        let command = crate::Command::new(
+            "warning".into(),
+            "100% done\nnext: 50%0A".into(),
+            HashMap::from_iter([("title".to_string(), "a, b: c".to_string())]),
+        );
+        sim_assert_eq!(
+            command.to_string(),
+            "::warning title=a%2C b%3A c::100%25 done%0Anext: 50%250A"
+        );
+        sim_assert_eq!(
+            parse_command(&command.to_string()),
+            Some((
+                "warning".to_string(),
+                HashMap::from_iter([("title".to_string(), "a, b: c".to_string())]),
+                "100% done\nnext: 50%0A".to_string(),
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_file_command() {
+        let content = "expr=a<<b\nmulti<<EOF\nfirst\nsecond\nEOF\nlast=value\n";
+        sim_assert_eq!(
+            parse_file_command(content),
+            vec![
+                ("expr".to_string(), "a<<b".to_string()),
+                ("multi".to_string(), "first\nsecond".to_string()),
+                ("last".to_string(), "value".to_string()),
+            ]
+        );
+    }
+}
@@ -39,6 +39,22 @@ pub fn escape_property(prop: impl AsRef<str>) -> String {
         .replace(',', "%2C")
 }
 
+pub fn unescape_data(data: impl AsRef<str>) -> String {
+    data.as_ref()
+        .replace("%0A", "\n")
+        .replace("%0D", "\r")
+        .replace("%25", "%")
+}
+
+pub fn unescape_property(prop: impl AsRef<str>) -> String {
+    prop.as_ref()
+        .replace("%2C", ",")
+        .replace("%3A", ":")
+        .replace("%0A", "\n")
+        .replace("%0D", "\r")
+        .replace("%25", "%")
+}
+
 /// Filters empty values.
 ///
 /// # Errors
@@ -0,0 +1,566 @@
+use crate::InputField;
+use crate::manifest::{Branding, Docker, Input, JavaScript, Manifest, Output, Runs};
+use indexmap::IndexMap;
+use proc_macro2::Span;
+use std::path::{Path, PathBuf};
+use syn::spanned::Spanned;
+
+/// Environment variable that enables the check mode for all generated manifests.
+pub const CHECK_ENV_VAR: &str = "ACTION_DERIVE_CHECK";
+
+/// Environment variable that enables writing all generated manifests that are out of date.
+pub const WRITE_ENV_VAR: &str = "ACTION_DERIVE_WRITE";
+
+/// What happens to the file of a generated manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Only embed the manifest, the file is neither read nor written.
+    Embed,
+    /// Fail if the file is out of date.
+    Check,
+    /// Write the file if it is out of date.
+    Write,
+}
+
+impl Mode {
+    /// Mode set by the `check` attribute and the environment, where writing takes precedence.
+    pub fn from_env(check: bool) -> Self {
+        let env = |key: &str| std::env::var(key).ok();
+        Self::new(
+            check,
+            env(CHECK_ENV_VAR).as_deref(),
+            env(WRITE_ENV_VAR).as_deref(),
+        )
+    }
+
+    fn new(check: bool, check_env: Option<&str>, write_env: Option<&str>) -> Self {
+        let enabled =
+            |value: Option<&str>| value.is_some_and(|value| !matches!(value, "" | "0" | "false"));
+        if enabled(write_env) {
+            Self::Write
+        } else if check || enabled(check_env) {
+            Self::Check
+        } else {
+            Self::Embed
+        }
+    }
+}
+
+fn error(span: Option<Span>, message: impl std::fmt::Display) -> syn::Error {
+    syn::Error::new(span.unwrap_or_else(Span::call_site), message)
+}
+
+/// Configuration for generating an action manifest from a struct.
+#[derive(Default, Debug)]
+pub struct Generate {
+    pub path: PathBuf,
+    /// Span of the `generate = "PATH"` attribute, for errors about the file.
+    pub span: Option<Span>,
+    pub check: bool,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub branding: Option<Branding>,
+    pub runs: Option<RunsAttribute>,
+    pub outputs: IndexMap<String, Output>,
+}
+
+/// Flat `#[action(runs(...))]` attribute, converted into [`Runs`] depending on `using`.
+#[derive(Default, Debug)]
+pub struct RunsAttribute {
+    /// Span of the `runs(...)` attribute.
+    pub span: Option<Span>,
+    pub using: String,
+    pub main: Option<String>,
+    pub pre: Option<String>,
+    pub pre_if: Option<String>,
+    pub post: Option<String>,
+    pub post_if: Option<String>,
+    pub image: Option<String>,
+    pub pre_entrypoint: Option<String>,
+    pub entrypoint: Option<String>,
+    pub post_entrypoint: Option<String>,
+    pub args: Vec<String>,
+}
+
+impl RunsAttribute {
+    fn into_runs(self) -> syn::Result<Runs> {
+        let missing = |field: &str| {
+            error(
+                self.span,
+                format!("{} actions require `runs({field} = \"...\")`", self.using),
+            )
+        };
+        Ok(match self.using.as_str() {
+            "docker" => Runs::Docker(Docker {
+                image: self.image.ok_or_else(|| missing("image"))?,
+                pre_entrypoint: self.pre_entrypoint,
+                pre_if: self.pre_if,
+                entrypoint: self.entrypoint,
+                post_entrypoint: self.post_entrypoint,
+                post_if: self.post_if,
+                args: self.args,
+                env: IndexMap::new(),
+            }),
+            "composite" => {
+                return Err(error(self.span, "composite actions cannot be generated"));
+            }
+            using => Runs::JavaScript(JavaScript {
+                using: serde_yaml::from_str(using)
+                    .map_err(|_| error(self.span, format!("unknown runtime {using:?}")))?,
+                main: self.main.ok_or_else(|| missing("main"))?,
+                pre: self.pre,
+                pre_if: self.pre_if,
+                post: self.post,
+                post_if: self.post_if,
+            }),
+        })
+    }
+}
+
+fn parse_string(meta: &syn::meta::ParseNestedMeta) -> syn::Result<String> {
+    let value: syn::LitStr = meta.value()?.parse()?;
+    Ok(value.value())
+}
+
+impl Generate {
+    /// Parses a `#[action(generate = "PATH", ...)]` attribute into this configuration.
+    pub fn parse_attribute(&mut self, meta: &syn::meta::ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("generate") {
+            let path: syn::LitStr = meta.value()?.parse()?;
+            if path.value().is_empty() {
+                return Err(syn::Error::new(
+                    path.span(),
+                    "the path of the generated action manifest must not be empty",
+                ));
+            }
+            self.path = PathBuf::from(path.value());
+            self.span = Some(path.span());
+        } else if meta.path.is_ident("check") {
+            self.check = true;
+        } else if meta.path.is_ident("name") {
+            self.name = Some(parse_string(meta)?);
+        } else if meta.path.is_ident("description") {
+            self.description = Some(parse_string(meta)?);
+        } else if meta.path.is_ident("author") {
+            self.author = Some(parse_string(meta)?);
+        } else if meta.path.is_ident("branding") {
+            let branding = self.branding.get_or_insert_with(Branding::default);
+            meta.parse_nested_meta(|meta| {
+                if meta.path.is_ident("icon") {
+                    branding.icon = Some(parse_string(&meta)?);
+                } else if meta.path.is_ident("color") {
+                    branding.color = Some(parse_string(&meta)?);
+                } else {
+                    return Err(meta.error("unsupported branding attribute"));
+                }
+                Ok(())
+            })?;
+        } else if meta.path.is_ident("runs") {
+            let runs = self.runs.get_or_insert_with(RunsAttribute::default);
+            runs.span = Some(meta.path.span());
+            meta.parse_nested_meta(|meta| {
+                if meta.path.is_ident("using") {
+                    runs.using = parse_string(&meta)?;
+                } else if meta.path.is_ident("main") {
+                    runs.main = Some(parse_string(&meta)?);
+                } else if meta.path.is_ident("pre") {
+                    runs.pre = Some(parse_string(&meta)?);
+                } else if meta.path.is_ident("pre_if") {
+                    runs.pre_if = Some(parse_string(&meta)?);
+                } else if meta.path.is_ident("post") {
+                    runs.post = Some(parse_string(&meta)?);
+                } else if meta.path.is_ident("post_if") {
+                    runs.post_if = Some(parse_string(&meta)?);
+                } else if meta.path.is_ident("image") {
+                    runs.image = Some(parse_string(&meta)?);
+                } else if meta.path.is_ident("pre_entrypoint") {
+                    runs.pre_entrypoint = Some(parse_string(&meta)?);
+                } else if meta.path.is_ident("entrypoint") {
+                    runs.entrypoint = Some(parse_string(&meta)?);
+                } else if meta.path.is_ident("post_entrypoint") {
+                    runs.post_entrypoint = Some(parse_string(&meta)?);
+                } else if meta.path.is_ident("arg") {
+                    runs.args.push(parse_string(&meta)?);
+                } else {
+                    return Err(meta.error("unsupported runs attribute"));
+                }
+                Ok(())
+            })?;
+        } else if meta.path.is_ident("output") {
+            let mut name = None;
+            let mut output = Output::default();
+            meta.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    name = Some(parse_string(&meta)?);
+                } else if meta.path.is_ident("description") {
+                    output.description = Some(parse_string(&meta)?);
+                } else if meta.path.is_ident("value") {
+                    output.value = Some(parse_string(&meta)?);
+                } else {
+                    return Err(meta.error("unsupported output attribute"));
+                }
+                Ok(())
+            })?;
+            let name = name.ok_or_else(|| meta.error("output requires a name"))?;
+            self.outputs.insert(name, output);
+        } else {
+            return Err(meta.error("unsupported action attribute"));
+        }
+        Ok(())
+    }
+
+    /// Builds the manifest of a struct with input fields.
+    ///
+    /// Missing metadata falls back to the doc comment of the struct and the cargo package.
+    ///
+    /// # Errors
+    /// If the `runs` attribute is incomplete.
+    pub fn manifest(
+        self,
+        attrs: &[syn::Attribute],
+        fields: &[InputField<'_>],
+    ) -> syn::Result<Manifest> {
+        let package_env = |key: &str| std::env::var(key).ok().filter(|value| !value.is_empty());
+
+        let inputs = fields
+            .iter()
+            .filter(|field| !field.skip)
+            .map(|field| {
+                let input = Input {
+                    description: field.description.clone(),
+                    deprecation_message: field.deprecation_message.clone(),
+                    default: field.default.clone(),
+                    required: field.is_required().then_some(true),
+                };
+                (field.input_name(), input)
+            })
+            .collect();
+
+        Ok(Manifest {
+            name: self.name.or_else(|| package_env("CARGO_PKG_NAME")),
+            description: self
+                .description
+                .or_else(|| doc_comment(attrs))
+                .or_else(|| package_env("CARGO_PKG_DESCRIPTION")),
+            author: self.author.or_else(|| {
+                package_env("CARGO_PKG_AUTHORS").map(|authors| authors.replace(':', ", "))
+            }),
+            branding: self.branding,
+            inputs,
+            outputs: self.outputs,
+            runs: self.runs.map(RunsAttribute::into_runs).transpose()?,
+        })
+    }
+}
+
+/// Collects the doc comment of an item.
+pub fn doc_comment(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(s),
+                        ..
+                    }),
+                ..
+            }) => Some(s.value()),
+            _ => None,
+        })
+        .map(|line| {
+            line.strip_prefix(' ')
+                .unwrap_or(&line)
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+    let doc = lines.join("\n").trim().to_string();
+    if doc.is_empty() { None } else { Some(doc) }
+}
+
+/// Validates the manifest and checks or writes the file at `path` depending on `mode`.
+///
+/// The file is only written if it is out of date,
+/// errors are reported at `span`, the `generate` attribute.
+pub fn sync(path: &Path, manifest: &Manifest, mode: Mode, span: Option<Span>) -> syn::Result<()> {
+    crate::manifest::validate(manifest)
+        .map_err(|err| error(span, format!("invalid action manifest: {err}")))?;
+    if mode == Mode::Embed {
+        return Ok(());
+    }
+    let content = crate::manifest::to_action_yml(manifest);
+    let current = std::fs::read_to_string(path).ok();
+    if current.as_deref() == Some(content.as_str()) {
+        return Ok(());
+    }
+    if mode == Mode::Check {
+        return Err(error(
+            span,
+            format!(
+                "{} is out of date, rebuild with {WRITE_ENV_VAR}=1 to regenerate it",
+                path.display()
+            ),
+        ));
+    }
+    std::fs::write(path, content)
+        .map_err(|err| error(span, format!("failed to write {}: {err}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mode, sync};
+    use crate::{Source, parse_derive, parse_input_fields};
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    /// Generates the action manifest of a struct deriving `Action`.
+    fn generate(ast: &syn::DeriveInput) -> syn::Result<String> {
+        let (_, _, source, _) = parse_derive(ast)?;
+        let Source::Generate(generate) = source else {
+            panic!("expected #[action(generate = \"...\")]");
+        };
+        let fields = parse_input_fields(ast)?;
+        let manifest = generate.manifest(&ast.attrs, &fields)?;
+        Ok(crate::manifest::to_yaml(&manifest))
+    }
+
+    #[test]
+    fn test_generate() -> color_eyre::eyre::Result<()> {
+        let ast: syn::DeriveInput = syn::parse_quote! {
+            /// Publish crates
+            ///
+            /// on crates.io
+            #[action(generate = "action.yml", name = "publish", author = "octocat")]
+            #[action(branding(icon = "box", color = "orange"))]
+            #[action(runs(using = "node20", main = "dist/index.js", post = "dist/post.js"))]
+            #[action(output(name = "version", description = "Version that was published"))]
+            struct Publish {
+                /// Github API token
+                #[input(default = "${{ github.token }}")]
+                token: String,
+                /// Additional arguments
+                /// for cargo publish
+                #[input(name = "args")]
+                extra_args: Option<String>,
+                #[input(deprecation_message = "use `args: --dry-run` instead")]
+                dry_run: bool,
+                #[input(skip)]
+                attempts: usize,
+            }
+        };
+        sim_assert_eq!(
+            generate(&ast)?,
+            indoc(
+                r"
+                name: publish
+                description: |-
+                  Publish crates
+
+                  on crates.io
+                author: octocat
+                branding:
+                  icon: box
+                  color: orange
+                inputs:
+                  token:
+                    description: Github API token
+                    default: ${{ github.token }}
+                  args:
+                    description: |-
+                      Additional arguments
+                      for cargo publish
+                  dry-run:
+                    deprecationMessage: 'use `args: --dry-run` instead'
+                    required: true
+                outputs:
+                  version:
+                    description: Version that was published
+                runs:
+                  using: node20
+                  main: dist/index.js
+                  post: dist/post.js
+                "
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_docker() -> color_eyre::eyre::Result<()> {
+        let ast: syn::DeriveInput = syn::parse_quote! {
+            #[action(generate = "action.yml", name = "lint", description = "Lint the code")]
+            #[action(author = "octocat")]
+            #[action(runs(using = "docker", image = "Dockerfile", arg = "--fix", arg = "."))]
+            struct Lint {}
+        };
+        sim_assert_eq!(
+            generate(&ast)?,
+            indoc(
+                r"
+                name: lint
+                description: Lint the code
+                author: octocat
+                runs:
+                  using: docker
+                  image: Dockerfile
+                  args:
+                  - --fix
+                  - .
+                "
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_attributes() {
+        let error = |ast: syn::DeriveInput| generate(&ast).unwrap_err().to_string();
+        sim_assert_eq!(
+            error(syn::parse_quote! {
+                #[action(generate = "action.yml", runs(using = "node20", entry = "index.js"))]
+                struct Action {}
+            }),
+            "unsupported runs attribute"
+        );
+        sim_assert_eq!(
+            error(syn::parse_quote! {
+                #[action(generate = "action.yml", output(description = "Version"))]
+                struct Action {}
+            }),
+            "output requires a name"
+        );
+        sim_assert_eq!(
+            error(syn::parse_quote! {
+                #[action(generate = "action.yml")]
+                struct Action {
+                    #[input(required)]
+                    token: Option<String>,
+                }
+            }),
+            "`#[input(required)]` cannot be used on an `Option`, use the inner type instead"
+        );
+        sim_assert_eq!(
+            error(syn::parse_quote! {
+                #[action(generate = "action.yml", runs(using = "node20"))]
+                struct Action {}
+            }),
+            "node20 actions require `runs(main = \"...\")`"
+        );
+        sim_assert_eq!(
+            error(syn::parse_quote! {
+                #[action(generate = "action.yml", runs(using = "docker", main = "index.js"))]
+                struct Action {}
+            }),
+            "docker actions require `runs(image = \"...\")`"
+        );
+        sim_assert_eq!(
+            error(syn::parse_quote! {
+                #[action(generate = "action.yml", runs(using = "composite"))]
+                struct Action {}
+            }),
+            "composite actions cannot be generated"
+        );
+        sim_assert_eq!(
+            error(syn::parse_quote! {
+                #[action(generate = "action.yml", runs(using = "node8", main = "index.js"))]
+                struct Action {}
+            }),
+            "unknown runtime \"node8\""
+        );
+        sim_assert_eq!(
+            error(syn::parse_quote! {
+                #[action(generate = "")]
+                struct Action {}
+            }),
+            "the path of the generated action manifest must not be empty"
+        );
+        assert!(
+            error(syn::parse_quote! {
+                #[action(name = "lint")]
+                struct Action {}
+            })
+            .starts_with(
+                "the path of the generated action manifest (action.yml) needs to be provided"
+            )
+        );
+        sim_assert_eq!(
+            error(syn::parse_quote! {
+                #[action = 42]
+                struct Action {}
+            }),
+            "action attribute must be a literal string"
+        );
+        assert!(
+            error(syn::parse_quote! {
+                #[action]
+                struct Action {}
+            })
+            .starts_with("action attribute must be of the form")
+        );
+        assert!(
+            error(syn::parse_quote! {
+                struct Action {}
+            })
+            .starts_with("a path to an action manifest (action.yml) file needs to be provided")
+        );
+    }
+
+    #[test]
+    fn test_check_mode() -> color_eyre::eyre::Result<()> {
+        let ast: syn::DeriveInput = syn::parse_quote! {
+            #[action(generate = "action.yml", name = "lint", description = "Lint the code")]
+            #[action(author = "octocat", runs(using = "node20", main = "index.js"))]
+            struct Lint {}
+        };
+        let (_, _, Source::Generate(generate), _) = parse_derive(&ast)? else {
+            panic!("expected #[action(generate = \"...\")]");
+        };
+        let manifest = generate.manifest(&ast.attrs, &[])?;
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("action.yml");
+
+        // by default, the file is neither read nor written
+        sync(&path, &manifest, Mode::Embed, None)?;
+        assert!(!path.exists());
+
+        let err = sync(&path, &manifest, Mode::Check, None).unwrap_err();
+        assert!(
+            err.to_string()
+                .ends_with("is out of date, rebuild with ACTION_DERIVE_WRITE=1 to regenerate it")
+        );
+        assert!(!path.exists());
+
+        sync(&path, &manifest, Mode::Write, None)?;
+        let written = std::fs::read_to_string(&path)?;
+        assert!(written.starts_with("# This file is generated by `#[derive(Action)]`"));
+        sync(&path, &manifest, Mode::Check, None)?;
+
+        std::fs::write(&path, written.replace("Lint the code", "Edited"))?;
+        assert!(sync(&path, &manifest, Mode::Check, None).is_err());
+        sync(&path, &manifest, Mode::Embed, None)?;
+        assert!(std::fs::read_to_string(&path)?.contains("Edited"));
+
+        sim_assert_eq!(Mode::new(false, None, None), Mode::Embed);
+        sim_assert_eq!(Mode::new(false, Some(""), Some("0")), Mode::Embed);
+        sim_assert_eq!(Mode::new(false, Some("false"), Some("false")), Mode::Embed);
+        sim_assert_eq!(Mode::new(false, Some("1"), None), Mode::Check);
+        sim_assert_eq!(Mode::new(true, None, Some("")), Mode::Check);
+        sim_assert_eq!(Mode::new(true, Some("true"), Some("1")), Mode::Write);
+        Ok(())
+    }
+
+    /// Removes the indentation of the first line from all lines.
+    fn indoc(text: &str) -> String {
+        let text = text.trim_start_matches('\n');
+        let indent = text.len() - text.trim_start().len();
+        text.lines()
+            .map(|line| line.get(indent..).unwrap_or("").trim_end())
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim_end()
+            .to_string()
+            + "\n"
+    }
+}
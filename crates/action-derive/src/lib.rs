@@ -1,5 +1,6 @@
 #![allow(clippy::missing_panics_doc)]
 
+mod generate;
 mod ident;
 mod manifest;
 
@@ -8,8 +9,12 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use std::path::{Path, PathBuf};
 
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".into()))
+}
+
 fn resolve_path(path: impl AsRef<Path>) -> PathBuf {
-    let root = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".into()));
+    let root = manifest_dir();
     if root.join(path.as_ref()).exists() {
         root.join(path.as_ref())
     } else {
@@ -25,45 +30,246 @@ fn quote_option<T: quote::ToTokens>(value: Option<&T>) -> TokenStream {
     }
 }
 
-fn get_attribute(attr: &syn::Attribute) -> String {
-    match &attr.meta {
-        syn::Meta::NameValue(syn::MetaNameValue { path, value, .. }) => {
-            debug_assert!(path.is_ident("action"));
-            match value {
-                syn::Expr::Lit(syn::ExprLit {
-                    lit: syn::Lit::Str(s),
-                    ..
-                }) => s.value(),
-                _ => panic!("action attribute must be a literal string"),
+/// Source of the action manifest.
+enum Source {
+    /// Read an existing action manifest (`#[action = "PATH"]`).
+    Read(PathBuf, proc_macro2::Span),
+    /// Generate the action manifest from the struct (`#[action(generate = "PATH", ...)]`).
+    Generate(Box<generate::Generate>),
+}
+
+/// Decoding of an input into a struct field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decode {
+    Parse,
+    Json,
+    Yaml,
+}
+
+/// A struct field that is populated from an input.
+struct InputField<'a> {
+    ident: &'a syn::Ident,
+    ty: &'a syn::Type,
+    name: Option<String>,
+    decode: Decode,
+    description: Option<String>,
+    default: Option<String>,
+    deprecation_message: Option<String>,
+    required: bool,
+    /// Not populated from an input (`#[input(skip)]`), but with [`Default::default`].
+    skip: bool,
+}
+
+impl InputField<'_> {
+    /// Name of the input when generating the manifest.
+    fn input_name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| self.ident.to_string().replace('_', "-"))
+    }
+
+    /// Whether the input is required when generating the manifest.
+    fn is_required(&self) -> bool {
+        self.required || (self.default.is_none() && option_inner_type(self.ty).is_none())
+    }
+}
+
+fn parse_input_field(field: &syn::Field) -> syn::Result<InputField<'_>> {
+    let mut input_field = InputField {
+        ident: field
+            .ident
+            .as_ref()
+            .ok_or_else(|| syn::Error::new_spanned(field, "input fields must be named fields"))?,
+        ty: &field.ty,
+        name: None,
+        decode: Decode::Parse,
+        description: generate::doc_comment(&field.attrs),
+        default: None,
+        deprecation_message: None,
+        required: false,
+        skip: false,
+    };
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("input"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("json") {
+                input_field.decode = Decode::Json;
+            } else if meta.path.is_ident("yaml") {
+                input_field.decode = Decode::Yaml;
+            } else if meta.path.is_ident("name") {
+                let name: syn::LitStr = meta.value()?.parse()?;
+                input_field.name = Some(name.value());
+            } else if meta.path.is_ident("default") {
+                let default: syn::LitStr = meta.value()?.parse()?;
+                input_field.default = Some(default.value());
+            } else if meta.path.is_ident("deprecation_message") {
+                let message: syn::LitStr = meta.value()?.parse()?;
+                input_field.deprecation_message = Some(message.value());
+            } else if meta.path.is_ident("required") {
+                input_field.required = true;
+            } else if meta.path.is_ident("skip") {
+                input_field.skip = true;
+            } else {
+                return Err(meta.error("unsupported input attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    if input_field.required && option_inner_type(input_field.ty).is_some() {
+        return Err(syn::Error::new_spanned(
+            input_field.ty,
+            "`#[input(required)]` cannot be used on an `Option`, use the inner type instead",
+        ));
+    }
+    Ok(input_field)
+}
+
+fn parse_input_fields(ast: &syn::DeriveInput) -> syn::Result<Vec<InputField<'_>>> {
+    match &ast.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => fields.named.iter().map(parse_input_field).collect(),
+        _ => Ok(vec![]),
+    }
+}
+
+/// Returns the type wrapped in `Option<T>`, if any.
+fn option_inner_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(syn::TypePath { qself: None, path }) = ty else {
+        return None;
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => {
+            match args.args.first()? {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
             }
         }
-        _ => panic!(r#"action attribute must be of the form `action = "..."`"#),
+        _ => None,
     }
 }
 
-fn parse_derive(ast: &syn::DeriveInput) -> (&syn::Ident, &syn::Generics, PathBuf) {
+/// Parses the `#[action]` attributes into the source of the manifest
+/// and whether the struct is loaded from its inputs (`#[action(load)]`).
+fn parse_derive(
+    ast: &syn::DeriveInput,
+) -> syn::Result<(&syn::Ident, &syn::Generics, Source, bool)> {
     let name = &ast.ident;
     let generics = &ast.generics;
 
-    let manifests: Vec<_> = ast
+    let mut manifest = None;
+    let mut load = false;
+    let mut generate: Option<(generate::Generate, &syn::Attribute)> = None;
+    for attr in ast
         .attrs
         .iter()
         .filter(|attr| attr.path().is_ident("action"))
-        .map(get_attribute)
-        .map(resolve_path)
-        .collect();
+    {
+        match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(s),
+                        ..
+                    }),
+                ..
+            }) => manifest = Some((resolve_path(s.value()), s.span())),
+            syn::Meta::NameValue(meta) => {
+                return Err(syn::Error::new_spanned(
+                    &meta.value,
+                    "action attribute must be a literal string",
+                ));
+            }
+            syn::Meta::List(_) => {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("load") {
+                        load = true;
+                        return Ok(());
+                    }
+                    let (generate, _) =
+                        generate.get_or_insert_with(|| (generate::Generate::default(), attr));
+                    generate.parse_attribute(&meta)
+                })?;
+            }
+            syn::Meta::Path(_) => {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    r#"action attribute must be of the form `action = "..."` or `action(generate = "...")`"#,
+                ));
+            }
+        }
+    }
 
-    let manifest = manifests.into_iter().next().expect("a path to an action manifest (action.yml) file needs to be provided with the #[action = \"PATH\"] attribute");
-    (name, generics, manifest)
+    let source = match (manifest, generate) {
+        (Some(_), Some((_, attr))) => {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "an action manifest cannot be both read and generated",
+            ));
+        }
+        (Some((path, span)), None) => Source::Read(path, span),
+        (None, Some((generate, attr))) => {
+            if generate.path.as_os_str().is_empty() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "the path of the generated action manifest (action.yml) needs to be provided with the #[action(generate = \"PATH\")] attribute",
+                ));
+            }
+            Source::Generate(Box::new(generate))
+        }
+        (None, None) => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "a path to an action manifest (action.yml) file needs to be provided with the #[action = \"PATH\"] or #[action(generate = \"PATH\")] attribute",
+            ));
+        }
+    };
+    Ok((name, generics, source, load))
 }
 
-#[proc_macro_derive(Action, attributes(action))]
+#[proc_macro_derive(Action, attributes(action, input))]
 pub fn action_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let ast: syn::DeriveInput = syn::parse2(input.into()).unwrap();
-    let (struct_name, generics, manifest_path) = parse_derive(&ast);
+    let ast = syn::parse_macro_input!(input as syn::DeriveInput);
+    derive(&ast)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn derive(ast: &syn::DeriveInput) -> syn::Result<TokenStream> {
+    let (struct_name, generics, source, load) = parse_derive(ast)?;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let fields = parse_input_fields(ast)?;
 
-    let manifest = Manifest::from_action_yml(manifest_path);
+    let (manifest, track) = match source {
+        Source::Read(path, span) => (manifest::from_action_yml(path, span)?, quote! {}),
+        Source::Generate(generate) => {
+            let path = manifest_dir().join(&generate.path);
+            let mode = generate::Mode::from_env(generate.check);
+            let span = generate.span;
+            let manifest = generate.manifest(&ast.attrs, &fields)?;
+            generate::sync(&path, &manifest, mode, span)?;
+            let (check, write) = (generate::CHECK_ENV_VAR, generate::WRITE_ENV_VAR);
+            // the environment and included files are tracked by the compiler,
+            // so changing the mode or editing the checked file expands the macro again
+            let track_file = (mode != generate::Mode::Embed).then(|| {
+                let path = path.to_string_lossy();
+                quote! { const _: &[u8] = include_bytes!(#path); }
+            });
+            let track = quote! {
+                const _: [Option<&str>; 2] = [option_env!(#check), option_env!(#write)];
+                #track_file
+            };
+            (manifest, track)
+        }
+    };
     // dbg!(&manifest);
 
     let input_enum_variants: Vec<_> = manifest
@@ -122,20 +328,27 @@ pub fn action_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream
     };
 
     let input_impl_methods = input_impl_methods(&manifest);
+    let load_impl_methods = if load {
+        load_impl_methods(&manifest, &fields)?
+    } else {
+        quote! {}
+    };
     let input_impl = quote! {
         #[allow(clippy::all)]
         impl #impl_generics #struct_name #ty_generics #where_clause {
             #input_impl_methods
+            #load_impl_methods
         }
     };
 
     let tokens = quote! {
+        #track
         #input_enum
         #input_impl
         #parse_impl
     };
     // eprintln!("{}", pretty_print(&tokens));
-    tokens.into()
+    Ok(tokens)
 }
 
 fn input_impl_methods(manifest: &Manifest) -> TokenStream {
@@ -146,6 +359,8 @@ fn input_impl_methods(manifest: &Manifest) -> TokenStream {
         ..
     } = manifest;
 
+    let yaml = manifest::to_yaml(manifest);
+
     let derived_methods: TokenStream = manifest
         .inputs
         .keys()
@@ -207,10 +422,111 @@ fn input_impl_methods(manifest: &Manifest) -> TokenStream {
             #author
         }
 
+        /// Manifest (action.yml) of this action.
+        pub fn manifest() -> &'static ::action_core::manifest::Manifest {
+            static MANIFEST: ::std::sync::OnceLock<::action_core::manifest::Manifest> =
+                ::std::sync::OnceLock::new();
+            MANIFEST.get_or_init(|| {
+                ::action_core::manifest::Manifest::from_yaml(#yaml)
+                    .expect("manifest was validated at compile time")
+            })
+        }
+
         #derived_methods
     }
 }
 
+/// Generates `load` and `load_from`, which populate every field from its input.
+fn load_impl_methods(manifest: &Manifest, fields: &[InputField<'_>]) -> syn::Result<TokenStream> {
+    let field_values = fields
+        .iter()
+        .map(|field| {
+            let InputField { ident, ty, .. } = field;
+            if field.skip {
+                return Ok(quote! { #ident: ::std::default::Default::default() });
+            }
+            let name = match &field.name {
+                Some(name) => name.clone(),
+                None => manifest
+                    .inputs
+                    .keys()
+                    .find(|name| ident::parse_str(name) == **ident)
+                    .cloned()
+                    .ok_or_else(|| {
+                        syn::Error::new_spanned(
+                            ident,
+                            format!(
+                                "field `{ident}` does not match any input, rename it with `#[input(name = \"...\")]` or exclude it with `#[input(skip)]`"
+                            ),
+                        )
+                    })?,
+            };
+            let input = manifest.inputs.get(&name).ok_or_else(|| {
+                syn::Error::new_spanned(ident, format!("input `{name}` does not exist"))
+            })?;
+            let r#default = quote_option(input.default.as_ref());
+
+            let (optional, ty) = match option_inner_type(ty) {
+                Some(inner) => (true, inner),
+                None => (false, *ty),
+            };
+            let (decode_ty, unwrap) = match field.decode {
+                Decode::Parse => (quote! { #ty }, quote! { value }),
+                Decode::Json => (
+                    quote! { ::action_core::input::Json<#ty> },
+                    quote! { value.0 },
+                ),
+                Decode::Yaml => (
+                    quote! { ::action_core::input::Yaml<#ty> },
+                    quote! { value.0 },
+                ),
+            };
+            let require = if optional {
+                quote! {}
+            } else {
+                quote! {
+                    .ok_or_else(|| ::action_core::input::InputError::Missing {
+                        name: #name.into(),
+                    })?
+                }
+            };
+            Ok(quote! {
+                #ident: {
+                    let default: Option<&str> = #r#default;
+                    ::action_core::input::GetInput::get_input(env, #name)
+                        .or_else(|| default.map(Into::into))
+                        .map(|value| {
+                            <#decode_ty as ::action_core::input::Parse>::parse(value)
+                                .map(|value| #unwrap)
+                                .map_err(|source| ::action_core::input::InputError::Parse {
+                                    name: #name.into(),
+                                    source: ::action_core::input::BoxError(Box::new(source)),
+                                })
+                        })
+                        .transpose()?
+                        #require
+                }
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        /// Loads this action from its inputs.
+        pub fn load() -> Result<Self, ::action_core::input::InputError<::action_core::input::BoxError>> {
+            Self::load_from(&::action_core::env::OsEnv)
+        }
+
+        /// Loads this action from its inputs in `env`.
+        pub fn load_from<E: ::action_core::env::Read>(
+            env: &E,
+        ) -> Result<Self, ::action_core::input::InputError<::action_core::input::BoxError>> {
+            Ok(Self {
+                #(#field_values,)*
+            })
+        }
+    })
+}
+
 #[allow(dead_code)]
 fn pretty_print(tokens: &TokenStream) -> String {
     let _file = syn::parse_file(&tokens.to_string()).unwrap();
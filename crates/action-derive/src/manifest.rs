@@ -1,46 +1,78 @@
-use std::collections::HashMap;
+pub use action_model::action::*;
+use proc_macro2::Span;
 use std::path::Path;
 
-#[derive(PartialEq, Eq, Hash, Debug, serde::Deserialize)]
-pub struct Input {
-    pub description: Option<String>,
-    #[serde(rename(deserialize = "deprecationMessage"))]
-    pub deprecation_message: Option<String>,
-    pub default: Option<String>,
-    pub required: Option<bool>,
+/// Validates the manifest.
+///
+/// Unknown branding icons are accepted, since GitHub adds icons over time
+/// and the list of this crate may be outdated.
+pub fn validate(manifest: &Manifest) -> Result<(), ValidationError> {
+    match manifest.validate() {
+        Err(ValidationError::BrandingIcon(_)) => Ok(()),
+        result => result,
+    }
 }
 
-#[derive(PartialEq, Eq, Hash, Debug, serde::Deserialize)]
-pub struct Output {
-    pub description: Option<String>,
+/// Reads and validates the action manifest at `path`.
+///
+/// # Errors
+/// If the manifest cannot be read or is invalid, reported at `span`.
+pub fn from_action_yml(path: impl AsRef<Path>, span: Span) -> syn::Result<Manifest> {
+    let path = path.as_ref();
+    let error = |message: String| syn::Error::new(span, message);
+    let file = std::fs::File::open(path)
+        .map_err(|err| error(format!("failed to open {}: {err}", path.display())))?;
+    let manifest = Manifest::from_reader(std::io::BufReader::new(file))
+        .map_err(|err| error(format!("failed to parse {}: {err}", path.display())))?;
+    validate(&manifest)
+        .map_err(|err| error(format!("invalid action manifest {}: {err}", path.display())))?;
+    Ok(manifest)
 }
 
-#[derive(PartialEq, Eq, Hash, Debug, serde::Deserialize)]
-pub struct Branding {
-    pub icon: Option<String>,
-    pub color: Option<String>,
+/// Serializes the manifest to YAML.
+pub fn to_yaml(manifest: &Manifest) -> String {
+    manifest
+        .to_yaml()
+        .unwrap_or_else(|err| panic!("failed to serialize action manifest: {err}"))
 }
 
-#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
-pub struct Manifest {
-    pub name: Option<String>,
-    pub description: Option<String>,
-    pub author: Option<String>,
-    pub branding: Option<Branding>,
-
-    #[serde(default)]
-    pub inputs: HashMap<String, Input>,
-    #[serde(default)]
-    pub outputs: HashMap<String, Output>,
+/// Serializes the manifest to YAML, prefixed with a notice that it is generated.
+pub fn to_action_yml(manifest: &Manifest) -> String {
+    let yaml = to_yaml(manifest);
+    format!("# This file is generated by `#[derive(Action)]`, do not edit it manually.\n{yaml}")
 }
 
-impl Manifest {
-    pub fn from_action_yml(path: impl AsRef<Path>) -> Self {
-        let file = std::fs::OpenOptions::new()
-            .read(true)
-            .open(path.as_ref())
-            .unwrap();
-        let reader = std::io::BufReader::new(file);
-        serde_yaml::from_reader(reader).unwrap()
+#[cfg(test)]
+mod tests {
+    use super::from_action_yml;
+    use proc_macro2::Span;
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    #[test]
+    fn test_from_action_yml() -> color_eyre::eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("action.yml");
+        let error = |path| {
+            from_action_yml(path, Span::call_site())
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert!(error(&path).starts_with("failed to open"));
+
+        std::fs::write(&path, "name: [")?;
+        assert!(error(&path).starts_with("failed to parse"));
+
+        std::fs::write(&path, "name: test\nbranding:\n  color: pink\n")?;
+        assert!(error(&path).contains("invalid branding color \"pink\""));
+
+        // icons added by GitHub since are accepted
+        std::fs::write(&path, "name: test\nbranding:\n  icon: coffee\n")?;
+        let manifest = from_action_yml(&path, Span::call_site())?;
+        sim_assert_eq!(
+            manifest.branding.and_then(|branding| branding.icon),
+            Some("coffee".to_string())
+        );
+        Ok(())
     }
 }
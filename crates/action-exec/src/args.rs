@@ -0,0 +1,169 @@
+//! Splitting argument strings into arguments and quoting arguments for the log.
+
+use std::borrow::Cow;
+
+/// Splits an argument string, e.g. from an input, into arguments like `argStringToArray` of `@actions/exec`.
+///
+/// Arguments are separated by spaces, except within double quotes, which are removed.
+/// Within double quotes, `\"` is a literal double quote and all other backslashes are kept.
+/// An unterminated quote extends to the end of the string.
+#[must_use]
+pub fn arg_string_to_array(arg_string: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut in_quotes = false;
+    let mut escaped = false;
+    let mut arg = String::new();
+
+    for c in arg_string.chars() {
+        match c {
+            '"' if escaped => append(&mut arg, &mut escaped, c),
+            '"' => in_quotes = !in_quotes,
+            '\\' if escaped => append(&mut arg, &mut escaped, c),
+            '\\' if in_quotes => escaped = true,
+            ' ' if !in_quotes => {
+                if !arg.is_empty() {
+                    args.push(std::mem::take(&mut arg));
+                }
+            }
+            c => append(&mut arg, &mut escaped, c),
+        }
+    }
+    if !arg.is_empty() {
+        args.push(arg.trim().to_string());
+    }
+    args
+}
+
+/// Appends `c` to `arg`, only double quotes are escaped and other escaped characters keep their backslash.
+fn append(arg: &mut String, escaped: &mut bool, c: char) {
+    if *escaped && c != '"' {
+        arg.push('\\');
+    }
+    arg.push(c);
+    *escaped = false;
+}
+
+/// Whether `c` never needs quoting in a POSIX shell.
+fn is_safe(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_-+=,./:@%".contains(c)
+}
+
+/// Quotes `arg` for a POSIX shell, leaving it unchanged if that is not needed.
+///
+/// Used to log command lines that can be copied into a shell.
+#[must_use]
+pub fn quote(arg: &str) -> Cow<'_, str> {
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        return Cow::Borrowed(arg);
+    }
+    Cow::Owned(format!("'{}'", arg.replace('\'', r"'\''")))
+}
+
+/// Joins `command` and `args` into a command line, [quoting](quote) them where needed.
+#[must_use]
+pub fn join<S>(command: &str, args: &[S]) -> String
+where
+    S: AsRef<str>,
+{
+    std::iter::once(command)
+        .chain(args.iter().map(AsRef::as_ref))
+        .map(quote)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{arg_string_to_array, join, quote};
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    #[test]
+    fn test_arg_string_to_array() {
+        let cases: &[(&str, &[&str])] = &[
+            ("", &[]),
+            ("   ", &[]),
+            ("--foo", &["--foo"]),
+            ("--foo --bar", &["--foo", "--bar"]),
+            ("  --foo    --bar  ", &["--foo", "--bar"]),
+            ("--foo\t--bar", &["--foo\t--bar"]),
+            (r#"--foo "bar baz""#, &["--foo", "bar baz"]),
+            (r#"--foo="bar baz" qux"#, &["--foo=bar baz", "qux"]),
+            (r#"foo"bar baz"qux"#, &["foobar bazqux"]),
+            (r#""""#, &[]),
+            (r#"foo "" bar"#, &["foo", "bar"]),
+            (r#"" ""#, &[""]),
+            (r#""foo "   "#, &["foo "]),
+            (r#"bar "foo ""#, &["bar", "foo"]),
+            (r#""foo bar"#, &["foo bar"]),
+            (r#""hello \"world\"""#, &[r#"hello "world""#]),
+            (r#""\"quoted\"" plain"#, &[r#""quoted""#, "plain"]),
+            (r#""foo\bar""#, &[r"foo\bar"]),
+            (r#""foo\\bar""#, &[r"foo\\bar"]),
+            (r#""foo\\\"bar""#, &[r#"foo\\"bar"#]),
+            (r"C:\path\to\file", &[r"C:\path\to\file"]),
+            (r#"foo\"bar"#, &[r#"foo\bar"#]),
+            (r#""foo\"#, &["foo"]),
+            ("'single quoted'", &["'single", "quoted'"]),
+            (
+                r#"-Dprop="a value" --flag -x "C:\Program Files\tool.exe""#,
+                &[
+                    "-Dprop=a value",
+                    "--flag",
+                    "-x",
+                    r"C:\Program Files\tool.exe",
+                ],
+            ),
+        ];
+        for (arg_string, expected) in cases {
+            sim_assert_eq!(
+                arg_string_to_array(arg_string),
+                expected.to_vec(),
+                "splitting {arg_string:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_quote() {
+        sim_assert_eq!(quote("--foo=bar/baz.txt"), "--foo=bar/baz.txt");
+        sim_assert_eq!(quote(""), "''");
+        sim_assert_eq!(quote("bar baz"), "'bar baz'");
+        sim_assert_eq!(quote("it's"), r"'it'\''s'");
+        sim_assert_eq!(quote("$HOME"), "'$HOME'");
+        sim_assert_eq!(
+            join("echo", &["hello world", "--flag", "a\"b"]),
+            r#"echo 'hello world' --flag 'a"b'"#
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_join_in_shell() -> color_eyre::eyre::Result<()> {
+        use crate::{ExecOptions, get_exec_output};
+
+        let args = [
+            "plain",
+            "",
+            "with space",
+            "it's",
+            "\"double\"",
+            "$HOME `pwd` $(pwd)",
+            "back\\slash",
+            "glob*?[a]",
+            "new\nline",
+            "~user",
+            "a;b|c&d>e<f",
+        ];
+        let output = get_exec_output(
+            "sh",
+            &["-c", &join("printf", &[&["%s|"], &args[..]].concat())],
+            ExecOptions {
+                silent: true,
+                ..ExecOptions::default()
+            },
+        )
+        .await?;
+        sim_assert_eq!(output.stdout, format!("{}|", args.join("|")));
+        Ok(())
+    }
+}
@@ -0,0 +1,469 @@
+//! Running processes with their output streamed to the log and listeners.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("failed to start {command:?}")]
+    Spawn {
+        command: String,
+        source: std::io::Error,
+    },
+    #[error("failed to communicate with {command:?}")]
+    Io {
+        command: String,
+        source: std::io::Error,
+    },
+    #[error("the process {command:?} failed with exit code {code}")]
+    ExitCode { command: String, code: i32 },
+    #[error("the process {command:?} was terminated by a signal")]
+    Terminated { command: String },
+    #[error(
+        "the process {command:?} failed because one or more lines were written to the STDERR stream"
+    )]
+    Stderr { command: String },
+    #[error("failed to start the runtime")]
+    Runtime(#[source] std::io::Error),
+}
+
+/// Called with every line a process writes, without the line ending.
+pub type LineListener = Box<dyn FnMut(&str) + Send>;
+
+/// Options of [`exec`].
+pub struct ExecOptions {
+    /// Working directory of the process, the current directory by default.
+    pub cwd: Option<PathBuf>,
+    /// Variables set for the process.
+    pub env: HashMap<String, String>,
+    /// Whether the process inherits the environment of the action,
+    /// otherwise it only sees the variables in [`ExecOptions::env`].
+    pub inherit_env: bool,
+    /// Data written to the standard input of the process.
+    pub input: Option<Vec<u8>>,
+    /// Does not echo the command line and the output of the process.
+    pub silent: bool,
+    /// Does not fail if the process exits with a non-zero code.
+    pub ignore_return_code: bool,
+    /// Fails if the process writes anything to stderr.
+    ///
+    /// Otherwise, its stderr is echoed to the stdout of the action.
+    pub fail_on_stderr: bool,
+    /// Called with every line the process writes to stdout.
+    pub stdout_line: Option<LineListener>,
+    /// Called with every line the process writes to stderr.
+    pub stderr_line: Option<LineListener>,
+}
+
+impl Default for ExecOptions {
+    fn default() -> Self {
+        Self {
+            cwd: None,
+            env: HashMap::new(),
+            inherit_env: true,
+            input: None,
+            silent: false,
+            ignore_return_code: false,
+            fail_on_stderr: false,
+            stdout_line: None,
+            stderr_line: None,
+        }
+    }
+}
+
+impl fmt::Debug for ExecOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExecOptions")
+            .field("cwd", &self.cwd)
+            .field("env", &self.env)
+            .field("inherit_env", &self.inherit_env)
+            .field("input", &self.input.as_ref().map(Vec::len))
+            .field("silent", &self.silent)
+            .field("ignore_return_code", &self.ignore_return_code)
+            .field("fail_on_stderr", &self.fail_on_stderr)
+            .field("stdout_line", &self.stdout_line.is_some())
+            .field("stderr_line", &self.stderr_line.is_some())
+            .finish()
+    }
+}
+
+/// Exit code and output of a process run with [`get_exec_output`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecOutput {
+    pub exit_code: i32,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Where the output of a stream of the process is echoed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Echo {
+    None,
+    Stdout,
+    Stderr,
+}
+
+impl Echo {
+    fn write(self, line: &str) {
+        match self {
+            Self::None => {}
+            Self::Stdout => print!("{line}"),
+            Self::Stderr => eprint!("{line}"),
+        }
+    }
+}
+
+/// Reads the lines of `stream`, returning all of it if `capture` is set.
+async fn read_lines(
+    stream: impl AsyncRead + Unpin,
+    echo: Echo,
+    mut listener: Option<LineListener>,
+    capture: bool,
+) -> std::io::Result<(Vec<u8>, bool)> {
+    let mut reader = BufReader::new(stream);
+    let mut captured = Vec::new();
+    let mut line = Vec::new();
+    let mut any = false;
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).await? == 0 {
+            break;
+        }
+        any = true;
+        if capture {
+            captured.extend_from_slice(&line);
+        }
+        let line = String::from_utf8_lossy(&line);
+        echo.write(&line);
+        if let Some(listener) = &mut listener {
+            listener(line.trim_end_matches('\n').trim_end_matches('\r'));
+        }
+    }
+    Ok((captured, any))
+}
+
+/// Runs `command` with `args` and returns its exit code.
+///
+/// Unless `silent`, the command line is echoed as `[command]...` and the output of the
+/// process is echoed to the log.
+///
+/// # Errors
+/// If the process cannot be started, exits with a non-zero code (unless `ignore_return_code`)
+/// or writes to stderr with `fail_on_stderr`.
+pub async fn exec<S>(command: &str, args: &[S], options: ExecOptions) -> Result<i32, Error>
+where
+    S: AsRef<str>,
+{
+    let (exit_code, _, _) = run(command, args, options, false).await?;
+    Ok(exit_code)
+}
+
+/// Runs `command` with `args` like [`exec`] and captures its output.
+///
+/// # Errors
+/// If the process fails, see [`exec`].
+pub async fn get_exec_output<S>(
+    command: &str,
+    args: &[S],
+    options: ExecOptions,
+) -> Result<ExecOutput, Error>
+where
+    S: AsRef<str>,
+{
+    let (exit_code, stdout, stderr) = run(command, args, options, true).await?;
+    Ok(ExecOutput {
+        exit_code,
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+    })
+}
+
+async fn run<S>(
+    command: &str,
+    args: &[S],
+    options: ExecOptions,
+    capture: bool,
+) -> Result<(i32, Vec<u8>, Vec<u8>), Error>
+where
+    S: AsRef<str>,
+{
+    let ExecOptions {
+        cwd,
+        env,
+        inherit_env,
+        input,
+        silent,
+        ignore_return_code,
+        fail_on_stderr,
+        stdout_line,
+        stderr_line,
+    } = options;
+    let io_error = |source| Error::Io {
+        command: command.to_string(),
+        source,
+    };
+
+    if !silent {
+        action_core::info!("[command]{}", crate::args::join(command, args));
+    }
+
+    let mut cmd = tokio::process::Command::new(command);
+    cmd.args(args.iter().map(AsRef::as_ref))
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if !inherit_env {
+        cmd.env_clear();
+    }
+    cmd.envs(env);
+    if let Some(cwd) = cwd {
+        cmd.current_dir(cwd);
+    }
+    let mut child = cmd.spawn().map_err(|source| Error::Spawn {
+        command: command.to_string(),
+        source,
+    })?;
+
+    let stdin = child.stdin.take();
+    let write_input = async move {
+        if let (Some(mut stdin), Some(input)) = (stdin, input) {
+            match stdin.write_all(&input).await {
+                // the process does not have to read all of its input
+                Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => {}
+                result => result?,
+            }
+        }
+        Ok::<_, std::io::Error>(())
+    };
+    let (stdout_echo, stderr_echo) = match (silent, fail_on_stderr) {
+        (true, _) => (Echo::None, Echo::None),
+        (false, true) => (Echo::Stdout, Echo::Stderr),
+        (false, false) => (Echo::Stdout, Echo::Stdout),
+    };
+    let stdout = read_lines(
+        child.stdout.take().expect("stdout is piped"),
+        stdout_echo,
+        stdout_line,
+        capture,
+    );
+    let stderr = read_lines(
+        child.stderr.take().expect("stderr is piped"),
+        stderr_echo,
+        stderr_line,
+        capture,
+    );
+    let (input, stdout, stderr) = tokio::join!(write_input, stdout, stderr);
+    input.map_err(io_error)?;
+    let (stdout, _) = stdout.map_err(io_error)?;
+    let (stderr, wrote_stderr) = stderr.map_err(io_error)?;
+    let status = child.wait().await.map_err(io_error)?;
+
+    let Some(exit_code) = status.code() else {
+        return Err(Error::Terminated {
+            command: command.to_string(),
+        });
+    };
+    if exit_code != 0 && !ignore_return_code {
+        return Err(Error::ExitCode {
+            command: command.to_string(),
+            code: exit_code,
+        });
+    }
+    if wrote_stderr && fail_on_stderr {
+        return Err(Error::Stderr {
+            command: command.to_string(),
+        });
+    }
+    Ok((exit_code, stdout, stderr))
+}
+
+/// Blocking variants of [`exec`] and [`get_exec_output`], for actions without an async runtime.
+///
+/// They must not be called from within an async runtime.
+pub mod blocking {
+    use super::{Error, ExecOptions, ExecOutput};
+
+    fn runtime() -> Result<tokio::runtime::Runtime, Error> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(Error::Runtime)
+    }
+
+    /// Runs `command` with `args` and returns its exit code, see [`super::exec`].
+    ///
+    /// # Errors
+    /// If the process fails.
+    pub fn exec<S>(command: &str, args: &[S], options: ExecOptions) -> Result<i32, Error>
+    where
+        S: AsRef<str>,
+    {
+        runtime()?.block_on(super::exec(command, args, options))
+    }
+
+    /// Runs `command` with `args` and captures its output, see [`super::get_exec_output`].
+    ///
+    /// # Errors
+    /// If the process fails.
+    pub fn get_exec_output<S>(
+        command: &str,
+        args: &[S],
+        options: ExecOptions,
+    ) -> Result<ExecOutput, Error>
+    where
+        S: AsRef<str>,
+    {
+        runtime()?.block_on(super::get_exec_output(command, args, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, ExecOptions, exec};
+
+    // the processes of these tests require a POSIX shell and utilities
+    #[cfg(unix)]
+    use super::{ExecOutput, blocking, get_exec_output};
+    #[cfg(unix)]
+    use parking_lot::Mutex;
+    #[cfg(unix)]
+    use similar_asserts::assert_eq as sim_assert_eq;
+    #[cfg(unix)]
+    use std::sync::Arc;
+
+    #[cfg(unix)]
+    fn sh(script: &str) -> [&str; 2] {
+        ["-c", script]
+    }
+
+    #[tokio::test]
+    async fn test_spawn_error() {
+        let err = exec("does-not-exist", &[] as &[&str], ExecOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Spawn { .. }));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_get_exec_output() -> color_eyre::eyre::Result<()> {
+        let output = get_exec_output(
+            "sh",
+            &sh("echo out; echo err >&2; exit 3"),
+            ExecOptions {
+                ignore_return_code: true,
+                ..ExecOptions::default()
+            },
+        )
+        .await?;
+        sim_assert_eq!(
+            output,
+            ExecOutput {
+                exit_code: 3,
+                stdout: "out\n".to_string(),
+                stderr: "err\n".to_string(),
+            }
+        );
+
+        let err = exec("sh", &sh("exit 3"), ExecOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ExitCode { code: 3, .. }));
+
+        let err = exec(
+            "sh",
+            &sh("echo err >&2"),
+            ExecOptions {
+                fail_on_stderr: true,
+                ..ExecOptions::default()
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, Error::Stderr { .. }));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_listeners_and_input() -> color_eyre::eyre::Result<()> {
+        let stdout = Arc::new(Mutex::new(Vec::new()));
+        let stderr = Arc::new(Mutex::new(Vec::new()));
+        let stdout_lines = Arc::clone(&stdout);
+        let stderr_lines = Arc::clone(&stderr);
+        let exit_code = exec(
+            "sh",
+            &sh("while read -r line; do echo \"out $line\"; done; printf 'err\\r\\nlast' >&2"),
+            ExecOptions {
+                input: Some(b"first\nsecond\n".to_vec()),
+                silent: true,
+                stdout_line: Some(Box::new(move |line| {
+                    stdout_lines.lock().push(line.to_string());
+                })),
+                stderr_line: Some(Box::new(move |line| {
+                    stderr_lines.lock().push(line.to_string());
+                })),
+                ..ExecOptions::default()
+            },
+        )
+        .await?;
+        sim_assert_eq!(exit_code, 0);
+        sim_assert_eq!(*stdout.lock(), ["out first", "out second"]);
+        sim_assert_eq!(*stderr.lock(), ["err", "last"]);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_cwd_and_env() -> color_eyre::eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let output = get_exec_output(
+            "/bin/sh",
+            &sh("pwd; echo \"$ACTION_EXEC_TEST:${PATH:+path}\""),
+            ExecOptions {
+                cwd: Some(dir.path().to_path_buf()),
+                env: [("ACTION_EXEC_TEST".to_string(), "set".to_string())].into(),
+                ..ExecOptions::default()
+            },
+        )
+        .await?;
+        sim_assert_eq!(
+            output.stdout,
+            format!("{}\nset:path\n", dir.path().canonicalize()?.display())
+        );
+
+        let output = get_exec_output(
+            "/usr/bin/env",
+            &[] as &[&str],
+            ExecOptions {
+                env: [("ACTION_EXEC_TEST".to_string(), "set".to_string())].into(),
+                inherit_env: false,
+                ..ExecOptions::default()
+            },
+        )
+        .await?;
+        sim_assert_eq!(output.stdout, "ACTION_EXEC_TEST=set\n");
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_blocking() -> color_eyre::eyre::Result<()> {
+        let output =
+            blocking::get_exec_output("echo", &["hello", "world"], ExecOptions::default())?;
+        sim_assert_eq!(output.stdout, "hello world\n");
+        sim_assert_eq!(
+            blocking::exec("true", &[] as &[&str], ExecOptions::default())?,
+            0
+        );
+        Ok(())
+    }
+}
@@ -1,15 +1,7 @@
-#[must_use]
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
-}
+//! Running processes from actions, like `@actions/exec`.
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+pub mod args;
+pub mod exec;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use args::{arg_string_to_array, join, quote};
+pub use exec::{Error, ExecOptions, ExecOutput, LineListener, blocking, exec, get_exec_output};
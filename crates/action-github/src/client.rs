@@ -0,0 +1,604 @@
+//! Authenticated client for the GitHub REST API.
+//!
+//! See <https://docs.github.com/en/rest>.
+
+use crate::context::{self, Context};
+use crate::rate_limit::RateLimitPolicy;
+use action_core::env::{self, OsEnv};
+use action_http_client::header::{self, HeaderName, HeaderValue};
+use action_http_client::{
+    BearerCredentialHandler, Method, Request, Response, RetryPolicy, StatusCode, Url,
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::time::SystemTime;
+
+/// Media type requested from the REST API.
+pub const ACCEPT: &str = "application/vnd.github+json";
+/// Version of the REST API the client is written against.
+pub const API_VERSION: &str = "2022-11-28";
+/// User agent sent if none is configured.
+pub const DEFAULT_USER_AGENT: &str = concat!("action-github/", env!("CARGO_PKG_VERSION"));
+
+#[derive(thiserror::Error, Debug)]
+pub enum ClientError {
+    #[error("GITHUB_TOKEN is not set")]
+    MissingToken,
+    #[error("token is not a valid header value")]
+    InvalidToken(#[source] header::InvalidHeaderValue),
+    #[error("invalid API URL {0:?}")]
+    InvalidUrl(String),
+    #[error("check run annotations require an output with a title and summary")]
+    AnnotationsWithoutOutput,
+    #[error(transparent)]
+    Http(#[from] action_http_client::Error),
+    #[error("GraphQL request failed: {}", crate::graphql::messages(.0))]
+    GraphQl(Vec<crate::graphql::GraphQlError>),
+    #[error("{method} {url} failed with status {status}: {message}")]
+    Api {
+        method: Method,
+        url: String,
+        status: StatusCode,
+        message: String,
+        documentation_url: Option<String>,
+        errors: Vec<serde_json::Value>,
+    },
+    #[error("failed to access {path:?}")]
+    Io {
+        path: std::path::PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to parse response of {url}")]
+    Deserialize {
+        url: String,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+impl ClientError {
+    /// Status of the response if the API returned an error.
+    #[must_use]
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Api { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+/// Error response of the REST API.
+#[derive(Debug, Default, Deserialize)]
+struct ErrorBody {
+    #[serde(default)]
+    message: String,
+    documentation_url: Option<String>,
+    #[serde(default)]
+    errors: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    token: String,
+    api_url: String,
+    graphql_url: Option<String>,
+    user_agent: String,
+    retry: RetryPolicy,
+    rate_limit: RateLimitPolicy,
+    #[cfg(feature = "cassette")]
+    cassette: Option<std::sync::Arc<action_http_client::Cassette>>,
+}
+
+impl ClientBuilder {
+    #[must_use]
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            api_url: context::DEFAULT_API_URL.to_string(),
+            graphql_url: None,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            retry: RetryPolicy::default(),
+            rate_limit: RateLimitPolicy::default(),
+            #[cfg(feature = "cassette")]
+            cassette: None,
+        }
+    }
+
+    /// Sets the URL of the REST API, e.g. `https://github.example.com/api/v3`.
+    #[must_use]
+    pub fn api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into();
+        self
+    }
+
+    /// Sets the URL of the GraphQL API, e.g. `https://github.example.com/api/graphql`.
+    ///
+    /// Derived from the URL of the REST API if not set.
+    #[must_use]
+    pub fn graphql_url(mut self, graphql_url: impl Into<String>) -> Self {
+        self.graphql_url = Some(graphql_url.into());
+        self
+    }
+
+    /// Sets the `User-Agent` identifying the action.
+    #[must_use]
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Sets how requests failing with a connection error or a `5xx` status are retried.
+    ///
+    /// Rate limited requests are retried according to [`ClientBuilder::rate_limit`] instead.
+    #[must_use]
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Sets how rate limited requests are retried.
+    #[must_use]
+    pub fn rate_limit(mut self, rate_limit: RateLimitPolicy) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Records requests to or replays them from `cassette`, to test actions offline.
+    #[cfg(feature = "cassette")]
+    #[must_use]
+    pub fn cassette(mut self, cassette: std::sync::Arc<action_http_client::Cassette>) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
+    /// Builds the client.
+    ///
+    /// # Errors
+    /// If the token or the API URL are invalid or the HTTP client fails to initialize.
+    pub fn build(self) -> Result<Client, ClientError> {
+        let api_url = Url::parse(&self.api_url)
+            .ok()
+            .filter(|url| !url.cannot_be_a_base())
+            .ok_or_else(|| ClientError::InvalidUrl(self.api_url.clone()))?;
+        let graphql_url = self.graphql_url.unwrap_or_else(|| {
+            let api_url = self.api_url.trim_end_matches('/');
+            match api_url.strip_suffix("/v3") {
+                Some(base) => format!("{base}/graphql"),
+                None => format!("{api_url}/graphql"),
+            }
+        });
+        let graphql_url =
+            Url::parse(&graphql_url).map_err(|_| ClientError::InvalidUrl(graphql_url.clone()))?;
+        let auth = BearerCredentialHandler::new(&self.token).map_err(|err| match err {
+            action_http_client::Error::InvalidHeader { source, .. } => {
+                ClientError::InvalidToken(source)
+            }
+            err => ClientError::Http(err),
+        })?;
+        // rate limits are handled by the rate limit policy, so their waits are not stacked
+        let http = action_http_client::Client::builder()
+            .user_agent(self.user_agent)
+            .retry(RetryPolicy {
+                retry_rate_limited: false,
+                ..self.retry
+            })
+            .handler(auth)
+            .header(header::ACCEPT, HeaderValue::from_static(ACCEPT))
+            .header(
+                HeaderName::from_static("x-github-api-version"),
+                HeaderValue::from_static(API_VERSION),
+            );
+        #[cfg(feature = "cassette")]
+        let http = match self.cassette {
+            Some(cassette) => http.cassette(cassette),
+            None => http,
+        };
+        let http = http.build()?;
+        Ok(Client {
+            http,
+            api_url,
+            graphql_url,
+            rate_limit: self.rate_limit,
+        })
+    }
+}
+
+/// Client for the GitHub REST API, authenticated with a token.
+#[derive(Debug, Clone)]
+pub struct Client {
+    pub(crate) http: action_http_client::Client,
+    api_url: Url,
+    graphql_url: Url,
+    rate_limit: RateLimitPolicy,
+}
+
+impl Client {
+    #[must_use]
+    pub fn builder(token: impl Into<String>) -> ClientBuilder {
+        ClientBuilder::new(token)
+    }
+
+    /// Creates a client for the API of the GitHub instance of `context`.
+    ///
+    /// # Errors
+    /// If the token or the API URL are invalid or the HTTP client fails to initialize.
+    pub fn new(token: impl Into<String>, context: &Context) -> Result<Self, ClientError> {
+        Self::builder(token)
+            .api_url(&context.api_url)
+            .graphql_url(&context.graphql_url)
+            .build()
+    }
+
+    /// Creates a client from the environment of the process.
+    ///
+    /// # Errors
+    /// If `GITHUB_TOKEN` is not set or the client cannot be built.
+    pub fn from_os_env() -> Result<Self, ClientError> {
+        Self::from_env(&OsEnv)
+    }
+
+    /// Creates a client authenticated with `GITHUB_TOKEN` for the API at `GITHUB_API_URL`.
+    ///
+    /// # Errors
+    /// If `GITHUB_TOKEN` is not set or the client cannot be built.
+    pub fn from_env(env: &impl env::Read) -> Result<Self, ClientError> {
+        let token = env
+            .get("GITHUB_TOKEN")
+            .map(|token| token.to_string_lossy().into_owned())
+            .filter(|token| !token.is_empty())
+            .ok_or(ClientError::MissingToken)?;
+        let (_, api_url, graphql_url) = context::server_urls(env);
+        Self::builder(token)
+            .api_url(api_url)
+            .graphql_url(graphql_url)
+            .build()
+    }
+
+    /// URL of the REST API.
+    #[must_use]
+    pub fn api_url(&self) -> &Url {
+        &self.api_url
+    }
+
+    /// URL of the GraphQL API.
+    #[must_use]
+    pub fn graphql_url(&self) -> &Url {
+        &self.graphql_url
+    }
+
+    /// URL of an endpoint of the REST API, with each segment percent-encoded.
+    #[must_use]
+    pub fn endpoint<I>(&self, segments: I) -> Url
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut url = self.api_url.clone();
+        url.path_segments_mut()
+            .expect("API URL can be a base")
+            .pop_if_empty()
+            .extend(segments);
+        url
+    }
+
+    /// Sends a request to the API.
+    ///
+    /// Rate limited requests are retried according to the [`RateLimitPolicy`],
+    /// a warning is logged before waiting.
+    ///
+    /// # Errors
+    /// If the request fails or the API responds with an error status.
+    pub async fn send(&self, mut request: Request) -> Result<Response, ClientError> {
+        let mut attempt = 0;
+        loop {
+            let retry = request.try_clone();
+            let method = request.method().clone();
+            let response = self.http.send(request).await?;
+            let status = response.status();
+            if !(status.is_client_error() || status.is_server_error()) {
+                return Ok(response);
+            }
+            let url = response.url().to_string();
+            let headers = response.headers().clone();
+            let body = response.bytes().await.unwrap_or_default();
+            let ErrorBody {
+                message,
+                documentation_url,
+                errors,
+            } = serde_json::from_slice(&body).unwrap_or_else(|_| ErrorBody {
+                message: String::from_utf8_lossy(&body).into_owned(),
+                ..ErrorBody::default()
+            });
+            let delay =
+                self.rate_limit
+                    .delay(status, &headers, &message, attempt, SystemTime::now());
+            if let (Some(retry), Some(delay)) = (retry, delay) {
+                attempt += 1;
+                action_core::warning!(
+                    "{method} {url} was rate limited, retrying in {}s (attempt {attempt} of {})",
+                    delay.as_secs(),
+                    self.rate_limit.max_retries,
+                );
+                tokio::time::sleep(delay).await;
+                request = retry;
+                continue;
+            }
+            return Err(ClientError::Api {
+                method,
+                url,
+                status,
+                message,
+                documentation_url,
+                errors,
+            });
+        }
+    }
+
+    /// Sends a request to the API and parses the JSON response.
+    ///
+    /// # Errors
+    /// If the request fails, the API responds with an error status or the response does not match `T`.
+    pub async fn send_json<T>(&self, request: Request) -> Result<T, ClientError>
+    where
+        T: DeserializeOwned,
+    {
+        let response = self.send(request).await?;
+        let url = response.url().to_string();
+        let body = response
+            .bytes()
+            .await
+            .map_err(action_http_client::Error::from)?;
+        serde_json::from_slice(&body).map_err(|source| ClientError::Deserialize { url, source })
+    }
+
+    async fn send_body<B, T>(&self, method: Method, url: Url, body: &B) -> Result<T, ClientError>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let request = action_http_client::Client::json_request(method, url, body)?;
+        self.send_json(request).await
+    }
+
+    /// Sends a `GET` request and parses the JSON response.
+    ///
+    /// # Errors
+    /// If the request fails, the API responds with an error status or the response does not match `T`.
+    pub async fn get<T>(&self, url: Url) -> Result<T, ClientError>
+    where
+        T: DeserializeOwned,
+    {
+        self.send_json(Request::new(Method::GET, url)).await
+    }
+
+    /// Sends a `POST` request with a JSON body and parses the JSON response.
+    ///
+    /// # Errors
+    /// If the request fails, the API responds with an error status or the response does not match `T`.
+    pub async fn post<B, T>(&self, url: Url, body: &B) -> Result<T, ClientError>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        self.send_body(Method::POST, url, body).await
+    }
+
+    /// Sends a `PATCH` request with a JSON body and parses the JSON response.
+    ///
+    /// # Errors
+    /// If the request fails, the API responds with an error status or the response does not match `T`.
+    pub async fn patch<B, T>(&self, url: Url, body: &B) -> Result<T, ClientError>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        self.send_body(Method::PATCH, url, body).await
+    }
+
+    /// Sends a `PUT` request with a JSON body and parses the JSON response.
+    ///
+    /// # Errors
+    /// If the request fails, the API responds with an error status or the response does not match `T`.
+    pub async fn put<B, T>(&self, url: Url, body: &B) -> Result<T, ClientError>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        self.send_body(Method::PUT, url, body).await
+    }
+
+    /// Sends a `DELETE` request.
+    ///
+    /// # Errors
+    /// If the request fails or the API responds with an error status.
+    pub async fn delete(&self, url: Url) -> Result<(), ClientError> {
+        self.send(Request::new(Method::DELETE, url)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{Client, ClientError, RetryPolicy};
+    use action_core::env::EnvMap;
+    use action_http_client::StatusCode;
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use std::time::Duration;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Starts a mock API server and a client for it.
+    pub(crate) async fn mock() -> color_eyre::eyre::Result<(MockServer, Client)> {
+        let server = MockServer::start().await;
+        let client = Client::builder("secret-token")
+            .api_url(format!("{}/api/v3", server.uri()))
+            .retry(RetryPolicy {
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(10),
+                ..RetryPolicy::default()
+            })
+            .build()?;
+        Ok((server, client))
+    }
+
+    #[test]
+    fn test_endpoint() -> color_eyre::eyre::Result<()> {
+        let client = Client::builder("token")
+            .api_url("https://github.example.com/api/v3/")
+            .build()?;
+        sim_assert_eq!(
+            client
+                .endpoint(["repos", "octo-org", "octo-repo", "contents", "a b", "c?"])
+                .as_str(),
+            "https://github.example.com/api/v3/repos/octo-org/octo-repo/contents/a%20b/c%3F"
+        );
+        assert!(matches!(
+            Client::builder("token").api_url("not a url").build(),
+            Err(ClientError::InvalidUrl(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_env() -> color_eyre::eyre::Result<()> {
+        assert!(matches!(
+            Client::from_env(&EnvMap::default()),
+            Err(ClientError::MissingToken)
+        ));
+        let env = EnvMap::from_iter([
+            ("GITHUB_TOKEN", "token"),
+            ("GITHUB_SERVER_URL", "https://github.example.com"),
+        ]);
+        sim_assert_eq!(
+            Client::from_env(&env)?.api_url().as_str(),
+            "https://github.example.com/api/v3"
+        );
+        sim_assert_eq!(
+            Client::builder("token")
+                .api_url("https://github.example.com/api/v3")
+                .build()?
+                .graphql_url()
+                .as_str(),
+            "https://github.example.com/api/graphql"
+        );
+        sim_assert_eq!(
+            Client::builder("token").build()?.graphql_url().as_str(),
+            "https://api.github.com/graphql"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_headers_and_errors() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/user"))
+            .and(header("authorization", "Bearer secret-token"))
+            .and(header("accept", "application/vnd.github+json"))
+            .and(header("x-github-api-version", "2022-11-28"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "login": "octocat",
+                "id": 1,
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/missing"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
+                "message": "Not Found",
+                "documentation_url": "https://docs.github.com/rest",
+            })))
+            .mount(&server)
+            .await;
+
+        let user: crate::event::User = client.get(client.endpoint(["user"])).await?;
+        sim_assert_eq!(user.login, "octocat");
+
+        let err = client
+            .get::<serde_json::Value>(client.endpoint(["missing"]))
+            .await
+            .unwrap_err();
+        sim_assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
+        sim_assert_eq!(
+            err.to_string(),
+            format!(
+                "GET {}/api/v3/missing failed with status 404 Not Found: Not Found",
+                server.uri()
+            )
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_retry() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/user"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("retry-after", "0")
+                    .set_body_json(serde_json::json!({
+                        "message": "You have exceeded a secondary rate limit."
+                    })),
+            )
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/user"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "login": "octocat",
+                "id": 1,
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/forbidden"))
+            .respond_with(ResponseTemplate::new(403).set_body_json(serde_json::json!({
+                "message": "Resource not accessible by integration"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let user: crate::event::User = client.get(client.endpoint(["user"])).await?;
+        sim_assert_eq!(user.login, "octocat");
+        let err = client
+            .get::<serde_json::Value>(client.endpoint(["forbidden"]))
+            .await
+            .unwrap_err();
+        sim_assert_eq!(err.status(), Some(StatusCode::FORBIDDEN));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_request_count() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/limited"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "0"))
+            .expect(4)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/unavailable"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(4)
+            .mount(&server)
+            .await;
+
+        let err = client
+            .get::<serde_json::Value>(client.endpoint(["limited"]))
+            .await
+            .unwrap_err();
+        sim_assert_eq!(err.status(), Some(StatusCode::TOO_MANY_REQUESTS));
+        let err = client
+            .get::<serde_json::Value>(client.endpoint(["unavailable"]))
+            .await
+            .unwrap_err();
+        sim_assert_eq!(err.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+        Ok(())
+    }
+}
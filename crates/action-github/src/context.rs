@@ -0,0 +1,434 @@
+use crate::event::{Branch, Event, PullRequest};
+use action_core::env::{self, OsEnv};
+use std::path::PathBuf;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ContextError {
+    #[error("failed to read event payload {path:?}")]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to parse payload of {event:?} event")]
+    Parse {
+        event: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("context.repo requires a GITHUB_REPOSITORY environment variable like 'owner/repo'")]
+    MissingRepository,
+    #[error("the {0:?} event does not refer to an issue or pull request")]
+    MissingIssue(String),
+}
+
+/// Default URL of the GitHub server.
+pub const DEFAULT_SERVER_URL: &str = "https://github.com";
+/// Default URL of the GitHub REST API.
+pub const DEFAULT_API_URL: &str = "https://api.github.com";
+/// Default URL of the GitHub GraphQL API.
+pub const DEFAULT_GRAPHQL_URL: &str = "https://api.github.com/graphql";
+
+/// Derives the API and GraphQL URLs of a GitHub server.
+///
+/// `github.com` and `*.ghe.com` serve the API from an `api.` subdomain,
+/// GitHub Enterprise Server serves it from `/api/v3` and `/api/graphql`.
+fn api_urls(server_url: &str) -> (String, String) {
+    let server_url = server_url.trim_end_matches('/');
+    let (scheme, host) = server_url
+        .split_once("://")
+        .unwrap_or(("https", server_url));
+    if host.eq_ignore_ascii_case("github.com") {
+        (DEFAULT_API_URL.to_string(), DEFAULT_GRAPHQL_URL.to_string())
+    } else if host.to_ascii_lowercase().ends_with(".ghe.com") {
+        let api_url = format!("{scheme}://api.{host}");
+        let graphql_url = format!("{api_url}/graphql");
+        (api_url, graphql_url)
+    } else {
+        (
+            format!("{server_url}/api/v3"),
+            format!("{server_url}/api/graphql"),
+        )
+    }
+}
+
+/// Reads a variable of the runner, treating an empty value as unset.
+fn var(env: &impl env::Read, name: &str) -> Option<String> {
+    env.get(name)
+        .map(|value| value.to_string_lossy().into_owned())
+        .filter(|value| !value.is_empty())
+}
+
+/// Server, REST API and GraphQL API URLs of the GitHub instance the workflow runs on.
+pub(crate) fn server_urls(env: &impl env::Read) -> (String, String, String) {
+    let server_url = var(env, "GITHUB_SERVER_URL").map_or_else(
+        || DEFAULT_SERVER_URL.to_string(),
+        |url| url.trim_end_matches('/').to_string(),
+    );
+    let (api_url, graphql_url) = api_urls(&server_url);
+    let api_url = var(env, "GITHUB_API_URL").unwrap_or(api_url);
+    let graphql_url = var(env, "GITHUB_GRAPHQL_URL").unwrap_or(graphql_url);
+    (server_url, api_url, graphql_url)
+}
+
+/// Context of the workflow run, as provided by the runner.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Context {
+    /// Name of the event that triggered the workflow (`GITHUB_EVENT_NAME`).
+    pub event_name: String,
+    /// Payload of the event (`GITHUB_EVENT_PATH`).
+    pub event: Event,
+    /// Commit SHA that triggered the workflow (`GITHUB_SHA`).
+    pub sha: String,
+    /// Fully-formed ref that triggered the workflow, e.g. `refs/heads/main` (`GITHUB_REF`).
+    pub r#ref: String,
+    /// Name of the workflow (`GITHUB_WORKFLOW`).
+    pub workflow: String,
+    /// Name of the action or id of the step (`GITHUB_ACTION`).
+    pub action: String,
+    /// Login of the user that triggered the workflow (`GITHUB_ACTOR`).
+    pub actor: String,
+    /// Id of the current job (`GITHUB_JOB`).
+    pub job: String,
+    /// Unique number of the workflow run, `0` if unknown (`GITHUB_RUN_ID`).
+    pub run_id: u64,
+    /// Number of the run of this workflow, `0` if unknown (`GITHUB_RUN_NUMBER`).
+    pub run_number: u64,
+    /// Attempt of the workflow run, `0` if unknown (`GITHUB_RUN_ATTEMPT`).
+    pub run_attempt: u64,
+    /// Owner and name of the repository, e.g. `octocat/hello-world` (`GITHUB_REPOSITORY`).
+    pub repository: Option<String>,
+    /// URL of the GitHub server (`GITHUB_SERVER_URL`).
+    pub server_url: String,
+    /// URL of the REST API (`GITHUB_API_URL`), derived from the server URL if not set.
+    pub api_url: String,
+    /// URL of the GraphQL API (`GITHUB_GRAPHQL_URL`), derived from the server URL if not set.
+    pub graphql_url: String,
+}
+
+impl Context {
+    /// Reads the context from the environment of the process.
+    ///
+    /// # Errors
+    /// If the event payload cannot be read or does not match the event.
+    pub fn new() -> Result<Self, ContextError> {
+        Self::from_env(&OsEnv)
+    }
+
+    /// Reads the context from `env`.
+    ///
+    /// As done by `@actions/github`, a missing payload file is reported as a warning
+    /// and results in an empty [`Event::Unknown`] payload.
+    ///
+    /// # Errors
+    /// If the event payload cannot be read or is not valid JSON.
+    pub fn from_env(env: &impl env::Read) -> Result<Self, ContextError> {
+        let var = |name: &str| var(env, name);
+        let event_name = var("GITHUB_EVENT_NAME").unwrap_or_default();
+        let event = match var("GITHUB_EVENT_PATH").map(PathBuf::from) {
+            Some(path) if path.is_file() => {
+                let payload =
+                    std::fs::read(&path).map_err(|source| ContextError::Read { path, source })?;
+                let payload =
+                    serde_json::from_slice(&payload).map_err(|source| ContextError::Parse {
+                        event: event_name.clone(),
+                        source,
+                    })?;
+                Event::from_value(&event_name, payload)
+            }
+            Some(path) => {
+                action_core::warning!("GITHUB_EVENT_PATH {} does not exist", path.display());
+                Event::default()
+            }
+            None => Event::default(),
+        };
+        let number = |name: &str| {
+            var(name)
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_default()
+        };
+        let (server_url, api_url, graphql_url) = server_urls(env);
+        Ok(Self {
+            event_name,
+            event,
+            sha: var("GITHUB_SHA").unwrap_or_default(),
+            r#ref: var("GITHUB_REF").unwrap_or_default(),
+            workflow: var("GITHUB_WORKFLOW").unwrap_or_default(),
+            action: var("GITHUB_ACTION").unwrap_or_default(),
+            actor: var("GITHUB_ACTOR").unwrap_or_default(),
+            job: var("GITHUB_JOB").unwrap_or_default(),
+            run_id: number("GITHUB_RUN_ID"),
+            run_number: number("GITHUB_RUN_NUMBER"),
+            run_attempt: number("GITHUB_RUN_ATTEMPT"),
+            repository: var("GITHUB_REPOSITORY"),
+            server_url,
+            api_url,
+            graphql_url,
+        })
+    }
+
+    /// Owner and name of the repository.
+    ///
+    /// Taken from `GITHUB_REPOSITORY`, or from the repository of the event payload.
+    ///
+    /// # Errors
+    /// If neither is available.
+    pub fn repo(&self) -> Result<(&str, &str), ContextError> {
+        if let Some((owner, name)) = self
+            .repository
+            .as_deref()
+            .and_then(|repository| repository.split_once('/'))
+        {
+            return Ok((owner, name));
+        }
+        if let Some(repository) = self.event.repository() {
+            return Ok((&repository.owner.login, &repository.name));
+        }
+        if let Event::Unknown(payload) = &self.event
+            && let Some(owner) = payload["repository"]["owner"]["login"].as_str()
+            && let Some(name) = payload["repository"]["name"].as_str()
+        {
+            return Ok((owner, name));
+        }
+        Err(ContextError::MissingRepository)
+    }
+
+    /// Owner and name of the repository, with the number of the issue or pull request
+    /// the event refers to.
+    ///
+    /// # Errors
+    /// If the repository is unknown or the event does not refer to an issue or pull request.
+    pub fn issue(&self) -> Result<(&str, &str, u64), ContextError> {
+        let (owner, repo) = self.repo()?;
+        let number = match &self.event {
+            Event::Issues(event) => Some(event.issue.number),
+            Event::IssueComment(event) => Some(event.issue.number),
+            Event::PullRequest(event) | Event::PullRequestTarget(event) => {
+                Some(event.pull_request.number)
+            }
+            Event::Unknown(payload) => ["issue", "pull_request"]
+                .iter()
+                .map(|key| &payload[key]["number"])
+                .chain([&payload["number"]])
+                .find_map(serde_json::Value::as_u64),
+            _ => None,
+        };
+        let number = number.ok_or_else(|| ContextError::MissingIssue(self.event_name.clone()))?;
+        Ok((owner, repo, number))
+    }
+
+    /// Pull request of a `pull_request` or `pull_request_target` event.
+    #[must_use]
+    pub fn pull_request(&self) -> Option<&PullRequest> {
+        match &self.event {
+            Event::PullRequest(event) | Event::PullRequestTarget(event) => {
+                Some(&event.pull_request)
+            }
+            _ => None,
+        }
+    }
+
+    /// Branch the pull request is merged into.
+    #[must_use]
+    pub fn base(&self) -> Option<&Branch> {
+        self.pull_request().map(|pull_request| &pull_request.base)
+    }
+
+    /// Branch of the changes of the pull request.
+    #[must_use]
+    pub fn head(&self) -> Option<&Branch> {
+        self.pull_request().map(|pull_request| &pull_request.head)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Context, ContextError, api_urls};
+    use crate::event::Event;
+    use action_core::env::EnvMap;
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use std::path::Path;
+
+    fn fixture(name: &str) -> String {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join(format!("{name}.json"))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_from_env() -> color_eyre::eyre::Result<()> {
+        let env = EnvMap::from_iter([
+            ("GITHUB_EVENT_NAME", "issues".to_string()),
+            ("GITHUB_EVENT_PATH", fixture("issues")),
+        ]);
+        let context = Context::from_env(&env)?;
+        sim_assert_eq!(context.event_name, "issues");
+        let Event::Issues(event) = context.event else {
+            panic!("expected issues event, got {:?}", context.event);
+        };
+        sim_assert_eq!(event.issue.number, 7);
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_payload() -> color_eyre::eyre::Result<()> {
+        let context = Context::from_env(&EnvMap::from_iter([("GITHUB_EVENT_NAME", "push")]))?;
+        sim_assert_eq!(context.event, Event::default());
+
+        let dir = tempfile::tempdir()?;
+        let env = EnvMap::from_iter([
+            ("GITHUB_EVENT_NAME", "push".to_string()),
+            (
+                "GITHUB_EVENT_PATH",
+                dir.path()
+                    .join("missing.json")
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+        ]);
+        sim_assert_eq!(Context::from_env(&env)?.event, Event::default());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mismatching_payload() -> color_eyre::eyre::Result<()> {
+        let env = EnvMap::from_iter([
+            ("GITHUB_EVENT_NAME", "push".to_string()),
+            ("GITHUB_EVENT_PATH", fixture("release")),
+        ]);
+        let payload: serde_json::Value =
+            serde_json::from_str(include_str!("../fixtures/release.json"))?;
+        sim_assert_eq!(Context::from_env(&env)?.event, Event::Unknown(payload));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_payload() -> color_eyre::eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("event.json");
+        std::fs::write(&path, "{ not json")?;
+        let env = EnvMap::from_iter([
+            ("GITHUB_EVENT_NAME", "push".to_string()),
+            ("GITHUB_EVENT_PATH", path.to_string_lossy().into_owned()),
+        ]);
+        assert!(matches!(
+            Context::from_env(&env),
+            Err(ContextError::Parse { event, .. }) if event == "push"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_context() -> color_eyre::eyre::Result<()> {
+        let env = EnvMap::from_iter([
+            ("GITHUB_SHA", "ffac537e6cbbf934b08745a378932722df287a53"),
+            ("GITHUB_REF", "refs/heads/main"),
+            ("GITHUB_ACTOR", "octocat"),
+            ("GITHUB_RUN_ID", "1658821493"),
+            ("GITHUB_RUN_ATTEMPT", "2"),
+            ("GITHUB_RUN_NUMBER", "invalid"),
+            ("GITHUB_REPOSITORY", "octo-org/octo-repo"),
+        ]);
+        let context = Context::from_env(&env)?;
+        sim_assert_eq!(context.sha, "ffac537e6cbbf934b08745a378932722df287a53");
+        sim_assert_eq!(context.r#ref, "refs/heads/main");
+        sim_assert_eq!(context.actor, "octocat");
+        sim_assert_eq!(context.run_id, 1_658_821_493);
+        sim_assert_eq!(context.run_attempt, 2);
+        sim_assert_eq!(context.run_number, 0);
+        sim_assert_eq!(context.repo()?, ("octo-org", "octo-repo"));
+        sim_assert_eq!(context.server_url, "https://github.com");
+        sim_assert_eq!(context.api_url, "https://api.github.com");
+        sim_assert_eq!(context.graphql_url, "https://api.github.com/graphql");
+        assert!(matches!(
+            context.issue(),
+            Err(ContextError::MissingIssue(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_issue() -> color_eyre::eyre::Result<()> {
+        for (event, number) in [
+            ("issues", 7),
+            ("issue_comment", 42),
+            ("pull_request", 42),
+            ("pull_request_target", 42),
+        ] {
+            let fixture = if event == "pull_request_target" {
+                fixture("pull_request")
+            } else {
+                fixture(event)
+            };
+            let env = EnvMap::from_iter([
+                ("GITHUB_EVENT_NAME", event.to_string()),
+                ("GITHUB_EVENT_PATH", fixture),
+            ]);
+            let context = Context::from_env(&env)?;
+            sim_assert_eq!(context.issue()?, ("octo-org", "octo-repo", number));
+        }
+
+        let context = Context {
+            event_name: "discussion".into(),
+            event: Event::Unknown(serde_json::json!({
+                "number": 3,
+                "repository": { "name": "octo-repo", "owner": { "login": "octo-org" } }
+            })),
+            ..Context::default()
+        };
+        sim_assert_eq!(context.issue()?, ("octo-org", "octo-repo", 3));
+        assert!(matches!(
+            Context::default().repo(),
+            Err(ContextError::MissingRepository)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_request_refs() -> color_eyre::eyre::Result<()> {
+        let env = EnvMap::from_iter([
+            ("GITHUB_EVENT_NAME", "pull_request".to_string()),
+            ("GITHUB_EVENT_PATH", fixture("pull_request")),
+        ]);
+        let context = Context::from_env(&env)?;
+        sim_assert_eq!(context.base().map(|base| base.r#ref.as_str()), Some("main"));
+        sim_assert_eq!(
+            context.head().map(|head| head.label.as_str()),
+            Some("octocat:feature")
+        );
+        sim_assert_eq!(Context::default().head(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_api_urls() -> color_eyre::eyre::Result<()> {
+        sim_assert_eq!(
+            api_urls("https://github.example.com/"),
+            (
+                "https://github.example.com/api/v3".to_string(),
+                "https://github.example.com/api/graphql".to_string()
+            )
+        );
+        sim_assert_eq!(
+            api_urls("https://octocorp.ghe.com"),
+            (
+                "https://api.octocorp.ghe.com".to_string(),
+                "https://api.octocorp.ghe.com/graphql".to_string()
+            )
+        );
+
+        let env = EnvMap::from_iter([
+            ("GITHUB_SERVER_URL", "https://github.example.com"),
+            ("GITHUB_API_URL", "https://api.example.com"),
+        ]);
+        let context = Context::from_env(&env)?;
+        sim_assert_eq!(context.api_url, "https://api.example.com");
+        sim_assert_eq!(
+            context.graphql_url,
+            "https://github.example.com/api/graphql"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,511 @@
+//! Webhook event payloads that trigger a workflow.
+//!
+//! Only the commonly used fields are modelled, the complete payload is
+//! available through [`Event::Unknown`] for events that are not covered.
+//! See <https://docs.github.com/en/webhooks/webhook-events-and-payloads>.
+
+use serde::{Deserialize, Serialize};
+
+pub type Map = serde_json::Map<String, serde_json::Value>;
+
+/// Deserializes `null` like a missing field, e.g. the `inputs` of a dispatch without inputs.
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct User {
+    pub login: String,
+    pub id: u64,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html_url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Repository {
+    pub id: u64,
+    pub name: String,
+    pub full_name: String,
+    pub owner: User,
+    #[serde(default)]
+    pub private: bool,
+    pub html_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_branch: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Label {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CommitAuthor {
+    pub name: String,
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Commit {
+    pub id: String,
+    pub message: String,
+    pub timestamp: String,
+    pub url: String,
+    pub author: CommitAuthor,
+    #[serde(default)]
+    pub distinct: bool,
+}
+
+/// Head or base of a pull request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Branch {
+    pub label: String,
+    #[serde(rename = "ref")]
+    pub r#ref: String,
+    pub sha: String,
+    /// Repository of the branch, `None` if the fork was deleted.
+    pub repo: Option<Repository>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PullRequest {
+    pub id: u64,
+    pub number: u64,
+    pub title: String,
+    pub body: Option<String>,
+    pub state: String,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub merged: bool,
+    pub html_url: String,
+    pub user: User,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    pub head: Branch,
+    pub base: Branch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Issue {
+    pub id: u64,
+    pub number: u64,
+    pub title: String,
+    pub body: Option<String>,
+    pub state: String,
+    pub html_url: String,
+    pub user: User,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    /// Set if the issue is a pull request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull_request: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Comment {
+    pub id: u64,
+    /// Global id of the comment for the GraphQL API.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    pub body: String,
+    pub html_url: String,
+    pub user: User,
+}
+
+/// File attached to a release.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Asset {
+    pub id: u64,
+    /// API URL of the asset, downloads the file when requested as `application/octet-stream`.
+    pub url: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub content_type: String,
+    /// `uploaded` or `open`.
+    pub state: String,
+    pub size: u64,
+    #[serde(default)]
+    pub download_count: u64,
+    pub browser_download_url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Release {
+    pub id: u64,
+    pub tag_name: String,
+    pub target_commitish: String,
+    pub name: Option<String>,
+    pub body: Option<String>,
+    pub draft: bool,
+    pub prerelease: bool,
+    pub html_url: String,
+    pub upload_url: String,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WorkflowRun {
+    pub id: u64,
+    pub name: Option<String>,
+    pub workflow_id: u64,
+    pub run_number: u64,
+    pub event: String,
+    pub status: Option<String>,
+    pub conclusion: Option<String>,
+    pub head_branch: Option<String>,
+    pub head_sha: String,
+    pub html_url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MergeGroup {
+    pub head_sha: String,
+    pub head_ref: String,
+    pub base_sha: String,
+    pub base_ref: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Pusher {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PushEvent {
+    #[serde(rename = "ref")]
+    pub r#ref: String,
+    pub before: String,
+    pub after: String,
+    #[serde(default)]
+    pub created: bool,
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub forced: bool,
+    pub base_ref: Option<String>,
+    pub compare: String,
+    #[serde(default)]
+    pub commits: Vec<Commit>,
+    pub head_commit: Option<Commit>,
+    pub pusher: Pusher,
+    pub repository: Repository,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<User>,
+}
+
+/// Payload of the `pull_request` and `pull_request_target` events.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PullRequestEvent {
+    pub action: String,
+    pub number: u64,
+    pub pull_request: PullRequest,
+    /// Label that was added or removed, for the `labeled` and `unlabeled` actions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<Label>,
+    pub repository: Repository,
+    pub sender: User,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IssuesEvent {
+    pub action: String,
+    pub issue: Issue,
+    /// Label that was added or removed, for the `labeled` and `unlabeled` actions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<Label>,
+    pub repository: Repository,
+    pub sender: User,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IssueCommentEvent {
+    pub action: String,
+    pub issue: Issue,
+    pub comment: Comment,
+    pub repository: Repository,
+    pub sender: User,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ReleaseEvent {
+    pub action: String,
+    pub release: Release,
+    pub repository: Repository,
+    pub sender: User,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WorkflowDispatchEvent {
+    /// Inputs of the dispatch, as strings or booleans.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub inputs: Map,
+    #[serde(rename = "ref")]
+    pub r#ref: String,
+    /// Path of the workflow file.
+    pub workflow: String,
+    pub repository: Repository,
+    pub sender: User,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WorkflowRunEvent {
+    pub action: String,
+    pub workflow_run: WorkflowRun,
+    pub repository: Repository,
+    pub sender: User,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ScheduleEvent {
+    /// Cron expression of the schedule that triggered the run.
+    pub schedule: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<Repository>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MergeGroupEvent {
+    pub action: String,
+    pub merge_group: MergeGroup,
+    pub repository: Repository,
+    pub sender: User,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RepositoryDispatchEvent {
+    /// Event type of the dispatch.
+    pub action: String,
+    pub branch: String,
+    #[serde(default)]
+    pub client_payload: serde_json::Value,
+    pub repository: Repository,
+    pub sender: User,
+}
+
+/// Payload of the event that triggered the workflow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Push(Box<PushEvent>),
+    PullRequest(Box<PullRequestEvent>),
+    PullRequestTarget(Box<PullRequestEvent>),
+    Issues(Box<IssuesEvent>),
+    IssueComment(Box<IssueCommentEvent>),
+    Release(Box<ReleaseEvent>),
+    WorkflowDispatch(Box<WorkflowDispatchEvent>),
+    WorkflowRun(Box<WorkflowRunEvent>),
+    Schedule(Box<ScheduleEvent>),
+    MergeGroup(Box<MergeGroupEvent>),
+    RepositoryDispatch(Box<RepositoryDispatchEvent>),
+    /// Any other event, or a payload that is not available.
+    Unknown(serde_json::Value),
+}
+
+impl Default for Event {
+    fn default() -> Self {
+        Self::Unknown(serde_json::Value::Object(Map::new()))
+    }
+}
+
+impl Event {
+    /// Deserializes the payload of the event named `name` (`GITHUB_EVENT_NAME`).
+    ///
+    /// Payloads of unknown events, and payloads that do not match the typed
+    /// struct of their event, are kept as [`Event::Unknown`].
+    #[must_use]
+    pub fn from_value(name: &str, payload: serde_json::Value) -> Self {
+        fn typed<T: serde::de::DeserializeOwned>(
+            name: &str,
+            payload: serde_json::Value,
+            event: fn(T) -> Event,
+        ) -> Event {
+            match T::deserialize(&payload) {
+                Ok(typed) => event(typed),
+                Err(err) => {
+                    action_core::debug!("failed to parse the payload of the {name} event: {err}");
+                    Event::Unknown(payload)
+                }
+            }
+        }
+        match name {
+            "push" => typed(name, payload, Self::Push),
+            "pull_request" => typed(name, payload, Self::PullRequest),
+            "pull_request_target" => typed(name, payload, Self::PullRequestTarget),
+            "issues" => typed(name, payload, Self::Issues),
+            "issue_comment" => typed(name, payload, Self::IssueComment),
+            "release" => typed(name, payload, Self::Release),
+            "workflow_dispatch" => typed(name, payload, Self::WorkflowDispatch),
+            "workflow_run" => typed(name, payload, Self::WorkflowRun),
+            "schedule" => typed(name, payload, Self::Schedule),
+            "merge_group" => typed(name, payload, Self::MergeGroup),
+            "repository_dispatch" => typed(name, payload, Self::RepositoryDispatch),
+            _ => Self::Unknown(payload),
+        }
+    }
+
+    /// Repository of the event, if the payload contains one.
+    #[must_use]
+    pub fn repository(&self) -> Option<&Repository> {
+        match self {
+            Self::Push(event) => Some(&event.repository),
+            Self::PullRequest(event) | Self::PullRequestTarget(event) => Some(&event.repository),
+            Self::Issues(event) => Some(&event.repository),
+            Self::IssueComment(event) => Some(&event.repository),
+            Self::Release(event) => Some(&event.repository),
+            Self::WorkflowDispatch(event) => Some(&event.repository),
+            Self::WorkflowRun(event) => Some(&event.repository),
+            Self::Schedule(event) => event.repository.as_ref(),
+            Self::MergeGroup(event) => Some(&event.repository),
+            Self::RepositoryDispatch(event) => Some(&event.repository),
+            Self::Unknown(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Event;
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    fn fixture(name: &str) -> color_eyre::eyre::Result<Event> {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join(format!("{name}.json"));
+        let payload = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        Ok(Event::from_value(name, payload))
+    }
+
+    #[test]
+    fn test_push() -> color_eyre::eyre::Result<()> {
+        let Event::Push(event) = fixture("push")? else {
+            panic!("expected push event");
+        };
+        sim_assert_eq!(event.r#ref, "refs/heads/main");
+        sim_assert_eq!(event.commits.len(), 1);
+        sim_assert_eq!(
+            event.head_commit.map(|commit| commit.author.name),
+            Some("Monalisa Octocat".to_string())
+        );
+        sim_assert_eq!(event.repository.full_name, "octo-org/octo-repo");
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_request() -> color_eyre::eyre::Result<()> {
+        let Event::PullRequest(event) = fixture("pull_request")? else {
+            panic!("expected pull_request event");
+        };
+        sim_assert_eq!(event.action, "opened");
+        sim_assert_eq!(event.number, 42);
+        sim_assert_eq!(event.pull_request.head.r#ref, "feature");
+        sim_assert_eq!(event.pull_request.base.r#ref, "main");
+        sim_assert_eq!(
+            event.pull_request.head.repo.map(|repo| repo.owner.login),
+            Some("octocat".to_string())
+        );
+
+        let payload = serde_json::from_str(include_str!("../fixtures/pull_request.json"))?;
+        let Event::PullRequestTarget(target) = Event::from_value("pull_request_target", payload)
+        else {
+            panic!("expected pull_request_target event");
+        };
+        sim_assert_eq!(target.number, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn test_issues() -> color_eyre::eyre::Result<()> {
+        let Event::Issues(event) = fixture("issues")? else {
+            panic!("expected issues event");
+        };
+        sim_assert_eq!(event.action, "labeled");
+        sim_assert_eq!(event.issue.number, 7);
+        sim_assert_eq!(event.label.map(|label| label.name), Some("bug".to_string()));
+
+        let Event::IssueComment(event) = fixture("issue_comment")? else {
+            panic!("expected issue_comment event");
+        };
+        sim_assert_eq!(event.comment.body, "/deploy staging");
+        assert!(event.issue.pull_request.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_release() -> color_eyre::eyre::Result<()> {
+        let Event::Release(event) = fixture("release")? else {
+            panic!("expected release event");
+        };
+        sim_assert_eq!(event.release.tag_name, "v1.0.0");
+        assert!(!event.release.prerelease);
+        Ok(())
+    }
+
+    #[test]
+    fn test_workflow_events() -> color_eyre::eyre::Result<()> {
+        let Event::WorkflowDispatch(event) = fixture("workflow_dispatch")? else {
+            panic!("expected workflow_dispatch event");
+        };
+        sim_assert_eq!(event.inputs["environment"], "staging");
+        sim_assert_eq!(event.inputs["dry-run"], true);
+
+        let payload = serde_json::from_str(include_str!(
+            "../fixtures/workflow_dispatch_without_inputs.json"
+        ))?;
+        let Event::WorkflowDispatch(event) = Event::from_value("workflow_dispatch", payload) else {
+            panic!("expected workflow_dispatch event");
+        };
+        assert!(event.inputs.is_empty());
+        sim_assert_eq!(event.r#ref, "refs/tags/v1.0.0");
+
+        let Event::WorkflowRun(event) = fixture("workflow_run")? else {
+            panic!("expected workflow_run event");
+        };
+        sim_assert_eq!(event.workflow_run.conclusion.as_deref(), Some("success"));
+
+        let Event::Schedule(event) = fixture("schedule")? else {
+            panic!("expected schedule event");
+        };
+        sim_assert_eq!(event.schedule, "0 0 * * 1");
+
+        let Event::MergeGroup(event) = fixture("merge_group")? else {
+            panic!("expected merge_group event");
+        };
+        sim_assert_eq!(event.merge_group.base_ref, "refs/heads/main");
+
+        let Event::RepositoryDispatch(event) = fixture("repository_dispatch")? else {
+            panic!("expected repository_dispatch event");
+        };
+        sim_assert_eq!(event.action, "deploy");
+        sim_assert_eq!(event.client_payload["version"], "1.2.3");
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown() {
+        let payload = serde_json::json!({ "action": "created", "star": true });
+        sim_assert_eq!(
+            Event::from_value("star", payload.clone()),
+            Event::Unknown(payload)
+        );
+        sim_assert_eq!(
+            Event::from_value("push", serde_json::json!({})),
+            Event::Unknown(serde_json::json!({}))
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! Queries and mutations of the GraphQL API.
+//!
+//! See <https://docs.github.com/en/graphql>.
+
+use crate::client::{Client, ClientError};
+use futures::{Stream, StreamExt, TryStreamExt, stream};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Position of an error in the query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Location {
+    pub line: u64,
+    pub column: u64,
+}
+
+/// Error reported in the `errors` of a GraphQL response.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GraphQlError {
+    pub message: String,
+    /// Kind of the error, e.g. `NOT_FOUND` or `FORBIDDEN`.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// Path of the field that failed, e.g. `["repository", "pullRequest"]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<Location>,
+}
+
+pub(crate) fn messages(errors: &[GraphQlError]) -> String {
+    errors
+        .iter()
+        .map(|error| error.message.as_str())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[derive(Serialize)]
+struct Request<'a, V: ?Sized> {
+    query: &'a str,
+    variables: &'a V,
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    pub has_next_page: bool,
+    pub end_cursor: Option<String>,
+}
+
+/// Page of a connection, queried with `nodes { ... } pageInfo { hasNextPage endCursor }`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Connection<T> {
+    pub nodes: Vec<T>,
+    pub page_info: PageInfo,
+}
+
+impl Client {
+    /// Sends a query or mutation with `variables` and deserializes its `data` into `T`.
+    ///
+    /// # Errors
+    /// If the request fails or the response contains `errors`.
+    pub async fn graphql<T, V>(&self, query: &str, variables: &V) -> Result<T, ClientError>
+    where
+        T: DeserializeOwned,
+        V: Serialize + ?Sized,
+    {
+        let request = action_http_client::Client::json_request(
+            action_http_client::Method::POST,
+            self.graphql_url().clone(),
+            &Request { query, variables },
+        )?;
+        let response: Response<T> = self.send_json(request).await?;
+        match response {
+            Response { errors, .. } if !errors.is_empty() => Err(ClientError::GraphQl(errors)),
+            Response {
+                data: Some(data), ..
+            } => Ok(data),
+            Response { data: None, .. } => Err(ClientError::GraphQl(vec![GraphQlError {
+                message: "response contains no data".to_string(),
+                kind: None,
+                path: Vec::new(),
+                locations: Vec::new(),
+            }])),
+        }
+    }
+
+    /// Lazily queries all pages of a connection.
+    ///
+    /// The query must declare a `$cursor: String` variable and pass it as the `after`
+    /// argument of the connection, which `connection` selects from the `data` of a response.
+    /// The cursor is set in `variables` for every page after the first.
+    ///
+    /// ```graphql
+    /// query($owner: String!, $repo: String!, $cursor: String) {
+    ///   repository(owner: $owner, name: $repo) {
+    ///     labels(first: 100, after: $cursor) {
+    ///       nodes { name }
+    ///       pageInfo { hasNextPage endCursor }
+    ///     }
+    ///   }
+    /// }
+    /// ```
+    pub fn graphql_paginate<'a, R, T, F>(
+        &'a self,
+        query: &'a str,
+        variables: serde_json::Map<String, serde_json::Value>,
+        connection: F,
+    ) -> impl Stream<Item = Result<T, ClientError>> + 'a
+    where
+        R: DeserializeOwned + 'a,
+        T: 'a,
+        F: FnMut(R) -> Connection<T> + 'a,
+    {
+        let state = (Some(variables), connection);
+        stream::try_unfold(state, move |(variables, mut connection)| async move {
+            let Some(mut variables) = variables else {
+                return Ok::<_, ClientError>(None);
+            };
+            let data: R = self.graphql(query, &variables).await?;
+            let Connection { nodes, page_info } = connection(data);
+            let next = match page_info.end_cursor {
+                Some(cursor) if page_info.has_next_page => {
+                    variables.insert("cursor".to_string(), cursor.into());
+                    Some(variables)
+                }
+                _ => None,
+            };
+            Ok(Some((stream::iter(nodes).map(Ok), (next, connection))))
+        })
+        .try_flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Connection;
+    use crate::client::{ClientError, tests::mock};
+    use futures::TryStreamExt;
+    use serde::Deserialize;
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    const QUERY: &str = "query($owner: String!, $repo: String!, $cursor: String) {
+  repository(owner: $owner, name: $repo) {
+    labels(first: 2, after: $cursor) { nodes { name } pageInfo { hasNextPage endCursor } }
+  }
+}";
+
+    #[derive(Debug, Deserialize)]
+    struct Data {
+        repository: Repository,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Repository {
+        labels: Connection<Label>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Label {
+        name: String,
+    }
+
+    fn page(names: &[&str], cursor: Option<&str>) -> serde_json::Value {
+        let nodes: Vec<_> = names
+            .iter()
+            .map(|name| serde_json::json!({ "name": name }))
+            .collect();
+        serde_json::json!({ "data": { "repository": { "labels": {
+            "nodes": nodes,
+            "pageInfo": { "hasNextPage": cursor.is_some(), "endCursor": cursor },
+        }}}})
+    }
+
+    #[tokio::test]
+    async fn test_paginate() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        Mock::given(method("POST"))
+            .and(path("/api/graphql"))
+            .and(body_partial_json(serde_json::json!({
+                "variables": { "owner": "octo-org", "cursor": "Y3Vyc29yOjI=" }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(page(&["question"], None)))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/graphql"))
+            .and(body_partial_json(serde_json::json!({ "query": QUERY })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(page(&["bug", "enhancement"], Some("Y3Vyc29yOjI="))),
+            )
+            .mount(&server)
+            .await;
+
+        let variables = serde_json::json!({ "owner": "octo-org", "repo": "octo-repo" });
+        let serde_json::Value::Object(variables) = variables else {
+            unreachable!()
+        };
+        let labels: Vec<Label> = client
+            .graphql_paginate(QUERY, variables, |data: Data| data.repository.labels)
+            .try_collect()
+            .await?;
+        sim_assert_eq!(
+            labels
+                .iter()
+                .map(|label| label.name.as_str())
+                .collect::<Vec<_>>(),
+            ["bug", "enhancement", "question"]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_errors() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        Mock::given(method("POST"))
+            .and(path("/api/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": { "repository": null },
+                "errors": [{
+                    "type": "NOT_FOUND",
+                    "path": ["repository"],
+                    "locations": [{ "line": 1, "column": 3 }],
+                    "message": "Could not resolve to a Repository with the name 'octo-org/missing'."
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let err = client
+            .graphql::<serde_json::Value, _>(
+                "{ repository(owner: \"octo-org\", name: \"missing\") { id } }",
+                &serde_json::json!({}),
+            )
+            .await
+            .unwrap_err();
+        let ClientError::GraphQl(errors) = &err else {
+            color_eyre::eyre::bail!("expected a GraphQL error, got {err:?}");
+        };
+        sim_assert_eq!(errors[0].kind.as_deref(), Some("NOT_FOUND"));
+        sim_assert_eq!(
+            err.to_string(),
+            "GraphQL request failed: Could not resolve to a Repository with the name 'octo-org/missing'."
+        );
+        Ok(())
+    }
+}
@@ -1,15 +1,13 @@
-#[must_use]
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
-}
+pub mod client;
+pub mod context;
+pub mod event;
+pub mod graphql;
+pub mod paginate;
+pub mod rate_limit;
+pub mod rest;
+pub mod sticky;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use client::{Client, ClientBuilder, ClientError};
+pub use context::{Context, ContextError};
+pub use event::Event;
+pub use rate_limit::RateLimitPolicy;
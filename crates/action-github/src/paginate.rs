@@ -0,0 +1,185 @@
+//! Pagination of list endpoints through the `Link` header.
+//!
+//! See <https://docs.github.com/en/rest/using-the-rest-api/using-pagination-in-the-rest-api>.
+
+use crate::client::{Client, ClientError};
+use action_http_client::header::{self, HeaderMap};
+use action_http_client::{Method, Request, Url};
+use futures::{Stream, StreamExt, TryStreamExt, stream};
+use serde::de::DeserializeOwned;
+
+/// Number of items requested per page, the maximum supported by the API.
+pub const PER_PAGE: &str = "100";
+
+/// Keys of list responses wrapping the items in an object, besides the items themselves.
+const NAMESPACE_KEYS: &[&str] = &[
+    "total_count",
+    "incomplete_results",
+    "repository_selection",
+    "total_commits",
+];
+
+/// URL of the next page from a `Link` header such as `<https://...?page=2>; rel="next"`.
+#[must_use]
+pub fn next_link(headers: &HeaderMap) -> Option<Url> {
+    headers
+        .get_all(header::LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|link| {
+            let mut parts = link.split(';').map(str::trim);
+            let url = parts.next()?.strip_prefix('<')?.strip_suffix('>')?;
+            parts
+                .any(|param| param.replace(' ', "") == r#"rel="next""#)
+                .then(|| Url::parse(url).ok())
+                .flatten()
+        })
+}
+
+/// Items of a page, either a JSON array or an object with a single array
+/// besides counters, e.g. `{"total_count": 2, "workflow_runs": [...]}`.
+fn items(page: serde_json::Value) -> Option<Vec<serde_json::Value>> {
+    match page {
+        serde_json::Value::Array(items) => Some(items),
+        serde_json::Value::Object(object) => {
+            let mut arrays = object
+                .into_iter()
+                .filter(|(key, _)| !NAMESPACE_KEYS.contains(&key.as_str()));
+            match (arrays.next(), arrays.next()) {
+                (Some((_, serde_json::Value::Array(items))), None) => Some(items),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+impl Client {
+    async fn page<T>(&self, url: Url) -> Result<(Vec<T>, Option<Url>), ClientError>
+    where
+        T: DeserializeOwned,
+    {
+        let response = self.send(Request::new(Method::GET, url)).await?;
+        let next = next_link(response.headers());
+        let url = response.url().to_string();
+        let body = response
+            .bytes()
+            .await
+            .map_err(action_http_client::Error::from)?;
+        let deserialize_error = |source| ClientError::Deserialize {
+            url: url.clone(),
+            source,
+        };
+        let page = serde_json::from_slice(&body).map_err(deserialize_error)?;
+        let items = items(page).ok_or_else(|| {
+            deserialize_error(serde::de::Error::custom(
+                "expected an array of items or an object containing one",
+            ))
+        })?;
+        let items = items
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<_, _>>()
+            .map_err(deserialize_error)?;
+        Ok((items, next))
+    }
+
+    /// Lazily requests all pages of a list endpoint, following the `Link` header.
+    ///
+    /// Requests [`PER_PAGE`] items per page unless `url` specifies `per_page`.
+    pub fn paginate<T>(&self, mut url: Url) -> impl Stream<Item = Result<T, ClientError>> + '_
+    where
+        T: DeserializeOwned + 'static,
+    {
+        if !url.query_pairs().any(|(key, _)| key == "per_page") {
+            url.query_pairs_mut().append_pair("per_page", PER_PAGE);
+        }
+        stream::try_unfold(Some(url), move |next| async move {
+            let Some(url) = next else {
+                return Ok(None);
+            };
+            let (items, next) = self.page(url).await?;
+            Ok::<_, ClientError>(Some((stream::iter(items).map(Ok), next)))
+        })
+        .try_flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{items, next_link};
+    use crate::client::tests::mock;
+    use action_http_client::header::{HeaderMap, HeaderValue, LINK};
+    use futures::TryStreamExt;
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, ResponseTemplate};
+
+    #[test]
+    fn test_next_link() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            LINK,
+            HeaderValue::from_static(
+                r#"<https://api.github.com/repositories/1/issues?page=1>; rel="prev", <https://api.github.com/repositories/1/issues?page=3>; rel="next", <https://api.github.com/repositories/1/issues?page=5>; rel="last""#,
+            ),
+        );
+        sim_assert_eq!(
+            next_link(&headers).map(String::from),
+            Some("https://api.github.com/repositories/1/issues?page=3".to_string())
+        );
+        headers.insert(
+            LINK,
+            HeaderValue::from_static(
+                r#"<https://api.github.com/repositories/1/issues?page=1>; rel="first""#,
+            ),
+        );
+        sim_assert_eq!(next_link(&headers), None);
+    }
+
+    #[test]
+    fn test_items() {
+        sim_assert_eq!(
+            items(serde_json::json!({ "total_count": 1, "workflow_runs": [1] })),
+            Some(vec![serde_json::json!(1)])
+        );
+        sim_assert_eq!(
+            items(serde_json::json!([1, 2])).map(|items| items.len()),
+            Some(2)
+        );
+        sim_assert_eq!(items(serde_json::json!({ "a": [1], "b": [2] })), None);
+    }
+
+    #[tokio::test]
+    async fn test_paginate() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        let url = client.endpoint(["repos", "octo-org", "octo-repo", "actions", "runs"]);
+        let next = format!("{url}?per_page=100&page=2");
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/octo-org/octo-repo/actions/runs"))
+            .and(query_param("page", "2"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "total_count": 3, "workflow_runs": [3] })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/octo-org/octo-repo/actions/runs"))
+            .and(query_param("per_page", "100"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("link", format!(r#"<{next}>; rel="next""#))
+                    .set_body_json(
+                        serde_json::json!({ "total_count": 3, "workflow_runs": [1, 2] }),
+                    ),
+            )
+            .mount(&server)
+            .await;
+
+        let runs: Vec<u64> = client.paginate::<u64>(url).try_collect().await?;
+        sim_assert_eq!(runs, [1, 2, 3]);
+        Ok(())
+    }
+}
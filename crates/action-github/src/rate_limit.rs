@@ -0,0 +1,152 @@
+//! Handling of rate limited requests.
+//!
+//! See <https://docs.github.com/en/rest/using-the-rest-api/best-practices-for-using-the-rest-api#handle-rate-limit-errors-appropriately>.
+
+use action_http_client::StatusCode;
+use action_http_client::header::HeaderMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How rate limited requests are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitPolicy {
+    /// Maximum number of retries of a request.
+    pub max_retries: u32,
+    /// Initial delay of the exponential backoff, used if the response does not tell how long to wait.
+    pub backoff: Duration,
+    /// Maximum time to wait before a retry, requests that would have to wait longer fail.
+    pub max_wait: Duration,
+}
+
+impl Default for RateLimitPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            backoff: Duration::from_secs(60),
+            max_wait: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+impl RateLimitPolicy {
+    /// Never retries rate limited requests.
+    #[must_use]
+    pub fn disabled() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Time to wait before retrying a request that failed with `status`,
+    /// or `None` if it was not rate limited or must not be retried.
+    ///
+    /// - `Retry-After` is honored for secondary rate limits.
+    /// - An exhausted primary rate limit (`x-ratelimit-remaining: 0`) waits until `x-ratelimit-reset`.
+    /// - Other secondary rate limits back off exponentially, starting at [`RateLimitPolicy::backoff`].
+    #[must_use]
+    pub fn delay(
+        &self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        message: &str,
+        attempt: u32,
+        now: SystemTime,
+    ) -> Option<Duration> {
+        if attempt >= self.max_retries
+            || !matches!(
+                status,
+                StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS
+            )
+        {
+            return None;
+        }
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+        let delay = if let Some(seconds) = header("retry-after") {
+            Duration::from_secs(seconds)
+        } else if header("x-ratelimit-remaining") == Some(0) {
+            let reset = UNIX_EPOCH + Duration::from_secs(header("x-ratelimit-reset")?);
+            // the reset time has a resolution of one second
+            reset.duration_since(now).unwrap_or_default() + Duration::from_secs(1)
+        } else if status == StatusCode::TOO_MANY_REQUESTS
+            || message
+                .to_ascii_lowercase()
+                .contains("secondary rate limit")
+        {
+            self.backoff.saturating_mul(2u32.saturating_pow(attempt))
+        } else {
+            return None;
+        };
+        (delay <= self.max_wait).then_some(delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateLimitPolicy;
+    use action_http_client::StatusCode;
+    use action_http_client::header::{HeaderMap, HeaderValue};
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn headers(headers: &[(&'static str, &'static str)]) -> HeaderMap {
+        headers
+            .iter()
+            .map(|(name, value)| (name.parse().unwrap(), HeaderValue::from_static(value)))
+            .collect()
+    }
+
+    #[test]
+    fn test_delay() {
+        let policy = RateLimitPolicy::default();
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let delay = |status, headers: &HeaderMap, message, attempt| {
+            policy.delay(status, headers, message, attempt, now)
+        };
+
+        let retry_after = headers(&[("retry-after", "30")]);
+        sim_assert_eq!(
+            delay(StatusCode::FORBIDDEN, &retry_after, "", 0),
+            Some(Duration::from_secs(30))
+        );
+        sim_assert_eq!(delay(StatusCode::FORBIDDEN, &retry_after, "", 3), None);
+        sim_assert_eq!(delay(StatusCode::NOT_FOUND, &retry_after, "", 0), None);
+
+        let exhausted = headers(&[
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", "1700000120"),
+        ]);
+        sim_assert_eq!(
+            delay(StatusCode::FORBIDDEN, &exhausted, "", 0),
+            Some(Duration::from_secs(121))
+        );
+        let exhausted = headers(&[
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", "1700003600"),
+        ]);
+        sim_assert_eq!(delay(StatusCode::FORBIDDEN, &exhausted, "", 0), None);
+
+        let secondary = "You have exceeded a secondary rate limit.";
+        sim_assert_eq!(
+            delay(StatusCode::FORBIDDEN, &HeaderMap::new(), secondary, 1),
+            Some(Duration::from_secs(120))
+        );
+        sim_assert_eq!(
+            delay(StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new(), "", 0),
+            Some(Duration::from_secs(60))
+        );
+        sim_assert_eq!(
+            delay(
+                StatusCode::FORBIDDEN,
+                &HeaderMap::new(),
+                "Resource not accessible by integration",
+                0
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,11 @@
+//! Typed endpoints of the REST API.
+//!
+//! Each group of endpoints is accessed through a handler borrowed from the [`Client`](crate::Client)
+//! for a repository, e.g. `client.issues(owner, repo)`.
+
+pub mod actions;
+pub mod checks;
+pub mod contents;
+pub mod issues;
+pub mod pulls;
+pub mod releases;
@@ -0,0 +1,52 @@
+//! Workflow runs of GitHub Actions.
+
+use crate::client::{Client, ClientError};
+use crate::event::WorkflowRun;
+use futures::Stream;
+
+/// Endpoints for the workflows of a repository.
+#[derive(Debug, Clone, Copy)]
+pub struct Actions<'a> {
+    client: &'a Client,
+    owner: &'a str,
+    repo: &'a str,
+}
+
+impl Client {
+    #[must_use]
+    pub fn actions<'a>(&'a self, owner: &'a str, repo: &'a str) -> Actions<'a> {
+        Actions {
+            client: self,
+            owner,
+            repo,
+        }
+    }
+}
+
+impl Actions<'_> {
+    fn endpoint(&self, path: &[&str]) -> action_http_client::Url {
+        let segments = ["repos", self.owner, self.repo, "actions"];
+        self.client.endpoint(segments.iter().chain(path))
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn get_workflow_run(&self, id: u64) -> Result<WorkflowRun, ClientError> {
+        self.client
+            .get(self.endpoint(&["runs", &id.to_string()]))
+            .await
+    }
+
+    /// Lists the runs of a workflow, identified by its id or file name such as `ci.yml`,
+    /// or of all workflows if `None`, most recent first.
+    pub fn list_workflow_runs(
+        &self,
+        workflow: Option<&str>,
+    ) -> impl Stream<Item = Result<WorkflowRun, ClientError>> + use<'_> {
+        let url = match workflow {
+            Some(workflow) => self.endpoint(&["workflows", workflow, "runs"]),
+            None => self.endpoint(&["runs"]),
+        };
+        self.client.paginate(url)
+    }
+}
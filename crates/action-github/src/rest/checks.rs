@@ -0,0 +1,488 @@
+//! Check runs reporting the result of a tool on a commit.
+
+use crate::client::{Client, ClientError};
+use action_core::{AnnotationProperties, LogLevel};
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of annotations the API accepts per request.
+pub const MAX_ANNOTATIONS_PER_REQUEST: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Queued,
+    InProgress,
+    Completed,
+    Waiting,
+    Requested,
+    Pending,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Conclusion {
+    ActionRequired,
+    Cancelled,
+    Failure,
+    Neutral,
+    Success,
+    Skipped,
+    Stale,
+    TimedOut,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationLevel {
+    Notice,
+    Warning,
+    Failure,
+}
+
+impl From<LogLevel> for AnnotationLevel {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => Self::Failure,
+            LogLevel::Warning => Self::Warning,
+            LogLevel::Notice | LogLevel::Debug => Self::Notice,
+        }
+    }
+}
+
+/// Annotation of a line range of a file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Annotation {
+    /// Path of the file relative to the root of the repository.
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Only allowed if `start_line` and `end_line` are equal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_column: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_column: Option<usize>,
+    pub annotation_level: AnnotationLevel,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_details: Option<String>,
+}
+
+impl Annotation {
+    /// Creates an annotation from the same arguments as [`action_core::issue_level`].
+    ///
+    /// The annotation spans the first line if the properties have no lines,
+    /// columns are dropped unless it spans a single line.
+    /// Returns `None` if the properties have no file, which a check run cannot annotate.
+    #[must_use]
+    pub fn new(
+        level: impl Into<AnnotationLevel>,
+        message: impl Into<String>,
+        props: AnnotationProperties,
+    ) -> Option<Self> {
+        let start_line = props.start_line.unwrap_or(1);
+        let end_line = props.end_line.unwrap_or(start_line).max(start_line);
+        let single_line = start_line == end_line;
+        Some(Self {
+            path: props.file?,
+            start_line,
+            end_line,
+            start_column: props.start_column.filter(|_| single_line),
+            end_column: props.end_column.filter(|_| single_line),
+            annotation_level: level.into(),
+            message: message.into(),
+            title: props.title,
+            raw_details: None,
+        })
+    }
+}
+
+/// Output shown on the page of a check run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Output {
+    pub title: String,
+    /// Markdown summary, at most 65535 characters.
+    pub summary: String,
+    /// Markdown details, at most 65535 characters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Annotations added to the check run, at most [`MAX_ANNOTATIONS_PER_REQUEST`] per request.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CheckRun {
+    pub id: u64,
+    pub name: String,
+    pub head_sha: String,
+    pub status: Status,
+    pub conclusion: Option<Conclusion>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CreateCheckRun {
+    pub name: String,
+    pub head_sha: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<Status>,
+    /// Required if `status` is [`Status::Completed`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conclusion: Option<Conclusion>,
+    /// ISO 8601 timestamp, e.g. `2011-01-26T19:01:12Z`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
+    /// ISO 8601 timestamp, e.g. `2011-01-26T19:01:12Z`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<Output>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct UpdateCheckRun {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<Status>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conclusion: Option<Conclusion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<Output>,
+}
+
+/// Endpoints for the check runs of a repository.
+#[derive(Debug, Clone, Copy)]
+pub struct Checks<'a> {
+    client: &'a Client,
+    owner: &'a str,
+    repo: &'a str,
+}
+
+impl Client {
+    #[must_use]
+    pub fn checks<'a>(&'a self, owner: &'a str, repo: &'a str) -> Checks<'a> {
+        Checks {
+            client: self,
+            owner,
+            repo,
+        }
+    }
+}
+
+impl Checks<'_> {
+    fn endpoint(&self, path: &[&str]) -> action_http_client::Url {
+        let segments = ["repos", self.owner, self.repo, "check-runs"];
+        self.client.endpoint(segments.iter().chain(path))
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn get(&self, id: u64) -> Result<CheckRun, ClientError> {
+        self.client.get(self.endpoint(&[&id.to_string()])).await
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn create(&self, check_run: &CreateCheckRun) -> Result<CheckRun, ClientError> {
+        self.client.post(self.endpoint(&[]), check_run).await
+    }
+
+    /// Creates a check run with any number of annotations.
+    ///
+    /// Annotations already in the output of `check_run` come first.
+    /// The check run is created with the first [`MAX_ANNOTATIONS_PER_REQUEST`] annotations,
+    /// the remaining ones are added by updating it, see [`Checks::annotate`].
+    ///
+    /// # Errors
+    /// If there are annotations but `check_run` has no output, or a request fails.
+    pub async fn create_with_annotations(
+        &self,
+        check_run: &CreateCheckRun,
+        annotations: Vec<Annotation>,
+    ) -> Result<CheckRun, ClientError> {
+        let mut output = match (&check_run.output, annotations.is_empty()) {
+            (Some(output), _) => output.clone(),
+            (None, true) => return self.create(check_run).await,
+            (None, false) => return Err(ClientError::AnnotationsWithoutOutput),
+        };
+        let mut annotations = std::mem::take(&mut output.annotations)
+            .into_iter()
+            .chain(annotations);
+        output.annotations = annotations
+            .by_ref()
+            .take(MAX_ANNOTATIONS_PER_REQUEST)
+            .collect();
+        let created = self
+            .create(&CreateCheckRun {
+                output: Some(output.clone()),
+                ..check_run.clone()
+            })
+            .await?;
+        self.annotate(created.id, &output, annotations.collect())
+            .await?;
+        Ok(created)
+    }
+
+    /// Adds any number of annotations to a check run,
+    /// in batches of [`MAX_ANNOTATIONS_PER_REQUEST`].
+    ///
+    /// Each request replaces the title, summary and text of the check run with `output`.
+    ///
+    /// # Errors
+    /// If a request fails.
+    pub async fn annotate(
+        &self,
+        id: u64,
+        output: &Output,
+        annotations: Vec<Annotation>,
+    ) -> Result<(), ClientError> {
+        for batch in annotations.chunks(MAX_ANNOTATIONS_PER_REQUEST) {
+            let update = UpdateCheckRun {
+                output: Some(Output {
+                    annotations: batch.to_vec(),
+                    ..output.clone()
+                }),
+                ..UpdateCheckRun::default()
+            };
+            self.update(id, &update).await?;
+        }
+        Ok(())
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn update(
+        &self,
+        id: u64,
+        check_run: &UpdateCheckRun,
+    ) -> Result<CheckRun, ClientError> {
+        self.client
+            .patch(self.endpoint(&[&id.to_string()]), check_run)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Annotation, AnnotationLevel, Conclusion, CreateCheckRun, Output, Status};
+    use crate::client::ClientError;
+    use crate::client::tests::mock;
+    use action_core::{AnnotationProperties, LogLevel};
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_create() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        Mock::given(method("POST"))
+            .and(path("/api/v3/repos/octo-org/octo-repo/check-runs"))
+            .and(body_json(serde_json::json!({
+                "name": "lint",
+                "head_sha": "ffac537e6cbbf934b08745a378932722df287a53",
+                "status": "completed",
+                "conclusion": "success",
+                "output": { "title": "No problems", "summary": "All files passed" },
+            })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+                "id": 4,
+                "name": "lint",
+                "head_sha": "ffac537e6cbbf934b08745a378932722df287a53",
+                "status": "completed",
+                "conclusion": "success",
+                "html_url": "https://github.com/octo-org/octo-repo/runs/4",
+            })))
+            .mount(&server)
+            .await;
+
+        let check_run = client
+            .checks("octo-org", "octo-repo")
+            .create(&CreateCheckRun {
+                name: "lint".into(),
+                head_sha: "ffac537e6cbbf934b08745a378932722df287a53".into(),
+                status: Some(Status::Completed),
+                conclusion: Some(Conclusion::Success),
+                output: Some(Output {
+                    title: "No problems".into(),
+                    summary: "All files passed".into(),
+                    ..Output::default()
+                }),
+                ..CreateCheckRun::default()
+            })
+            .await?;
+        sim_assert_eq!(check_run.id, 4);
+        sim_assert_eq!(check_run.conclusion, Some(Conclusion::Success));
+        Ok(())
+    }
+
+    #[test]
+    fn test_annotation_from_properties() {
+        let props = AnnotationProperties {
+            title: Some("unused variable".into()),
+            file: Some("src/lib.rs".into()),
+            start_line: Some(3),
+            end_line: Some(5),
+            start_column: Some(1),
+            end_column: Some(4),
+        };
+        sim_assert_eq!(
+            Annotation::new(LogLevel::Error, "`x` is never used", props.clone()),
+            Some(Annotation {
+                path: "src/lib.rs".into(),
+                start_line: 3,
+                end_line: 5,
+                start_column: None,
+                end_column: None,
+                annotation_level: AnnotationLevel::Failure,
+                message: "`x` is never used".into(),
+                title: Some("unused variable".into()),
+                raw_details: None,
+            })
+        );
+        let single_line = AnnotationProperties {
+            end_line: None,
+            ..props.clone()
+        };
+        let annotation = Annotation::new(LogLevel::Warning, "", single_line);
+        sim_assert_eq!(annotation.and_then(|a| a.start_column), Some(1));
+        let without_file = AnnotationProperties {
+            file: None,
+            ..props
+        };
+        sim_assert_eq!(Annotation::new(LogLevel::Notice, "", without_file), None);
+    }
+
+    #[tokio::test]
+    async fn test_batched_annotations() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        let check_run = serde_json::json!({
+            "id": 4,
+            "name": "lint",
+            "head_sha": "ffac537e6cbbf934b08745a378932722df287a53",
+            "status": "completed",
+            "conclusion": "failure",
+        });
+        Mock::given(method("POST"))
+            .and(path("/api/v3/repos/octo-org/octo-repo/check-runs"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(&check_run))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/v3/repos/octo-org/octo-repo/check-runs/4"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&check_run))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let mut annotations: Vec<_> = (1..=120)
+            .filter_map(|line| {
+                let props = AnnotationProperties {
+                    file: Some("src/lib.rs".into()),
+                    start_line: Some(line),
+                    ..AnnotationProperties::default()
+                };
+                Annotation::new(LogLevel::Warning, format!("problem {line}"), props)
+            })
+            .collect();
+        // annotations already in the output are kept in front of the others
+        let existing = annotations.drain(..10).collect();
+        client
+            .checks("octo-org", "octo-repo")
+            .create_with_annotations(
+                &CreateCheckRun {
+                    name: "lint".into(),
+                    head_sha: "ffac537e6cbbf934b08745a378932722df287a53".into(),
+                    status: Some(Status::Completed),
+                    conclusion: Some(Conclusion::Failure),
+                    output: Some(Output {
+                        title: "120 problems".into(),
+                        summary: "Found 120 problems".into(),
+                        annotations: existing,
+                        ..Output::default()
+                    }),
+                    ..CreateCheckRun::default()
+                },
+                annotations,
+            )
+            .await?;
+
+        let batches = server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .iter()
+            .map(|request| {
+                let body: serde_json::Value = serde_json::from_slice(&request.body)?;
+                let output = &body["output"];
+                assert_eq!(output["title"], "120 problems");
+                let annotations = output["annotations"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default();
+                let first = annotations[0]["message"].as_str().map(String::from);
+                Ok((request.method.to_string(), annotations.len(), first))
+            })
+            .collect::<color_eyre::eyre::Result<Vec<_>>>()?;
+        sim_assert_eq!(
+            batches,
+            [
+                ("POST".to_string(), 50, Some("problem 1".to_string())),
+                ("PATCH".to_string(), 50, Some("problem 51".to_string())),
+                ("PATCH".to_string(), 20, Some("problem 101".to_string()))
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_annotations_without_output() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        let props = AnnotationProperties {
+            file: Some("src/lib.rs".into()),
+            start_line: Some(1),
+            ..AnnotationProperties::default()
+        };
+        let annotations = Annotation::new(LogLevel::Error, "problem", props)
+            .into_iter()
+            .collect();
+        let err = client
+            .checks("octo-org", "octo-repo")
+            .create_with_annotations(
+                &CreateCheckRun {
+                    name: "lint".into(),
+                    head_sha: "ffac537e6cbbf934b08745a378932722df287a53".into(),
+                    ..CreateCheckRun::default()
+                },
+                annotations,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ClientError::AnnotationsWithoutOutput));
+        sim_assert_eq!(
+            server.received_requests().await.unwrap_or_default().len(),
+            0
+        );
+        Ok(())
+    }
+}
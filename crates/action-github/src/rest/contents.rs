@@ -0,0 +1,221 @@
+//! Files and directories of a repository.
+
+use crate::client::{Client, ClientError};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    File,
+    Dir,
+    Symlink,
+    Submodule,
+}
+
+/// File, directory, symlink or submodule in a repository.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Content {
+    #[serde(rename = "type")]
+    pub kind: Kind,
+    pub name: String,
+    pub path: String,
+    pub sha: String,
+    pub size: u64,
+    /// Encoding of `content`, `base64` for files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    /// Content of a file, missing when listing a directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+}
+
+impl Content {
+    /// Decodes the content of a file.
+    ///
+    /// Returns `None` if the content is missing or not base64 encoded.
+    #[must_use]
+    pub fn decode(&self) -> Option<Vec<u8>> {
+        if self.encoding.as_deref() != Some("base64") {
+            return None;
+        }
+        // the API wraps the encoded content in lines of 60 characters
+        let content: String = self
+            .content
+            .as_deref()?
+            .chars()
+            .filter(|c| !c.is_ascii_whitespace())
+            .collect();
+        BASE64.decode(content).ok()
+    }
+}
+
+/// Response for a path, which is a single item or the entries of a directory.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Entry {
+    Item(Box<Content>),
+    Dir(Vec<Content>),
+}
+
+/// Creates or replaces a file with a commit.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct UpdateFile {
+    pub message: String,
+    /// Base64 encoded content, see [`UpdateFile::new`].
+    pub content: String,
+    /// Blob SHA of the replaced file, required when updating an existing file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha: Option<String>,
+    /// Branch to commit to, the default branch of the repository if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+}
+
+impl UpdateFile {
+    #[must_use]
+    pub fn new(message: impl Into<String>, content: impl AsRef<[u8]>) -> Self {
+        Self {
+            message: message.into(),
+            content: BASE64.encode(content),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GitCommit {
+    pub sha: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html_url: Option<String>,
+}
+
+/// Result of a change to a file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FileCommit {
+    /// The file, `None` if it was deleted.
+    pub content: Option<Content>,
+    pub commit: GitCommit,
+}
+
+/// Endpoints for the contents of a repository.
+#[derive(Debug, Clone, Copy)]
+pub struct Contents<'a> {
+    client: &'a Client,
+    owner: &'a str,
+    repo: &'a str,
+}
+
+impl Client {
+    #[must_use]
+    pub fn contents<'a>(&'a self, owner: &'a str, repo: &'a str) -> Contents<'a> {
+        Contents {
+            client: self,
+            owner,
+            repo,
+        }
+    }
+}
+
+impl Contents<'_> {
+    fn endpoint(&self, path: &str) -> action_http_client::Url {
+        let segments = ["repos", self.owner, self.repo, "contents"];
+        let path = path.split('/').filter(|segment| !segment.is_empty());
+        self.client.endpoint(segments.into_iter().chain(path))
+    }
+
+    /// Gets a file or lists a directory at `ref`, or the default branch if `None`.
+    ///
+    /// # Errors
+    /// If the request fails.
+    pub async fn get(&self, path: &str, r#ref: Option<&str>) -> Result<Entry, ClientError> {
+        let mut url = self.endpoint(path);
+        if let Some(r#ref) = r#ref {
+            url.query_pairs_mut().append_pair("ref", r#ref);
+        }
+        self.client.get(url).await
+    }
+
+    /// Creates or replaces a file.
+    ///
+    /// # Errors
+    /// If the request fails.
+    pub async fn put(&self, path: &str, file: &UpdateFile) -> Result<FileCommit, ClientError> {
+        self.client.put(self.endpoint(path), file).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Entry, Kind, UpdateFile};
+    use crate::client::tests::mock;
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use wiremock::matchers::{body_json, method, path, query_param};
+    use wiremock::{Mock, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_get_and_put() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        let file = serde_json::json!({
+            "type": "file",
+            "name": "README.md",
+            "path": "docs/README.md",
+            "sha": "3d21ec53a331a6f037a91c368710b99387d012c1",
+            "size": 12,
+            "encoding": "base64",
+            "content": "SGVsbG8s\nIHdvcmxk\n",
+        });
+        Mock::given(method("GET"))
+            .and(path(
+                "/api/v3/repos/octo-org/octo-repo/contents/docs/README.md",
+            ))
+            .and(query_param("ref", "main"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&file))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/api/v3/repos/octo-org/octo-repo/contents/docs/README.md"))
+            .and(body_json(serde_json::json!({
+                "message": "update readme",
+                "content": "SGVsbG8gYWdhaW4=",
+                "sha": "3d21ec53a331a6f037a91c368710b99387d012c1",
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "content": file,
+                "commit": { "sha": "7638417db6d59f3c431d3e1f261cc637155684cd", "message": "update readme" },
+            })))
+            .mount(&server)
+            .await;
+
+        let contents = client.contents("octo-org", "octo-repo");
+        let Entry::Item(content) = contents.get("docs/README.md", Some("main")).await? else {
+            color_eyre::eyre::bail!("expected a file");
+        };
+        sim_assert_eq!(content.kind, Kind::File);
+        sim_assert_eq!(
+            content.decode().as_deref(),
+            Some(b"Hello, world".as_slice())
+        );
+
+        let commit = contents
+            .put(
+                "docs/README.md",
+                &UpdateFile {
+                    sha: Some(content.sha.clone()),
+                    ..UpdateFile::new("update readme", "Hello again")
+                },
+            )
+            .await?;
+        sim_assert_eq!(
+            commit.commit.sha,
+            "7638417db6d59f3c431d3e1f261cc637155684cd"
+        );
+        Ok(())
+    }
+}
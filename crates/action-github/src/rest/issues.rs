@@ -0,0 +1,219 @@
+//! Issues, their labels and comments.
+//!
+//! Pull requests are issues as well, comments on a pull request are created here.
+
+use crate::client::{Client, ClientError};
+use crate::event::{Comment, Issue, Label};
+use futures::Stream;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CreateIssue {
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub assignees: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct UpdateIssue {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// `open` or `closed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    /// Replaces all labels of the issue.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+struct CommentBody<'a> {
+    body: &'a str,
+}
+
+/// Endpoints for the issues of a repository.
+#[derive(Debug, Clone, Copy)]
+pub struct Issues<'a> {
+    client: &'a Client,
+    owner: &'a str,
+    repo: &'a str,
+}
+
+impl Client {
+    #[must_use]
+    pub fn issues<'a>(&'a self, owner: &'a str, repo: &'a str) -> Issues<'a> {
+        Issues {
+            client: self,
+            owner,
+            repo,
+        }
+    }
+}
+
+impl Issues<'_> {
+    fn endpoint(&self, path: &[&str]) -> action_http_client::Url {
+        let segments = ["repos", self.owner, self.repo, "issues"];
+        self.client.endpoint(segments.iter().chain(path))
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn get(&self, number: u64) -> Result<Issue, ClientError> {
+        self.client.get(self.endpoint(&[&number.to_string()])).await
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn create(&self, issue: &CreateIssue) -> Result<Issue, ClientError> {
+        self.client.post(self.endpoint(&[]), issue).await
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn update(&self, number: u64, issue: &UpdateIssue) -> Result<Issue, ClientError> {
+        self.client
+            .patch(self.endpoint(&[&number.to_string()]), issue)
+            .await
+    }
+
+    /// Adds labels to an issue, returning all its labels.
+    ///
+    /// # Errors
+    /// If the request fails.
+    pub async fn add_labels(
+        &self,
+        number: u64,
+        labels: &[impl AsRef<str>],
+    ) -> Result<Vec<Label>, ClientError> {
+        let labels: Vec<&str> = labels.iter().map(AsRef::as_ref).collect();
+        self.client
+            .post(
+                self.endpoint(&[&number.to_string(), "labels"]),
+                &serde_json::json!({ "labels": labels }),
+            )
+            .await
+    }
+
+    /// Removes a label from an issue, returning its remaining labels.
+    ///
+    /// # Errors
+    /// If the request fails.
+    pub async fn remove_label(&self, number: u64, label: &str) -> Result<Vec<Label>, ClientError> {
+        let url = self.endpoint(&[&number.to_string(), "labels", label]);
+        self.client
+            .send_json(action_http_client::Request::new(
+                action_http_client::Method::DELETE,
+                url,
+            ))
+            .await
+    }
+
+    /// Lists all comments of an issue, oldest first.
+    pub fn list_comments(
+        &self,
+        number: u64,
+    ) -> impl Stream<Item = Result<Comment, ClientError>> + use<'_> {
+        let url = self.endpoint(&[&number.to_string(), "comments"]);
+        self.client.paginate(url)
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn get_comment(&self, id: u64) -> Result<Comment, ClientError> {
+        self.client
+            .get(self.endpoint(&["comments", &id.to_string()]))
+            .await
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn create_comment(&self, number: u64, body: &str) -> Result<Comment, ClientError> {
+        self.client
+            .post(
+                self.endpoint(&[&number.to_string(), "comments"]),
+                &CommentBody { body },
+            )
+            .await
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn update_comment(&self, id: u64, body: &str) -> Result<Comment, ClientError> {
+        self.client
+            .patch(
+                self.endpoint(&["comments", &id.to_string()]),
+                &CommentBody { body },
+            )
+            .await
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn delete_comment(&self, id: u64) -> Result<(), ClientError> {
+        self.client
+            .delete(self.endpoint(&["comments", &id.to_string()]))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::tests::mock;
+    use futures::TryStreamExt;
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use wiremock::matchers::{body_json, method, path, query_param};
+    use wiremock::{Mock, ResponseTemplate};
+
+    fn comment(id: u64, body: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "body": body,
+            "html_url": format!("https://github.com/octo-org/octo-repo/issues/42#issuecomment-{id}"),
+            "user": { "login": "github-actions[bot]", "id": 41_898_282, "type": "Bot" },
+        })
+    }
+
+    #[tokio::test]
+    async fn test_comments() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/octo-org/octo-repo/issues/42/comments"))
+            .and(query_param("per_page", "100"))
+            .respond_with(ResponseTemplate::new(200).set_body_json([comment(1, "first")]))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v3/repos/octo-org/octo-repo/issues/42/comments"))
+            .and(body_json(serde_json::json!({ "body": "second" })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(comment(2, "second")))
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/v3/repos/octo-org/octo-repo/issues/comments/2"))
+            .and(body_json(serde_json::json!({ "body": "updated" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(comment(2, "updated")))
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/v3/repos/octo-org/octo-repo/issues/comments/1"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let issues = client.issues("octo-org", "octo-repo");
+        let comments: Vec<_> = issues.list_comments(42).try_collect().await?;
+        sim_assert_eq!(comments.len(), 1);
+        sim_assert_eq!(comments[0].body, "first");
+        sim_assert_eq!(issues.create_comment(42, "second").await?.id, 2);
+        sim_assert_eq!(issues.update_comment(2, "updated").await?.body, "updated");
+        issues.delete_comment(1).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+//! Pull requests and their changed files.
+
+use crate::client::{Client, ClientError};
+use crate::event::PullRequest;
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct UpdatePullRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// `open` or `closed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    /// Branch the pull request is merged into.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+}
+
+/// File changed by a pull request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct File {
+    pub sha: String,
+    pub filename: String,
+    /// `added`, `removed`, `modified`, `renamed`, `copied`, `changed` or `unchanged`.
+    pub status: String,
+    pub additions: u64,
+    pub deletions: u64,
+    pub changes: u64,
+    /// Unified diff of the file, missing for binary and very large files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_filename: Option<String>,
+}
+
+/// Endpoints for the pull requests of a repository.
+#[derive(Debug, Clone, Copy)]
+pub struct Pulls<'a> {
+    client: &'a Client,
+    owner: &'a str,
+    repo: &'a str,
+}
+
+impl Client {
+    #[must_use]
+    pub fn pulls<'a>(&'a self, owner: &'a str, repo: &'a str) -> Pulls<'a> {
+        Pulls {
+            client: self,
+            owner,
+            repo,
+        }
+    }
+}
+
+impl Pulls<'_> {
+    fn endpoint(&self, path: &[&str]) -> action_http_client::Url {
+        let segments = ["repos", self.owner, self.repo, "pulls"];
+        self.client.endpoint(segments.iter().chain(path))
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn get(&self, number: u64) -> Result<PullRequest, ClientError> {
+        self.client.get(self.endpoint(&[&number.to_string()])).await
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn update(
+        &self,
+        number: u64,
+        pull_request: &UpdatePullRequest,
+    ) -> Result<PullRequest, ClientError> {
+        self.client
+            .patch(self.endpoint(&[&number.to_string()]), pull_request)
+            .await
+    }
+
+    /// Lists the files changed by a pull request, at most 3000.
+    pub fn list_files(
+        &self,
+        number: u64,
+    ) -> impl Stream<Item = Result<File, ClientError>> + use<'_> {
+        let url = self.endpoint(&[&number.to_string(), "files"]);
+        self.client.paginate(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::tests::mock;
+    use futures::TryStreamExt;
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_pull_request() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        let payload: serde_json::Value =
+            serde_json::from_str(include_str!("../../fixtures/pull_request.json"))?;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/octo-org/octo-repo/pulls/42"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&payload["pull_request"]))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/octo-org/octo-repo/pulls/42/files"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!([{
+                    "sha": "bbcd538c8e72b8c175046e27cc8f907076331401",
+                    "filename": "src/lib.rs",
+                    "status": "modified",
+                    "additions": 10,
+                    "deletions": 2,
+                    "changes": 12,
+                    "patch": "@@ -1,2 +1,10 @@",
+                }])),
+            )
+            .mount(&server)
+            .await;
+
+        let pulls = client.pulls("octo-org", "octo-repo");
+        let pull_request = pulls.get(42).await?;
+        sim_assert_eq!(pull_request.head.r#ref, "feature");
+        let files: Vec<_> = pulls.list_files(42).try_collect().await?;
+        sim_assert_eq!(files.len(), 1);
+        sim_assert_eq!(files[0].filename, "src/lib.rs");
+        sim_assert_eq!(files[0].previous_filename, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,429 @@
+//! Releases of a repository.
+
+use crate::client::{Client, ClientError};
+use crate::event::{Asset, Release};
+use action_http_client::header::{self, HeaderValue};
+use action_http_client::progress::Progress;
+use action_http_client::{Body, DownloadOptions, Method, Request, StatusCode, Url};
+use futures::{Stream, StreamExt, TryStreamExt};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CreateRelease {
+    pub tag_name: String,
+    /// Branch or commit the tag is created from if it does not exist yet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_commitish: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    pub draft: bool,
+    pub prerelease: bool,
+    pub generate_release_notes: bool,
+}
+
+/// Options for uploading a release asset.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UploadAsset {
+    /// Name of the asset, the file name if not set.
+    pub name: Option<String>,
+    /// Label shown instead of the name.
+    pub label: Option<String>,
+    /// Media type of the asset, guessed from the file extension if not set.
+    pub content_type: Option<String>,
+    /// Whether an existing asset with the same name is deleted first.
+    pub replace: bool,
+}
+
+/// Whether `name` matches `pattern`, in which `*` matches any sequence of characters
+/// and `?` matches a single character.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // matches[j]: whether the pattern so far matches the first j characters of the name
+    let mut matches = vec![false; name.len() + 1];
+    matches[0] = true;
+    for p in pattern {
+        let previous = matches.clone();
+        matches[0] = previous[0] && p == '*';
+        for j in 1..=name.len() {
+            matches[j] = match p {
+                '*' => previous[j] || matches[j - 1],
+                '?' => previous[j - 1],
+                c => previous[j - 1] && name[j - 1] == c,
+            };
+        }
+    }
+    matches[name.len()]
+}
+
+/// Endpoints for the releases of a repository.
+#[derive(Debug, Clone, Copy)]
+pub struct Releases<'a> {
+    client: &'a Client,
+    owner: &'a str,
+    repo: &'a str,
+}
+
+impl Client {
+    #[must_use]
+    pub fn releases<'a>(&'a self, owner: &'a str, repo: &'a str) -> Releases<'a> {
+        Releases {
+            client: self,
+            owner,
+            repo,
+        }
+    }
+}
+
+impl Releases<'_> {
+    fn endpoint(&self, path: &[&str]) -> action_http_client::Url {
+        let segments = ["repos", self.owner, self.repo, "releases"];
+        self.client.endpoint(segments.iter().chain(path))
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn get(&self, id: u64) -> Result<Release, ClientError> {
+        self.client.get(self.endpoint(&[&id.to_string()])).await
+    }
+
+    /// Latest published full release, `None` if there is none.
+    ///
+    /// # Errors
+    /// If the request fails.
+    pub async fn latest(&self) -> Result<Option<Release>, ClientError> {
+        not_found_as_none(self.client.get(self.endpoint(&["latest"])).await)
+    }
+
+    /// Published release with tag `tag`, `None` if there is none.
+    ///
+    /// Draft releases are not associated with a tag yet and are never found.
+    ///
+    /// # Errors
+    /// If the request fails.
+    pub async fn find_by_tag(&self, tag: &str) -> Result<Option<Release>, ClientError> {
+        not_found_as_none(self.client.get(self.endpoint(&["tags", tag])).await)
+    }
+
+    /// Lists all releases, including drafts if the token has push access, most recent first.
+    pub fn list(&self) -> impl Stream<Item = Result<Release, ClientError>> + use<'_> {
+        self.client.paginate(self.endpoint(&[]))
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn create(&self, release: &CreateRelease) -> Result<Release, ClientError> {
+        self.client.post(self.endpoint(&[]), release).await
+    }
+
+    /// Finds the release with the tag of `release`, including drafts, or creates it.
+    ///
+    /// # Errors
+    /// If a request fails.
+    pub async fn find_or_create(&self, release: &CreateRelease) -> Result<Release, ClientError> {
+        let tag = release.tag_name.as_str();
+        if let Some(found) = self.find_by_tag(tag).await? {
+            return Ok(found);
+        }
+        let draft = self
+            .list()
+            .try_filter(|found| std::future::ready(found.tag_name == tag))
+            .boxed()
+            .try_next()
+            .await?;
+        match draft {
+            Some(draft) => Ok(draft),
+            None => self.create(release).await,
+        }
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn delete_asset(&self, id: u64) -> Result<(), ClientError> {
+        self.client
+            .delete(self.endpoint(&["assets", &id.to_string()]))
+            .await
+    }
+
+    /// Uploads the file at `path` as an asset of `release`, streaming it from disk.
+    ///
+    /// # Errors
+    /// If the file cannot be read, an asset with the same name exists and is not replaced,
+    /// or a request fails.
+    pub async fn upload_asset(
+        &self,
+        release: &Release,
+        path: &Path,
+        options: &UploadAsset,
+    ) -> Result<Asset, ClientError> {
+        let io_error = |source| ClientError::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+        let name = match &options.name {
+            Some(name) => name.clone(),
+            None => path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .ok_or_else(|| io_error(std::io::ErrorKind::InvalidInput.into()))?,
+        };
+        if options.replace
+            && let Some(existing) = release.assets.iter().find(|asset| asset.name == name)
+        {
+            action_core::info!("Deleting existing asset {name}");
+            self.delete_asset(existing.id).await?;
+        }
+
+        let content_type = options.content_type.clone().unwrap_or_else(|| {
+            mime_guess::from_path(path)
+                .first_or_octet_stream()
+                .essence_str()
+                .to_string()
+        });
+        let file = tokio::fs::File::open(path).await.map_err(io_error)?;
+        let size = file.metadata().await.map_err(io_error)?.len();
+
+        // the upload URL is a URI template such as `.../assets{?name,label}`
+        let upload_url = release
+            .upload_url
+            .split_once('{')
+            .map_or(release.upload_url.as_str(), |(url, _)| url);
+        let mut url = Url::parse(upload_url)
+            .map_err(|_| ClientError::InvalidUrl(release.upload_url.clone()))?;
+        url.query_pairs_mut().append_pair("name", &name);
+        if let Some(label) = &options.label {
+            url.query_pairs_mut().append_pair("label", label);
+        }
+
+        let mut progress = Progress::new("Uploading", &name, Some(size));
+        let stream = tokio_util::io::ReaderStream::new(file).inspect_ok(move |chunk| {
+            progress.advance(chunk.len() as u64);
+        });
+        let mut request = Request::new(Method::POST, url);
+        let headers = request.headers_mut();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_str(&content_type)
+                .unwrap_or(HeaderValue::from_static("application/octet-stream")),
+        );
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from(size));
+        *request.body_mut() = Some(Body::wrap_stream(stream));
+        self.client.send_json(request).await
+    }
+
+    /// Downloads `asset` to the file at `path`, which is removed if the download fails.
+    ///
+    /// Interrupted downloads are resumed, see [`action_http_client::Client::download`].
+    /// Returns the number of bytes written.
+    ///
+    /// # Errors
+    /// If the request fails or the file cannot be written.
+    pub async fn download_asset(&self, asset: &Asset, path: &Path) -> Result<u64, ClientError> {
+        let url = Url::parse(&asset.url).map_err(|_| ClientError::InvalidUrl(asset.url.clone()))?;
+        let mut options = DownloadOptions::default();
+        options.headers.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("application/octet-stream"),
+        );
+        Ok(self.client.http.download(url, path, &options).await?)
+    }
+
+    /// Downloads the assets of `release` whose name matches `pattern` into `dir`,
+    /// where `*` matches any sequence of characters and `?` a single character.
+    ///
+    /// Returns the paths of the downloaded files.
+    ///
+    /// # Errors
+    /// If a download fails.
+    pub async fn download_assets(
+        &self,
+        release: &Release,
+        pattern: &str,
+        dir: &Path,
+    ) -> Result<Vec<PathBuf>, ClientError> {
+        let mut paths = Vec::new();
+        for asset in &release.assets {
+            if !matches_pattern(pattern, &asset.name) {
+                continue;
+            }
+            let path = dir.join(&asset.name);
+            self.download_asset(asset, &path).await?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// # Errors
+    /// If the request fails.
+    pub async fn delete(&self, id: u64) -> Result<(), ClientError> {
+        self.client.delete(self.endpoint(&[&id.to_string()])).await
+    }
+}
+
+fn not_found_as_none<T>(result: Result<T, ClientError>) -> Result<Option<T>, ClientError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.status() == Some(StatusCode::NOT_FOUND) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CreateRelease, UploadAsset, matches_pattern};
+    use crate::client::tests::mock;
+    use crate::event::Release;
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use wiremock::matchers::{body_partial_json, body_string, header, method, path, query_param};
+    use wiremock::{Mock, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_find_or_create() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        let payload: serde_json::Value =
+            serde_json::from_str(include_str!("../../fixtures/release.json"))?;
+        Mock::given(method("GET"))
+            .and(path(
+                "/api/v3/repos/octo-org/octo-repo/releases/tags/v2.0.0",
+            ))
+            .respond_with(
+                ResponseTemplate::new(404)
+                    .set_body_json(serde_json::json!({ "message": "Not Found" })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v3/repos/octo-org/octo-repo/releases"))
+            .and(body_partial_json(
+                serde_json::json!({ "tag_name": "v2.0.0", "draft": true }),
+            ))
+            .respond_with(ResponseTemplate::new(201).set_body_json(&payload["release"]))
+            .mount(&server)
+            .await;
+
+        let releases = client.releases("octo-org", "octo-repo");
+        sim_assert_eq!(releases.find_by_tag("v2.0.0").await?, None);
+        let release = releases
+            .create(&CreateRelease {
+                tag_name: "v2.0.0".into(),
+                draft: true,
+                ..CreateRelease::default()
+            })
+            .await?;
+        sim_assert_eq!(release.id, 11_248_810);
+        Ok(())
+    }
+
+    fn asset(server: &wiremock::MockServer, id: u64, name: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "url": format!("{}/api/v3/repos/octo-org/octo-repo/releases/assets/{id}", server.uri()),
+            "name": name,
+            "content_type": "application/gzip",
+            "state": "uploaded",
+            "size": 5,
+            "browser_download_url": format!("https://github.com/octo-org/octo-repo/releases/download/v1.0.0/{name}"),
+        })
+    }
+
+    fn release(
+        server: &wiremock::MockServer,
+        assets: &[serde_json::Value],
+    ) -> color_eyre::eyre::Result<Release> {
+        let mut release: serde_json::Value =
+            serde_json::from_str(include_str!("../../fixtures/release.json"))?;
+        release["release"]["upload_url"] = format!(
+            "{}/api/uploads/repos/octo-org/octo-repo/releases/11248810/assets{{?name,label}}",
+            server.uri()
+        )
+        .into();
+        release["release"]["assets"] = assets.into();
+        Ok(serde_json::from_value(release["release"].take())?)
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("*.tar.gz", "tool-linux.tar.gz"));
+        assert!(matches_pattern("tool-*-x86_64*", "tool-linux-x86_64.zip"));
+        assert!(matches_pattern("v?.zip", "v1.zip"));
+        assert!(!matches_pattern("v?.zip", "v10.zip"));
+        assert!(!matches_pattern("*.tar.gz", "tool.zip"));
+        assert!(matches_pattern("*", ""));
+    }
+
+    #[tokio::test]
+    async fn test_upload_replaces_asset() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        let release = release(&server, &[asset(&server, 1, "tool.tar.gz")])?;
+        Mock::given(method("DELETE"))
+            .and(path("/api/v3/repos/octo-org/octo-repo/releases/assets/1"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(
+                "/api/uploads/repos/octo-org/octo-repo/releases/11248810/assets",
+            ))
+            .and(query_param("name", "tool.tar.gz"))
+            .and(header("content-type", "application/gzip"))
+            .and(header("content-length", "5"))
+            .and(body_string("hello"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(asset(
+                &server,
+                2,
+                "tool.tar.gz",
+            )))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("tool.tar.gz");
+        std::fs::write(&file, "hello")?;
+        let uploaded = client
+            .releases("octo-org", "octo-repo")
+            .upload_asset(
+                &release,
+                &file,
+                &UploadAsset {
+                    replace: true,
+                    ..UploadAsset::default()
+                },
+            )
+            .await?;
+        sim_assert_eq!(uploaded.id, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_download_assets() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        let release = release(
+            &server,
+            &[
+                asset(&server, 1, "tool-linux.tar.gz"),
+                asset(&server, 2, "tool-windows.zip"),
+            ],
+        )?;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/octo-org/octo-repo/releases/assets/1"))
+            .and(header("accept", "application/octet-stream"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"hello".as_slice()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir()?;
+        let paths = client
+            .releases("octo-org", "octo-repo")
+            .download_assets(&release, "*.tar.gz", dir.path())
+            .await?;
+        sim_assert_eq!(paths, [dir.path().join("tool-linux.tar.gz")]);
+        sim_assert_eq!(std::fs::read_to_string(&paths[0])?, "hello");
+        Ok(())
+    }
+}
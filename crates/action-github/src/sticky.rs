@@ -0,0 +1,278 @@
+//! Pull request comments that are updated in place instead of posted again.
+//!
+//! A comment is identified by a hidden HTML marker at the end of its body,
+//! so every run of an action finds and updates the comment of the previous run.
+//! Comments that only quote the marker, e.g. replies, are not matched.
+
+use crate::client::{Client, ClientError};
+use crate::event::Comment;
+use action_core::summary::Summary;
+use futures::TryStreamExt;
+
+const MINIMIZE_COMMENT: &str = "mutation($id: ID!) {
+  minimizeComment(input: { subjectId: $id, classifier: OUTDATED }) { clientMutationId }
+}";
+
+/// What happens to comments with the marker that are not updated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Stale {
+    /// Leave them unchanged.
+    #[default]
+    Keep,
+    /// Delete them.
+    Delete,
+    /// Hide them as outdated.
+    Minimize,
+}
+
+/// Comment on an issue or pull request identified by a hidden marker.
+#[derive(Debug, Clone)]
+pub struct StickyComment<'a> {
+    client: &'a Client,
+    owner: &'a str,
+    repo: &'a str,
+    number: u64,
+    marker: String,
+    stale: Stale,
+}
+
+impl Client {
+    /// Sticky comment on issue or pull request `number`, identified by `id`.
+    ///
+    /// Actions posting more than one sticky comment on the same pull request use a distinct `id`
+    /// for each, e.g. `coverage` and `benchmarks`.
+    /// A `>` in `id` is escaped, so it cannot end the hidden marker.
+    #[must_use]
+    pub fn sticky_comment<'a>(
+        &'a self,
+        owner: &'a str,
+        repo: &'a str,
+        number: u64,
+        id: &str,
+    ) -> StickyComment<'a> {
+        StickyComment {
+            client: self,
+            owner,
+            repo,
+            number,
+            marker: format!("<!-- sticky-comment: {} -->", id.replace('>', "&gt;")),
+            stale: Stale::default(),
+        }
+    }
+}
+
+impl StickyComment<'_> {
+    /// Sets what happens to comments with the marker that are not updated.
+    #[must_use]
+    pub fn stale(mut self, stale: Stale) -> Self {
+        self.stale = stale;
+        self
+    }
+
+    /// Hidden marker identifying the comment.
+    #[must_use]
+    pub fn marker(&self) -> &str {
+        &self.marker
+    }
+
+    fn body(&self, body: &str) -> String {
+        format!("{}\n\n{}", body.trim_end(), self.marker)
+    }
+
+    /// Comments ending with the marker, oldest first.
+    ///
+    /// # Errors
+    /// If the comments cannot be listed.
+    pub async fn find(&self) -> Result<Vec<Comment>, ClientError> {
+        self.client
+            .issues(self.owner, self.repo)
+            .list_comments(self.number)
+            .try_filter(|comment| {
+                std::future::ready(comment.body.trim_end().ends_with(&self.marker))
+            })
+            .try_collect()
+            .await
+    }
+
+    async fn remove_stale(&self, comments: &[Comment]) -> Result<(), ClientError> {
+        let issues = self.client.issues(self.owner, self.repo);
+        for comment in comments {
+            match (self.stale, &comment.node_id) {
+                (Stale::Keep, _) => {}
+                (Stale::Delete, _) => issues.delete_comment(comment.id).await?,
+                (Stale::Minimize, Some(id)) => {
+                    let _: serde_json::Value = self
+                        .client
+                        .graphql(MINIMIZE_COMMENT, &serde_json::json!({ "id": id }))
+                        .await?;
+                }
+                (Stale::Minimize, None) => {
+                    action_core::warning!(
+                        "cannot minimize comment {} without a node id",
+                        comment.html_url
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Updates the most recent comment with the marker, or creates it if there is none.
+    ///
+    /// Older comments with the marker are handled according to [`StickyComment::stale`].
+    ///
+    /// # Errors
+    /// If a request fails.
+    pub async fn upsert(&self, body: &str) -> Result<Comment, ClientError> {
+        let issues = self.client.issues(self.owner, self.repo);
+        let mut comments = self.find().await?;
+        let comment = match comments.pop() {
+            Some(latest) => issues.update_comment(latest.id, &self.body(body)).await?,
+            None => issues.create_comment(self.number, &self.body(body)).await?,
+        };
+        self.remove_stale(&comments).await?;
+        Ok(comment)
+    }
+
+    /// Creates a new comment below all others, handling the previous comments with the marker
+    /// according to [`StickyComment::stale`].
+    ///
+    /// # Errors
+    /// If a request fails.
+    pub async fn recreate(&self, body: &str) -> Result<Comment, ClientError> {
+        let comments = self.find().await?;
+        let comment = self
+            .client
+            .issues(self.owner, self.repo)
+            .create_comment(self.number, &self.body(body))
+            .await?;
+        self.remove_stale(&comments).await?;
+        Ok(comment)
+    }
+
+    /// Updates or creates the comment with the content of `summary`,
+    /// which can be written to the job summary as well.
+    ///
+    /// # Errors
+    /// If a request fails.
+    pub async fn upsert_summary(&self, summary: &Summary) -> Result<Comment, ClientError> {
+        self.upsert(summary.stringify()).await
+    }
+
+    /// Deletes all comments with the marker.
+    ///
+    /// # Errors
+    /// If a request fails.
+    pub async fn delete(&self) -> Result<(), ClientError> {
+        let issues = self.client.issues(self.owner, self.repo);
+        for comment in self.find().await? {
+            issues.delete_comment(comment.id).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stale;
+    use crate::client::tests::mock;
+    use action_core::summary::Summary;
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use wiremock::matchers::{body_json, body_partial_json, method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    const COMMENTS: &str = "/api/v3/repos/octo-org/octo-repo/issues/42/comments";
+
+    fn comment(id: u64, body: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "node_id": format!("IC_{id}"),
+            "body": body,
+            "html_url": format!("https://github.com/octo-org/octo-repo/pull/42#issuecomment-{id}"),
+            "user": { "login": "github-actions[bot]", "id": 41_898_282, "type": "Bot" },
+        })
+    }
+
+    #[tokio::test]
+    async fn test_create() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        Mock::given(method("GET"))
+            .and(path(COMMENTS))
+            .respond_with(ResponseTemplate::new(200).set_body_json([comment(1, "LGTM")]))
+            .mount(&server)
+            .await;
+        let body = "<h2>Coverage</h2>\n\n<!-- sticky-comment: coverage -->";
+        Mock::given(method("POST"))
+            .and(path(COMMENTS))
+            .and(body_json(serde_json::json!({ "body": body })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(comment(2, body)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut summary = Summary::new();
+        summary.add_heading("Coverage", 2);
+        let comment = client
+            .sticky_comment("octo-org", "octo-repo", 42, "coverage")
+            .upsert_summary(&summary)
+            .await?;
+        sim_assert_eq!(comment.id, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_and_minimize_stale() -> color_eyre::eyre::Result<()> {
+        let (server, client) = mock().await?;
+        let marker = "<!-- sticky-comment: coverage -->";
+        Mock::given(method("GET"))
+            .and(path(COMMENTS))
+            .respond_with(ResponseTemplate::new(200).set_body_json([
+                comment(1, &format!("old\n\n{marker}")),
+                comment(2, "unrelated"),
+                comment(3, &format!("newer\n\n{marker}\r\n")),
+                comment(4, &format!("> {marker}\n\nquoted in a reply")),
+            ]))
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/v3/repos/octo-org/octo-repo/issues/comments/3"))
+            .and(body_json(
+                serde_json::json!({ "body": format!("new\n\n{marker}") }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(comment(3, "new")))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/graphql"))
+            .and(body_partial_json(
+                serde_json::json!({ "variables": { "id": "IC_1" } }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": { "minimizeComment": { "clientMutationId": null } }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let comment = client
+            .sticky_comment("octo-org", "octo-repo", 42, "coverage")
+            .stale(Stale::Minimize)
+            .upsert("new\n")
+            .await?;
+        sim_assert_eq!(comment.id, 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_marker() -> color_eyre::eyre::Result<()> {
+        let (_server, client) = mock().await?;
+        sim_assert_eq!(
+            client
+                .sticky_comment("octo-org", "octo-repo", 42, "a --> b")
+                .marker(),
+            "<!-- sticky-comment: a --&gt; b -->"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+//! Handlers authenticating requests, like the credential handlers of `@actions/http-client`.
+
+use crate::Error;
+use base64::Engine;
+use reqwest::header::{self, HeaderValue};
+use reqwest::{Request, Response};
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+
+/// Future returned by [`RequestHandler::handle_authentication`].
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Modifies requests before they are sent, e.g. to add credentials.
+pub trait RequestHandler: Debug + Send + Sync {
+    /// Prepares a request, called before every attempt to send it.
+    fn prepare_request(&self, request: &mut Request);
+
+    /// Whether the handler can re-authenticate after the `401 Unauthorized` `response`.
+    fn can_handle_authentication(&self, _response: &Response) -> bool {
+        false
+    }
+
+    /// Re-authenticates after the `401 Unauthorized` `response`, e.g. by refreshing a token.
+    ///
+    /// If this returns `true`, the request is prepared and sent once more.
+    fn handle_authentication<'a>(
+        &'a self,
+        _response: &'a Response,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        Box::pin(async { Ok(false) })
+    }
+}
+
+/// Builds a sensitive `Authorization` header, masking `value` in the log.
+fn authorization(value: String) -> Result<HeaderValue, Error> {
+    action_core::set_secret(value.as_str());
+    let mut value = HeaderValue::try_from(value).map_err(|source| Error::InvalidHeader {
+        name: header::AUTHORIZATION,
+        source,
+    })?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+/// Encodes `Basic` credentials, masking the password and the encoded credentials in the log.
+fn basic(username: &str, password: &str) -> Result<HeaderValue, Error> {
+    action_core::set_secret(password);
+    let credentials =
+        base64::engine::general_purpose::STANDARD.encode(format!("{username}:{password}"));
+    action_core::set_secret(credentials.as_str());
+    authorization(format!("Basic {credentials}"))
+}
+
+/// Authenticates requests with a username and password.
+#[derive(Debug, Clone)]
+pub struct BasicCredentialHandler {
+    authorization: HeaderValue,
+}
+
+impl BasicCredentialHandler {
+    /// Registers the password as a secret.
+    ///
+    /// # Errors
+    /// If the credentials are not a valid header value.
+    pub fn new(username: &str, password: &str) -> Result<Self, Error> {
+        Ok(Self {
+            authorization: basic(username, password)?,
+        })
+    }
+}
+
+impl RequestHandler for BasicCredentialHandler {
+    fn prepare_request(&self, request: &mut Request) {
+        request
+            .headers_mut()
+            .insert(header::AUTHORIZATION, self.authorization.clone());
+    }
+}
+
+/// Authenticates requests with a bearer token.
+#[derive(Debug, Clone)]
+pub struct BearerCredentialHandler {
+    authorization: HeaderValue,
+}
+
+impl BearerCredentialHandler {
+    /// Registers the token as a secret.
+    ///
+    /// # Errors
+    /// If the token is not a valid header value.
+    pub fn new(token: &str) -> Result<Self, Error> {
+        action_core::set_secret(token);
+        Ok(Self {
+            authorization: authorization(format!("Bearer {token}"))?,
+        })
+    }
+}
+
+impl RequestHandler for BearerCredentialHandler {
+    fn prepare_request(&self, request: &mut Request) {
+        request
+            .headers_mut()
+            .insert(header::AUTHORIZATION, self.authorization.clone());
+    }
+}
+
+/// Authenticates requests with a personal access token, sent as the password for the user `PAT`.
+#[derive(Debug, Clone)]
+pub struct PersonalAccessTokenCredentialHandler {
+    authorization: HeaderValue,
+}
+
+impl PersonalAccessTokenCredentialHandler {
+    /// Registers the token as a secret.
+    ///
+    /// # Errors
+    /// If the token is not a valid header value.
+    pub fn new(token: &str) -> Result<Self, Error> {
+        Ok(Self {
+            authorization: basic("PAT", token)?,
+        })
+    }
+}
+
+impl RequestHandler for PersonalAccessTokenCredentialHandler {
+    fn prepare_request(&self, request: &mut Request) {
+        request
+            .headers_mut()
+            .insert(header::AUTHORIZATION, self.authorization.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BasicCredentialHandler, BearerCredentialHandler, BoxFuture,
+        PersonalAccessTokenCredentialHandler, RequestHandler,
+    };
+    use crate::{Client, Error};
+    use reqwest::header::{self, HeaderValue};
+    use reqwest::{Method, Request, Response, StatusCode, Url};
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use wiremock::matchers::{header as has_header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn authorization(handler: &impl RequestHandler) -> String {
+        let mut request = Request::new(Method::GET, Url::parse("https://example.com").unwrap());
+        handler.prepare_request(&mut request);
+        let value = &request.headers()[header::AUTHORIZATION];
+        assert!(value.is_sensitive());
+        value.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_credential_handlers() -> color_eyre::eyre::Result<()> {
+        sim_assert_eq!(
+            authorization(&BasicCredentialHandler::new("johndoe", "password")?),
+            "Basic am9obmRvZTpwYXNzd29yZA=="
+        );
+        sim_assert_eq!(
+            authorization(&BearerCredentialHandler::new("token")?),
+            "Bearer token"
+        );
+        sim_assert_eq!(
+            authorization(&PersonalAccessTokenCredentialHandler::new("token")?),
+            "Basic UEFUOnRva2Vu"
+        );
+        assert!(BearerCredentialHandler::new("line\nbreak").is_err());
+        Ok(())
+    }
+
+    /// Switches to a new token once the first one is rejected.
+    #[derive(Debug, Default)]
+    struct Refresh {
+        refreshed: AtomicBool,
+    }
+
+    impl RequestHandler for Refresh {
+        fn prepare_request(&self, request: &mut Request) {
+            let token = if self.refreshed.load(Ordering::SeqCst) {
+                "Bearer new"
+            } else {
+                "Bearer old"
+            };
+            request
+                .headers_mut()
+                .insert(header::AUTHORIZATION, HeaderValue::from_static(token));
+        }
+
+        fn can_handle_authentication(&self, _response: &Response) -> bool {
+            true
+        }
+
+        fn handle_authentication<'a>(
+            &'a self,
+            _response: &'a Response,
+        ) -> BoxFuture<'a, Result<bool, Error>> {
+            Box::pin(async { Ok(!self.refreshed.swap(true, Ordering::SeqCst)) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_authentication() -> color_eyre::eyre::Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(has_header("authorization", "Bearer new"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!(true)))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let url = Url::parse(&server.uri())?;
+        let client = Client::builder().handler(Refresh::default()).build()?;
+        assert!(client.post_json::<_, bool>(url.clone(), &()).await?);
+
+        let client = Client::builder()
+            .handler(BearerCredentialHandler::new("old")?)
+            .build()?;
+        let err = client.post_json::<_, bool>(url, &()).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Status {
+                status: StatusCode::UNAUTHORIZED,
+                ..
+            }
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,420 @@
+//! Recording of HTTP interactions to a cassette file, replayed to test actions offline.
+//!
+//! In [record](Cassette::record) mode, requests are sent and every request and response
+//! is written to the cassette. In [replay](Cassette::replay) mode, requests are answered
+//! with the recorded responses instead, in the order they were recorded.
+//!
+//! Sensitive headers such as `Authorization` and all secrets registered with
+//! [`action_core::set_secret`] are redacted before an interaction is written,
+//! also in bodies that are not valid UTF-8 and are recorded as Base64.
+//! The framing headers `Content-Length` and `Transfer-Encoding` are not recorded,
+//! as redaction changes the length of the body.
+
+use crate::Error;
+use base64::Engine;
+use parking_lot::Mutex;
+use reqwest::header::{self, HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Request, Response};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Replacement of redacted values, like in the log.
+const REDACTED: &str = "***";
+
+/// Body of a recorded request or response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordedBody {
+    Text(String),
+    /// Body that is not valid UTF-8.
+    Base64(String),
+}
+
+impl RecordedBody {
+    fn new(bytes: &[u8], secrets: &[String]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Self::Text(redact(text, secrets)),
+            Err(_) => Self::Base64(
+                base64::engine::general_purpose::STANDARD.encode(redact_bytes(bytes, secrets)),
+            ),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Text(text) => text.clone().into_bytes(),
+            Self::Base64(encoded) => base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<RecordedBody>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: RecordedBody,
+}
+
+/// A request and the response it received.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Record,
+    Replay,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    interactions: Vec<Interaction>,
+    /// Whether each interaction was already replayed.
+    replayed: Vec<bool>,
+}
+
+/// Interactions recorded to or replayed from a file, see the [module](self) documentation.
+///
+/// Attach a cassette to a client with [`ClientBuilder::cassette`](crate::ClientBuilder::cassette).
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: Mode,
+    state: Mutex<State>,
+}
+
+fn redact(text: &str, secrets: &[String]) -> String {
+    secrets.iter().fold(text.to_string(), |text, secret| {
+        text.replace(secret.as_str(), REDACTED)
+    })
+}
+
+/// Replaces the secrets in a body that is not valid UTF-8.
+fn redact_bytes(bytes: &[u8], secrets: &[String]) -> Vec<u8> {
+    secrets
+        .iter()
+        .filter(|secret| !secret.is_empty())
+        .fold(bytes.to_vec(), |bytes, secret| {
+            let secret = secret.as_bytes();
+            let mut redacted = Vec::with_capacity(bytes.len());
+            let mut rest = bytes.as_slice();
+            while !rest.is_empty() {
+                if rest.starts_with(secret) {
+                    redacted.extend_from_slice(REDACTED.as_bytes());
+                    rest = &rest[secret.len()..];
+                } else {
+                    redacted.push(rest[0]);
+                    rest = &rest[1..];
+                }
+            }
+            redacted
+        })
+}
+
+/// Whether a header describes the framing of the body, which no longer matches a redacted body.
+fn is_framing(name: &HeaderName) -> bool {
+    *name == header::CONTENT_LENGTH || *name == header::TRANSFER_ENCODING
+}
+
+fn record_headers(headers: &HeaderMap, secrets: &[String]) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(name, _)| !is_framing(name))
+        .map(|(name, value)| {
+            let value = if value.is_sensitive()
+                || *name == header::AUTHORIZATION
+                || *name == header::PROXY_AUTHORIZATION
+            {
+                REDACTED.to_string()
+            } else {
+                redact(&String::from_utf8_lossy(value.as_bytes()), secrets)
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+impl Cassette {
+    /// Records interactions to the file at `path`, replacing it on the first request.
+    #[must_use]
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            mode: Mode::Record,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Replays the interactions recorded in the file at `path`.
+    ///
+    /// # Errors
+    /// If the file cannot be read or is not a cassette.
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+        let data = std::fs::read(&path).map_err(|source| Error::Io {
+            path: path.clone(),
+            source,
+        })?;
+        let interactions: Vec<Interaction> =
+            serde_json::from_slice(&data).map_err(|source| Error::Cassette {
+                path: path.clone(),
+                source,
+            })?;
+        let replayed = vec![false; interactions.len()];
+        Ok(Self {
+            path,
+            mode: Mode::Replay,
+            state: Mutex::new(State {
+                interactions,
+                replayed,
+            }),
+        })
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the interactions recorded so far, or loaded for replay.
+    #[must_use]
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.state.lock().interactions.clone()
+    }
+
+    pub(crate) async fn execute(
+        &self,
+        client: &reqwest::Client,
+        request: Request,
+    ) -> Result<Response, Error> {
+        match self.mode {
+            Mode::Record => self.record_request(client, request).await,
+            Mode::Replay => self.replay_request(&request),
+        }
+    }
+
+    async fn record_request(
+        &self,
+        client: &reqwest::Client,
+        request: Request,
+    ) -> Result<Response, Error> {
+        let secrets = action_core::secrets();
+        let recorded_request = RecordedRequest {
+            method: request.method().to_string(),
+            url: redact(request.url().as_str(), &secrets),
+            headers: record_headers(request.headers(), &secrets),
+            body: request
+                .body()
+                .and_then(reqwest::Body::as_bytes)
+                .map(|bytes| RecordedBody::new(bytes, &secrets)),
+        };
+
+        let response = client.execute(request).await?;
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        let recorded_response = RecordedResponse {
+            status: status.as_u16(),
+            headers: record_headers(&headers, &secrets),
+            body: RecordedBody::new(&body, &secrets),
+        };
+
+        let data = {
+            let mut state = self.state.lock();
+            state.interactions.push(Interaction {
+                request: recorded_request,
+                response: recorded_response,
+            });
+            state.replayed.push(false);
+            serde_json::to_vec_pretty(&state.interactions).map_err(Error::Serialize)?
+        };
+        self.save(&data).await?;
+
+        let mut response = http::Response::new(body);
+        *response.status_mut() = status;
+        *response.version_mut() = version;
+        *response.headers_mut() = headers;
+        Ok(response.into())
+    }
+
+    async fn save(&self, data: &[u8]) -> Result<(), Error> {
+        let io_error = |source| Error::Io {
+            path: self.path.clone(),
+            source,
+        };
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await.map_err(io_error)?;
+        }
+        tokio::fs::write(&self.path, data).await.map_err(io_error)
+    }
+
+    /// Answers `request` with the first interaction that was not replayed yet
+    /// and has the same method and (redacted) URL.
+    fn replay_request(&self, request: &Request) -> Result<Response, Error> {
+        let method = request.method().as_str();
+        let url = redact(request.url().as_str(), &action_core::secrets());
+        let mut state = self.state.lock();
+        let State {
+            interactions,
+            replayed,
+        } = &mut *state;
+        let (interaction, replayed) = interactions
+            .iter()
+            .zip(replayed.iter_mut())
+            .find(|(interaction, replayed)| {
+                !**replayed
+                    && interaction.request.method == method
+                    && interaction.request.url == url
+            })
+            .ok_or_else(|| Error::CassetteMiss {
+                method: request.method().clone(),
+                url: request.url().to_string(),
+            })?;
+        *replayed = true;
+
+        let recorded = &interaction.response;
+        let body = recorded.body.to_bytes();
+        let length = HeaderValue::from(body.len());
+        let mut response = http::Response::new(body);
+        *response.status_mut() = http::StatusCode::from_u16(recorded.status)
+            .unwrap_or(http::StatusCode::INTERNAL_SERVER_ERROR);
+        for (name, value) in &recorded.headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) && !is_framing(&name)
+            {
+                response.headers_mut().append(name, value);
+            }
+        }
+        response
+            .headers_mut()
+            .insert(header::CONTENT_LENGTH, length);
+        Ok(response.into())
+    }
+
+    /// Whether all recorded interactions were replayed.
+    #[must_use]
+    pub fn is_exhausted(&self) -> bool {
+        self.state.lock().replayed.iter().all(|replayed| *replayed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cassette, RecordedBody};
+    use crate::{BearerCredentialHandler, Client, Error};
+    use reqwest::{StatusCode, Url};
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use std::sync::Arc;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_record_and_replay() -> color_eyre::eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cassette_path = dir.path().join("fixtures/cassette.json");
+        action_core::set_secret("cassette-secret");
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/data"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "value": 1, "data": "cassette-secret" })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/missing"))
+            .respond_with(
+                ResponseTemplate::new(404)
+                    .set_body_bytes([&[0xff, 0xfe], b"cassette-secret".as_slice()].concat()),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let url = Url::parse(&server.uri())?;
+        let cassette = Arc::new(Cassette::record(&cassette_path));
+        let client = Client::builder()
+            .handler(BearerCredentialHandler::new("cassette-token")?)
+            .cassette(Arc::clone(&cassette))
+            .build()?;
+        let value: serde_json::Value = client.get_json(url.join("data")?).await?;
+        sim_assert_eq!(value["data"], "cassette-secret");
+        let response = client
+            .send(reqwest::Request::new(
+                reqwest::Method::GET,
+                url.join("missing")?,
+            ))
+            .await?;
+        sim_assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let recorded = std::fs::read_to_string(&cassette_path)?;
+        assert!(!recorded.contains("cassette-secret"));
+        assert!(!recorded.contains("cassette-token"));
+        let interactions = cassette.interactions();
+        sim_assert_eq!(interactions.len(), 2);
+        sim_assert_eq!(
+            interactions[0].request.headers,
+            [
+                ("accept".to_string(), "application/json".to_string()),
+                ("authorization".to_string(), "***".to_string())
+            ]
+        );
+        sim_assert_eq!(
+            interactions[0].response.body,
+            RecordedBody::Text(r#"{"data":"***","value":1}"#.to_string())
+        );
+        sim_assert_eq!(
+            interactions[1].response.body,
+            RecordedBody::Base64("//4qKio=".to_string())
+        );
+        assert!(interactions.iter().all(|interaction| {
+            interaction
+                .response
+                .headers
+                .iter()
+                .all(|(name, _)| name != "content-length")
+        }));
+
+        // replays without the server
+        drop(server);
+        let cassette = Arc::new(Cassette::replay(&cassette_path)?);
+        let client = Client::builder().cassette(Arc::clone(&cassette)).build()?;
+        let value: serde_json::Value = client.get_json(url.join("data")?).await?;
+        sim_assert_eq!(value, serde_json::json!({ "value": 1, "data": "***" }));
+        let response = client
+            .send(reqwest::Request::new(
+                reqwest::Method::GET,
+                url.join("missing")?,
+            ))
+            .await?;
+        sim_assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        sim_assert_eq!(response.headers()["content-length"], "5");
+        sim_assert_eq!(response.bytes().await?.as_ref(), b"\xff\xfe***");
+        assert!(cassette.is_exhausted());
+
+        let err = client
+            .get_json::<serde_json::Value>(url.join("data")?)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::CassetteMiss { .. }));
+        Ok(())
+    }
+}
@@ -0,0 +1,560 @@
+use crate::auth::RequestHandler;
+use crate::proxy;
+use crate::retry::{self, RetryPolicy};
+use action_core::env;
+use reqwest::header::{self, HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Method, Request, Response, StatusCode, Url};
+use serde::{Serialize, de::DeserializeOwned};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// User agent sent if none is configured.
+pub const DEFAULT_USER_AGENT: &str = concat!("action-http-client/", env!("CARGO_PKG_VERSION"));
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+    #[error("invalid value for header {name}")]
+    InvalidHeader {
+        name: HeaderName,
+        source: header::InvalidHeaderValue,
+    },
+    #[error("{method} {url} failed with status {status}")]
+    Status {
+        method: Method,
+        url: String,
+        status: StatusCode,
+        body: String,
+    },
+    #[error("failed to serialize request body")]
+    Serialize(#[source] serde_json::Error),
+    #[error("failed to parse response of {url}")]
+    Deserialize {
+        url: String,
+        source: serde_json::Error,
+    },
+    #[error("failed to access {path:?}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[cfg(feature = "cassette")]
+    #[error("failed to parse cassette {path:?}")]
+    Cassette {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[cfg(feature = "cassette")]
+    #[error("no recorded interaction matches {method} {url}")]
+    CassetteMiss { method: Method, url: String },
+    #[error("{algorithm} checksum of {path:?} is {actual}, expected {expected}")]
+    ChecksumMismatch {
+        path: PathBuf,
+        algorithm: &'static str,
+        expected: String,
+        actual: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    user_agent: String,
+    headers: HeaderMap,
+    retry: RetryPolicy,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<reqwest::Proxy>,
+    handlers: Vec<Arc<dyn RequestHandler>>,
+    #[cfg(feature = "cassette")]
+    cassette: Option<Arc<crate::cassette::Cassette>>,
+}
+
+/// Sends requests through the proxy [configured](proxy::proxy_url) in `env`.
+fn env_proxy(env: impl env::Read + Send + Sync + 'static) -> reqwest::Proxy {
+    reqwest::Proxy::custom(move |url| proxy::proxy_url(&env, url))
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self {
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            headers: HeaderMap::new(),
+            retry: RetryPolicy::default(),
+            timeout: None,
+            connect_timeout: Some(Duration::from_secs(30)),
+            proxy: Some(env_proxy(env::OsEnv)),
+            handlers: Vec::new(),
+            #[cfg(feature = "cassette")]
+            cassette: None,
+        }
+    }
+}
+
+impl ClientBuilder {
+    /// Sets the `User-Agent` identifying the action.
+    #[must_use]
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Adds a header sent with every request.
+    #[must_use]
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Sets how failed requests are retried.
+    #[must_use]
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Sets the timeout of each attempt of a request, from sending it until the body is received.
+    ///
+    /// Requests without a timeout of their own ([`Request::timeout_mut`]) use this one,
+    /// by default there is none.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for connecting to a server, 30 seconds by default.
+    #[must_use]
+    pub fn connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Reads the proxy configuration from `env` instead of the process environment.
+    ///
+    /// The variables are read for every request, `https` requests are tunneled
+    /// through the proxy with `CONNECT`.
+    #[must_use]
+    pub fn proxy_env(mut self, env: impl env::Read + Send + Sync + 'static) -> Self {
+        self.proxy = Some(env_proxy(env));
+        self
+    }
+
+    /// Sends all requests directly, ignoring any configured proxy.
+    #[must_use]
+    pub fn no_proxy(mut self) -> Self {
+        self.proxy = None;
+        self
+    }
+
+    /// Adds a handler preparing every request, e.g. one of the credential handlers.
+    ///
+    /// Handlers are applied in the order they were added.
+    #[must_use]
+    pub fn handler(mut self, handler: impl RequestHandler + 'static) -> Self {
+        self.handlers.push(Arc::new(handler));
+        self
+    }
+
+    /// Records requests to or replays them from `cassette`.
+    #[cfg(feature = "cassette")]
+    #[must_use]
+    pub fn cassette(mut self, cassette: Arc<crate::cassette::Cassette>) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
+    /// Builds the client.
+    ///
+    /// # Errors
+    /// If the user agent is not a valid header value or the TLS backend fails to initialize.
+    pub fn build(self) -> Result<Client, Error> {
+        // reqwest uses the process-wide default provider, which is `ring` unless
+        // the application installed another one already.
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        let user_agent =
+            HeaderValue::from_str(&self.user_agent).map_err(|source| Error::InvalidHeader {
+                name: header::USER_AGENT,
+                source,
+            })?;
+        let mut builder = reqwest::Client::builder()
+            .user_agent(user_agent)
+            .default_headers(self.headers)
+            .no_proxy();
+        if let Some(proxy) = self.proxy {
+            builder = builder.proxy(proxy);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        Ok(Client {
+            inner: builder.build()?,
+            retry: self.retry,
+            handlers: self.handlers.into(),
+            #[cfg(feature = "cassette")]
+            cassette: self.cassette,
+        })
+    }
+}
+
+/// HTTP client sending requests on behalf of an action.
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: reqwest::Client,
+    pub(crate) retry: RetryPolicy,
+    handlers: Arc<[Arc<dyn RequestHandler>]>,
+    #[cfg(feature = "cassette")]
+    cassette: Option<Arc<crate::cassette::Cassette>>,
+}
+
+impl Client {
+    /// Creates a client with the default configuration.
+    ///
+    /// # Errors
+    /// If the TLS backend fails to initialize.
+    pub fn new() -> Result<Self, Error> {
+        ClientBuilder::default().build()
+    }
+
+    #[must_use]
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// Creates a request whose body is the JSON serialization of `body`.
+    ///
+    /// # Errors
+    /// If `body` cannot be serialized.
+    pub fn json_request<B>(method: Method, url: Url, body: &B) -> Result<Request, Error>
+    where
+        B: Serialize + ?Sized,
+    {
+        let body = serde_json::to_vec(body).map_err(Error::Serialize)?;
+        let mut request = Request::new(method, url);
+        request.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        *request.body_mut() = Some(body.into());
+        Ok(request)
+    }
+
+    /// Sends a request, retrying it according to the [`RetryPolicy`].
+    ///
+    /// The [handlers](ClientBuilder::handler) prepare the request before every attempt.
+    /// If one of them re-authenticates after a `401 Unauthorized` response, the request is sent once more.
+    /// Requests with a streaming body cannot be repeated and are never retried.
+    /// Responses with an error status are returned as well,
+    /// use [`Client::error_for_status`] to turn them into errors.
+    ///
+    /// # Errors
+    /// If the request could not be sent, the response could not be received
+    /// or re-authentication failed.
+    pub async fn send(&self, mut request: Request) -> Result<Response, Error> {
+        let mut attempt = 0;
+        let mut reauthenticated = false;
+        loop {
+            for handler in self.handlers.iter() {
+                handler.prepare_request(&mut request);
+            }
+            let method = request.method().clone();
+            let url = request.url().to_string();
+            let idempotent = retry::is_idempotent(&method);
+            let copy = request.try_clone();
+            let (delay, reason) = match self.execute(request).await {
+                Ok(response) => {
+                    let status = response.status();
+                    if status == StatusCode::UNAUTHORIZED
+                        && !reauthenticated
+                        && copy.is_some()
+                        && let Some(handler) = self
+                            .handlers
+                            .iter()
+                            .find(|handler| handler.can_handle_authentication(&response))
+                        && handler.handle_authentication(&response).await?
+                        && let Some(next) = copy
+                    {
+                        reauthenticated = true;
+                        request = next;
+                        continue;
+                    }
+                    let delay = idempotent
+                        .then(|| {
+                            self.retry
+                                .delay(&method, status, response.headers(), attempt)
+                        })
+                        .flatten();
+                    match (&copy, delay) {
+                        (Some(_), Some(delay)) => (delay, status.to_string()),
+                        _ => return Ok(response),
+                    }
+                }
+                Err(Error::Request(err))
+                    if (err.is_connect() || err.is_timeout())
+                        && idempotent
+                        && copy.is_some()
+                        && attempt < self.retry.max_retries =>
+                {
+                    (self.retry.backoff(attempt), err.to_string())
+                }
+                Err(err) => return Err(err),
+            };
+            attempt += 1;
+            action_core::debug!(
+                "{method} {url} failed ({reason}), retrying in {}ms (attempt {attempt} of {})",
+                delay.as_millis(),
+                self.retry.max_retries
+            );
+            tokio::time::sleep(delay).await;
+            request = copy.expect("retried requests are cloned");
+        }
+    }
+
+    /// Sends a request once, through the cassette if there is one.
+    async fn execute(&self, request: Request) -> Result<Response, Error> {
+        #[cfg(feature = "cassette")]
+        if let Some(cassette) = &self.cassette {
+            return cassette.execute(&self.inner, request).await;
+        }
+        Ok(self.inner.execute(request).await?)
+    }
+
+    /// Turns a response with a client or server error status into [`Error::Status`].
+    ///
+    /// # Errors
+    /// If the status of the response is 4xx or 5xx.
+    pub async fn error_for_status(method: Method, response: Response) -> Result<Response, Error> {
+        let status = response.status();
+        if !(status.is_client_error() || status.is_server_error()) {
+            return Ok(response);
+        }
+        let url = response.url().to_string();
+        let body = response.text().await.unwrap_or_default();
+        Err(Error::Status {
+            method,
+            url,
+            status,
+            body,
+        })
+    }
+
+    /// Sends a request and parses the JSON response.
+    ///
+    /// # Errors
+    /// If the request fails, the response has an error status or is not valid JSON.
+    pub async fn send_json<T>(&self, request: Request) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let method = request.method().clone();
+        let response = self.send(request).await?;
+        let response = Self::error_for_status(method, response).await?;
+        let url = response.url().to_string();
+        let bytes = response.bytes().await?;
+        serde_json::from_slice(&bytes).map_err(|source| Error::Deserialize { url, source })
+    }
+
+    /// Sends a `GET` request and parses the JSON response.
+    ///
+    /// # Errors
+    /// If the request fails, the response has an error status or is not valid JSON.
+    pub async fn get_json<T>(&self, url: Url) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let mut request = Request::new(Method::GET, url);
+        request
+            .headers_mut()
+            .insert(header::ACCEPT, HeaderValue::from_static("application/json"));
+        self.send_json(request).await
+    }
+
+    /// Sends a `POST` request with a JSON body and parses the JSON response.
+    ///
+    /// # Errors
+    /// If the body cannot be serialized, the request fails,
+    /// the response has an error status or is not valid JSON.
+    pub async fn post_json<B, T>(&self, url: Url, body: &B) -> Result<T, Error>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let request = Self::json_request(Method::POST, url, body)?;
+        self.send_json(request).await
+    }
+
+    /// Sends a `PUT` request with a JSON body and parses the JSON response.
+    ///
+    /// # Errors
+    /// If the body cannot be serialized, the request fails,
+    /// the response has an error status or is not valid JSON.
+    pub async fn put_json<B, T>(&self, url: Url, body: &B) -> Result<T, Error>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let request = Self::json_request(Method::PUT, url, body)?;
+        self.send_json(request).await
+    }
+
+    /// Sends a `PATCH` request with a JSON body and parses the JSON response.
+    ///
+    /// # Errors
+    /// If the body cannot be serialized, the request fails,
+    /// the response has an error status or is not valid JSON.
+    pub async fn patch_json<B, T>(&self, url: Url, body: &B) -> Result<T, Error>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let request = Self::json_request(Method::PATCH, url, body)?;
+        self.send_json(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Client, Error};
+    use crate::RetryPolicy;
+    use reqwest::{StatusCode, Url};
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use std::time::Duration;
+    use wiremock::matchers::{body_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_json() -> color_eyre::eyre::Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/items"))
+            .and(header("user-agent", "my-action/1.0"))
+            .and(header("content-type", "application/json"))
+            .and(body_json(serde_json::json!({ "name": "a" })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({ "id": 1 })))
+            .mount(&server)
+            .await;
+
+        let client = Client::builder().user_agent("my-action/1.0").build()?;
+        let url = Url::parse(&server.uri())?.join("items")?;
+        let created: serde_json::Value = client
+            .post_json(url, &serde_json::json!({ "name": "a" }))
+            .await?;
+        sim_assert_eq!(created, serde_json::json!({ "id": 1 }));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_error_status() -> color_eyre::eyre::Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("not found"))
+            .mount(&server)
+            .await;
+
+        let client = Client::new()?;
+        let url = Url::parse(&server.uri())?.join("missing")?;
+        let err = client.get_json::<serde_json::Value>(url).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Status { status: StatusCode::NOT_FOUND, ref body, .. } if body == "not found"
+        ));
+        Ok(())
+    }
+
+    fn fast_retries() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry() -> color_eyre::eyre::Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "0"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!(true)))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = Client::builder().retry(fast_retries()).build()?;
+        let url = Url::parse(&server.uri())?.join("flaky")?;
+        assert!(client.get_json::<bool>(url.clone()).await?);
+        let err = client
+            .post_json::<_, bool>(url, &serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Status {
+                status: StatusCode::SERVICE_UNAVAILABLE,
+                ..
+            }
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_timeout() -> color_eyre::eyre::Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let client = Client::builder()
+            .retry(fast_retries())
+            .timeout(Duration::from_millis(50))
+            .build()?;
+        let url = Url::parse(&server.uri())?.join("slow")?;
+        let err = client.get_json::<bool>(url).await.unwrap_err();
+        assert!(matches!(err, Error::Request(ref err) if err.is_timeout()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_proxy() -> color_eyre::eyre::Result<()> {
+        let proxy = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/proxied"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!(true)))
+            .expect(1)
+            .mount(&proxy)
+            .await;
+
+        let env = action_core::env::EnvMap::from_iter([("http_proxy", proxy.uri())]);
+        let client = Client::builder().proxy_env(env).build()?;
+        let url = Url::parse("http://proxied.invalid/proxied")?;
+        assert!(client.get_json::<bool>(url).await?);
+        Ok(())
+    }
+}
@@ -0,0 +1,371 @@
+//! Downloads to files, resumed after interruptions and verified against a checksum.
+
+use crate::progress::Progress;
+use crate::{Client, Error};
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use reqwest::{Method, Request, Response, StatusCode, Url};
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+
+/// Expected digest of a download, as a hexadecimal string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Checksum {
+    Sha256(String),
+    Sha512(String),
+}
+
+impl Checksum {
+    fn algorithm(&self) -> &'static ring::digest::Algorithm {
+        match self {
+            Self::Sha256(_) => &ring::digest::SHA256,
+            Self::Sha512(_) => &ring::digest::SHA512,
+        }
+    }
+
+    fn expected(&self) -> &str {
+        match self {
+            Self::Sha256(digest) | Self::Sha512(digest) => digest,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Sha256(_) => "SHA-256",
+            Self::Sha512(_) => "SHA-512",
+        }
+    }
+}
+
+/// Options of [`Client::download`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadOptions {
+    /// Digest the downloaded file must match.
+    pub checksum: Option<Checksum>,
+    /// Maximum number of times an interrupted download is resumed.
+    pub max_resumes: u32,
+    /// Headers of the download requests, e.g. `Accept`.
+    pub headers: HeaderMap,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            checksum: None,
+            max_resumes: 5,
+            headers: HeaderMap::new(),
+        }
+    }
+}
+
+/// Validator for `If-Range`, so a resumed download restarts if the file changed in between.
+///
+/// Weak entity tags cannot be used for ranges.
+fn validator(headers: &HeaderMap) -> Option<HeaderValue> {
+    headers
+        .get(header::ETAG)
+        .filter(|etag| !etag.as_bytes().starts_with(b"W/"))
+        .or_else(|| headers.get(header::LAST_MODIFIED))
+        .cloned()
+}
+
+/// First byte of the partial content of a `206 Partial Content` response.
+fn content_range_start(response: &Response) -> Option<u64> {
+    if response.status() != StatusCode::PARTIAL_CONTENT {
+        return None;
+    }
+    let range = response
+        .headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?;
+    let (start, _) = range.strip_prefix("bytes ")?.split_once('-')?;
+    start.trim().parse().ok()
+}
+
+/// Total size of the file, from either a complete or a partial response.
+fn total_size(response: &Response) -> Option<u64> {
+    if response.status() != StatusCode::PARTIAL_CONTENT {
+        return response.content_length();
+    }
+    let range = response
+        .headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?;
+    range.rsplit_once('/')?.1.parse().ok()
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+impl Client {
+    /// Downloads `url` to the file at `path`, which is removed if the download fails.
+    ///
+    /// If the connection drops, the download is resumed with a `Range` request
+    /// for the missing bytes, or restarted if the server does not support ranges.
+    /// The progress is reported to the log.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// # Errors
+    /// If the request fails, the download is interrupted more often than allowed,
+    /// the file cannot be written or does not match the checksum.
+    pub async fn download(
+        &self,
+        url: Url,
+        path: &Path,
+        options: &DownloadOptions,
+    ) -> Result<u64, Error> {
+        let result = self.try_download(url, path, options).await;
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(path).await;
+        }
+        result
+    }
+
+    async fn try_download(
+        &self,
+        url: Url,
+        path: &Path,
+        options: &DownloadOptions,
+    ) -> Result<u64, Error> {
+        let io_error = |source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+        let name = path.file_name().map_or_else(
+            || url.to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        let new_digest = || {
+            options
+                .checksum
+                .as_ref()
+                .map(|checksum| ring::digest::Context::new(checksum.algorithm()))
+        };
+
+        let mut file = tokio::fs::File::create(path).await.map_err(io_error)?;
+        let mut digest = new_digest();
+        let mut progress = Progress::new("Downloading", &name, None);
+        let mut written = 0;
+        let mut validator = None;
+        let mut resumes = 0;
+        loop {
+            let mut request = Request::new(Method::GET, url.clone());
+            *request.headers_mut() = options.headers.clone();
+            if written > 0 {
+                let range =
+                    HeaderValue::from_str(&format!("bytes={written}-")).map_err(|source| {
+                        Error::InvalidHeader {
+                            name: header::RANGE,
+                            source,
+                        }
+                    })?;
+                request.headers_mut().insert(header::RANGE, range);
+                if let Some(validator) = &validator {
+                    request
+                        .headers_mut()
+                        .insert(header::IF_RANGE, HeaderValue::clone(validator));
+                }
+            }
+            let mut response =
+                Self::error_for_status(Method::GET, self.send(request).await?).await?;
+
+            if content_range_start(&response) != Some(written) {
+                if written > 0 {
+                    action_core::debug!("{url} cannot be resumed, restarting the download");
+                    file = tokio::fs::File::create(path).await.map_err(io_error)?;
+                    digest = new_digest();
+                    written = 0;
+                }
+                validator = self::validator(response.headers());
+                progress = Progress::new("Downloading", &name, total_size(&response));
+            }
+
+            let interrupted = loop {
+                match response.chunk().await {
+                    Ok(Some(chunk)) => {
+                        file.write_all(&chunk).await.map_err(io_error)?;
+                        if let Some(digest) = &mut digest {
+                            digest.update(&chunk);
+                        }
+                        written += chunk.len() as u64;
+                        progress.advance(chunk.len() as u64);
+                    }
+                    Ok(None) => break None,
+                    Err(err) => break Some(err),
+                }
+            };
+            match interrupted {
+                None => break,
+                Some(err) if resumes < options.max_resumes => {
+                    let delay = self.retry.backoff(resumes);
+                    resumes += 1;
+                    action_core::warning!(
+                        "Download of {url} was interrupted after {written} bytes ({err}), resuming in {}ms (attempt {resumes} of {})",
+                        delay.as_millis(),
+                        options.max_resumes
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Some(err) => return Err(err.into()),
+            }
+        }
+        file.flush().await.map_err(io_error)?;
+
+        if let (Some(checksum), Some(digest)) = (&options.checksum, digest) {
+            let actual = hex(digest.finish().as_ref());
+            if !actual.eq_ignore_ascii_case(checksum.expected().trim()) {
+                return Err(Error::ChecksumMismatch {
+                    path: path.to_path_buf(),
+                    algorithm: checksum.name(),
+                    expected: checksum.expected().to_string(),
+                    actual,
+                });
+            }
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Checksum, DownloadOptions};
+    use crate::{Client, Error, RetryPolicy};
+    use parking_lot::Mutex;
+    use reqwest::Url;
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const BODY: &[u8] = b"hello world";
+    const SHA256: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+    const SHA512: &str = "309ecc489c12d6eb4cc40f50c902f2b4d0ed77ee511a7c7a9bcd3ca86d4cd86f989dd35bc5ff499670da34255b45b0cfd830e81f605dcf7dc5542e93ae9cd76f";
+
+    /// Serves [`BODY`], dropping the first `drops` connections after 5 bytes of the body.
+    ///
+    /// Returns the URL and the `Range` headers of the received requests.
+    async fn serve(drops: usize) -> (Url, Arc<Mutex<Vec<Option<String>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/file", listener.local_addr().unwrap())).unwrap();
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&ranges);
+        tokio::spawn(async move {
+            for connection in 0.. {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let read = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..read]);
+                }
+                let request = String::from_utf8_lossy(&request).to_lowercase();
+                let range = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("range: bytes="))
+                    .map(|range| range.trim_end_matches('-').to_string());
+                received.lock().push(range.clone());
+
+                let start: usize = range.map_or(0, |range| range.parse().unwrap());
+                let head = if start == 0 {
+                    format!(
+                        "HTTP/1.1 200 OK\r\ncontent-length: {}\r\netag: \"v1\"\r\n\r\n",
+                        BODY.len()
+                    )
+                } else {
+                    format!(
+                        "HTTP/1.1 206 Partial Content\r\ncontent-length: {}\r\ncontent-range: bytes {start}-{}/{}\r\n\r\n",
+                        BODY.len() - start,
+                        BODY.len() - 1,
+                        BODY.len()
+                    )
+                };
+                stream.write_all(head.as_bytes()).await.unwrap();
+                let end = if connection < drops {
+                    (start + 5).min(BODY.len())
+                } else {
+                    BODY.len()
+                };
+                stream.write_all(&BODY[start..end]).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+        (url, ranges)
+    }
+
+    fn client() -> color_eyre::eyre::Result<Client> {
+        Ok(Client::builder()
+            .retry(RetryPolicy {
+                max_retries: 0,
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(10),
+                ..RetryPolicy::default()
+            })
+            .build()?)
+    }
+
+    #[tokio::test]
+    async fn test_resume() -> color_eyre::eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("file");
+        let (url, ranges) = serve(2).await;
+
+        let options = DownloadOptions {
+            checksum: Some(Checksum::Sha256(SHA256.to_uppercase())),
+            ..DownloadOptions::default()
+        };
+        let written = client()?.download(url, &path, &options).await?;
+        sim_assert_eq!(written, BODY.len() as u64);
+        sim_assert_eq!(std::fs::read(&path)?, BODY);
+        sim_assert_eq!(
+            *ranges.lock(),
+            [None, Some("5".to_string()), Some("10".to_string())]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_checksum_mismatch() -> color_eyre::eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("file");
+        let (url, _) = serve(0).await;
+
+        let options = DownloadOptions {
+            checksum: Some(Checksum::Sha512(SHA512.to_string())),
+            ..DownloadOptions::default()
+        };
+        client()?.download(url.clone(), &path, &options).await?;
+
+        let options = DownloadOptions {
+            checksum: Some(Checksum::Sha256(SHA512.to_string())),
+            ..DownloadOptions::default()
+        };
+        let err = client()?.download(url, &path, &options).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::ChecksumMismatch { ref actual, .. } if actual == SHA256
+        ));
+        assert!(!path.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_too_many_interruptions() -> color_eyre::eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("file");
+        let (url, ranges) = serve(usize::MAX).await;
+
+        let options = DownloadOptions {
+            max_resumes: 1,
+            ..DownloadOptions::default()
+        };
+        let err = client()?.download(url, &path, &options).await.unwrap_err();
+        assert!(matches!(err, Error::Request(_)));
+        assert!(!path.exists());
+        sim_assert_eq!(ranges.lock().len(), 2);
+        Ok(())
+    }
+}
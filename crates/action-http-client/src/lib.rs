@@ -1,15 +1,21 @@
-#[must_use]
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
-}
+//! HTTP client for actions.
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+pub mod auth;
+#[cfg(feature = "cassette")]
+pub mod cassette;
+pub mod client;
+pub mod download;
+pub mod progress;
+pub mod proxy;
+pub mod retry;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use auth::{
+    BasicCredentialHandler, BearerCredentialHandler, PersonalAccessTokenCredentialHandler,
+    RequestHandler,
+};
+#[cfg(feature = "cassette")]
+pub use cassette::Cassette;
+pub use client::{Client, ClientBuilder, Error};
+pub use download::{Checksum, DownloadOptions};
+pub use reqwest::{Body, Method, Request, Response, StatusCode, Url, header};
+pub use retry::RetryPolicy;
@@ -0,0 +1,56 @@
+//! Progress of file transfers, reported to the log.
+
+/// Interval between reports if the size of the transfer is unknown.
+const UNKNOWN_SIZE_INTERVAL: u64 = 10 * 1024 * 1024;
+
+/// Progress of a file transfer, such as `Downloading file.tar.gz: 30% (3 of 10 bytes)`.
+#[derive(Debug)]
+pub struct Progress {
+    action: &'static str,
+    name: String,
+    total: Option<u64>,
+    transferred: u64,
+    next_report: u64,
+}
+
+impl Progress {
+    /// Starts reporting the progress of `action` on `name`, transferring `total` bytes if known.
+    #[must_use]
+    pub fn new(action: &'static str, name: impl Into<String>, total: Option<u64>) -> Self {
+        let mut progress = Self {
+            action,
+            name: name.into(),
+            total: total.filter(|total| *total > 0),
+            transferred: 0,
+            next_report: 0,
+        };
+        progress.next_report = progress.interval();
+        progress
+    }
+
+    /// Reports about every 10 percent of the transfer, or every 10 MiB if the size is unknown.
+    fn interval(&self) -> u64 {
+        self.total
+            .map_or(UNKNOWN_SIZE_INTERVAL, |total| total.div_ceil(10))
+    }
+
+    /// Records that `bytes` more were transferred, logging the progress when the next interval is reached.
+    pub fn advance(&mut self, bytes: u64) {
+        self.transferred += bytes;
+        if self.transferred < self.next_report {
+            return;
+        }
+        match self.total {
+            Some(total) => action_core::info!(
+                "{} {}: {}% ({} of {total} bytes)",
+                self.action,
+                self.name,
+                self.transferred.saturating_mul(100) / total,
+                self.transferred,
+            ),
+            None => action_core::info!("{} {}: {} bytes", self.action, self.name, self.transferred),
+        }
+        let interval = self.interval();
+        self.next_report = (self.transferred / interval + 1) * interval;
+    }
+}
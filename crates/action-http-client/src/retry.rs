@@ -0,0 +1,188 @@
+//! Retries of failed requests with exponential backoff.
+
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Method, StatusCode};
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// How failed requests are retried.
+///
+/// Only idempotent requests are retried, after a connection error or a `429` or `5xx` status.
+/// Clients that handle rate limits themselves, e.g. to wait for their reset, do not retry `429`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of retries of a request.
+    pub max_retries: u32,
+    /// Upper bound of the delay before the first retry, doubled for every further retry.
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay before any retry,
+    /// a longer `Retry-After` stops retrying and returns the response.
+    pub max_backoff: Duration,
+    /// Whether `429 Too Many Requests` responses are retried.
+    pub retry_rate_limited: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+            retry_rate_limited: true,
+        }
+    }
+}
+
+/// Random number for the jitter, without depending on a random number generator.
+fn random() -> u64 {
+    std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish()
+}
+
+/// Whether repeating the request has the same effect as sending it once.
+#[must_use]
+pub fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE | Method::TRACE
+    )
+}
+
+/// Whether a response with `status` may succeed if the request is retried.
+#[must_use]
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Delay requested by the `Retry-After` header of a response, in seconds.
+#[must_use]
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+impl RetryPolicy {
+    /// Never retries requests.
+    #[must_use]
+    pub fn disabled() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry number `attempt`, starting at 0.
+    ///
+    /// Uses "full jitter": a random delay up to the exponentially growing ceiling,
+    /// which spreads out retries of many clients failing at the same time.
+    #[must_use]
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff);
+        let ceiling_millis = u64::try_from(ceiling.as_millis()).unwrap_or(u64::MAX);
+        Duration::from_millis(random() % ceiling_millis.saturating_add(1))
+    }
+
+    /// Delay before retrying after a response, or `None` if it must not be retried.
+    #[must_use]
+    pub fn delay(
+        &self,
+        method: &Method,
+        status: StatusCode,
+        headers: &HeaderMap,
+        attempt: u32,
+    ) -> Option<Duration> {
+        if attempt >= self.max_retries
+            || !is_idempotent(method)
+            || !is_retryable_status(status)
+            || (status == StatusCode::TOO_MANY_REQUESTS && !self.retry_rate_limited)
+        {
+            return None;
+        }
+        match retry_after(headers) {
+            Some(delay) => (delay <= self.max_backoff).then_some(delay),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RetryPolicy;
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+    use reqwest::{Method, StatusCode};
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use std::time::Duration;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(1000),
+            ..RetryPolicy::default()
+        };
+        for attempt in 0..10 {
+            let ceiling = Duration::from_millis(100 * 2u64.pow(attempt)).min(policy.max_backoff);
+            assert!(policy.backoff(attempt) <= ceiling);
+        }
+    }
+
+    #[test]
+    fn test_delay() {
+        let policy = RetryPolicy::default();
+        let none = HeaderMap::new();
+        assert!(
+            policy
+                .delay(&Method::GET, StatusCode::BAD_GATEWAY, &none, 0)
+                .is_some()
+        );
+        sim_assert_eq!(
+            policy.delay(&Method::POST, StatusCode::BAD_GATEWAY, &none, 0),
+            None
+        );
+        sim_assert_eq!(
+            policy.delay(&Method::GET, StatusCode::NOT_FOUND, &none, 0),
+            None
+        );
+        sim_assert_eq!(
+            policy.delay(&Method::GET, StatusCode::BAD_GATEWAY, &none, 3),
+            None
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        sim_assert_eq!(
+            policy.delay(&Method::PUT, StatusCode::TOO_MANY_REQUESTS, &headers, 0),
+            Some(Duration::from_secs(7))
+        );
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        sim_assert_eq!(
+            policy.delay(&Method::PUT, StatusCode::TOO_MANY_REQUESTS, &headers, 0),
+            None
+        );
+
+        let policy = RetryPolicy {
+            retry_rate_limited: false,
+            ..RetryPolicy::default()
+        };
+        sim_assert_eq!(
+            policy.delay(&Method::GET, StatusCode::TOO_MANY_REQUESTS, &none, 0),
+            None
+        );
+        assert!(
+            policy
+                .delay(&Method::GET, StatusCode::SERVICE_UNAVAILABLE, &none, 0)
+                .is_some()
+        );
+    }
+}
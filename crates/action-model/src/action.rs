@@ -0,0 +1,730 @@
+//! Model of the action manifest (`action.yml`).
+//!
+//! Maps such as `inputs`, `outputs`, `with` and `env` keep the order of their keys,
+//! so a manifest can be parsed, edited and serialized again without reordering them.
+
+use crate::scalar;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// Icons that can be used for the branding of an action.
+///
+/// See <https://docs.github.com/en/actions/sharing-automations/creating-actions/metadata-syntax-for-github-actions#brandingicon>.
+pub const BRANDING_ICONS: &[&str] = &[
+    "activity",
+    "airplay",
+    "alert-circle",
+    "alert-octagon",
+    "alert-triangle",
+    "align-center",
+    "align-justify",
+    "align-left",
+    "align-right",
+    "anchor",
+    "aperture",
+    "archive",
+    "arrow-down",
+    "arrow-down-circle",
+    "arrow-down-left",
+    "arrow-down-right",
+    "arrow-left",
+    "arrow-left-circle",
+    "arrow-right",
+    "arrow-right-circle",
+    "arrow-up",
+    "arrow-up-circle",
+    "arrow-up-left",
+    "arrow-up-right",
+    "at-sign",
+    "award",
+    "bar-chart",
+    "bar-chart-2",
+    "battery",
+    "battery-charging",
+    "bell",
+    "bell-off",
+    "bluetooth",
+    "bold",
+    "book",
+    "book-open",
+    "bookmark",
+    "box",
+    "briefcase",
+    "calendar",
+    "camera",
+    "camera-off",
+    "cast",
+    "check",
+    "check-circle",
+    "check-square",
+    "chevron-down",
+    "chevron-left",
+    "chevron-right",
+    "chevron-up",
+    "chevrons-down",
+    "chevrons-left",
+    "chevrons-right",
+    "chevrons-up",
+    "chrome",
+    "circle",
+    "clipboard",
+    "clock",
+    "cloud",
+    "cloud-drizzle",
+    "cloud-lightning",
+    "cloud-off",
+    "cloud-rain",
+    "cloud-snow",
+    "code",
+    "codepen",
+    "codesandbox",
+    "command",
+    "compass",
+    "copy",
+    "corner-down-left",
+    "corner-down-right",
+    "corner-left-down",
+    "corner-left-up",
+    "corner-right-down",
+    "corner-right-up",
+    "corner-up-left",
+    "corner-up-right",
+    "cpu",
+    "credit-card",
+    "crop",
+    "crosshair",
+    "database",
+    "delete",
+    "disc",
+    "dollar-sign",
+    "download",
+    "download-cloud",
+    "dribbble",
+    "droplet",
+    "edit",
+    "edit-2",
+    "edit-3",
+    "external-link",
+    "eye",
+    "eye-off",
+    "facebook",
+    "fast-forward",
+    "feather",
+    "figma",
+    "file",
+    "file-minus",
+    "file-plus",
+    "file-text",
+    "film",
+    "filter",
+    "flag",
+    "folder",
+    "folder-minus",
+    "folder-plus",
+    "framer",
+    "gift",
+    "git-branch",
+    "git-commit",
+    "git-merge",
+    "git-pull-request",
+    "github",
+    "gitlab",
+    "globe",
+    "grid",
+    "hard-drive",
+    "hash",
+    "headphones",
+    "heart",
+    "help-circle",
+    "home",
+    "image",
+    "inbox",
+    "info",
+    "instagram",
+    "italic",
+    "layers",
+    "layout",
+    "life-buoy",
+    "link",
+    "link-2",
+    "linkedin",
+    "list",
+    "loader",
+    "lock",
+    "log-in",
+    "log-out",
+    "mail",
+    "map",
+    "map-pin",
+    "maximize",
+    "maximize-2",
+    "menu",
+    "message-circle",
+    "message-square",
+    "mic",
+    "mic-off",
+    "minimize",
+    "minimize-2",
+    "minus",
+    "minus-circle",
+    "minus-square",
+    "monitor",
+    "moon",
+    "more-horizontal",
+    "more-vertical",
+    "move",
+    "music",
+    "navigation",
+    "navigation-2",
+    "octagon",
+    "package",
+    "paperclip",
+    "pause",
+    "pause-circle",
+    "pen-tool",
+    "percent",
+    "phone",
+    "phone-call",
+    "phone-forwarded",
+    "phone-incoming",
+    "phone-missed",
+    "phone-off",
+    "phone-outgoing",
+    "pie-chart",
+    "play",
+    "play-circle",
+    "plus",
+    "plus-circle",
+    "plus-square",
+    "pocket",
+    "power",
+    "printer",
+    "radio",
+    "refresh-ccw",
+    "refresh-cw",
+    "repeat",
+    "rewind",
+    "rotate-ccw",
+    "rotate-cw",
+    "rss",
+    "save",
+    "scissors",
+    "search",
+    "send",
+    "server",
+    "settings",
+    "share",
+    "share-2",
+    "shield",
+    "shield-off",
+    "shopping-bag",
+    "shopping-cart",
+    "shuffle",
+    "sidebar",
+    "skip-back",
+    "skip-forward",
+    "slack",
+    "slash",
+    "sliders",
+    "smartphone",
+    "speaker",
+    "square",
+    "star",
+    "stop-circle",
+    "sun",
+    "sunrise",
+    "sunset",
+    "table",
+    "tablet",
+    "tag",
+    "target",
+    "terminal",
+    "thermometer",
+    "thumbs-down",
+    "thumbs-up",
+    "toggle-left",
+    "toggle-right",
+    "trash",
+    "trash-2",
+    "trello",
+    "trending-down",
+    "trending-up",
+    "triangle",
+    "truck",
+    "tv",
+    "twitch",
+    "twitter",
+    "type",
+    "umbrella",
+    "underline",
+    "unlock",
+    "upload",
+    "upload-cloud",
+    "user",
+    "user-check",
+    "user-minus",
+    "user-plus",
+    "user-x",
+    "users",
+    "video",
+    "video-off",
+    "voicemail",
+    "volume",
+    "volume-1",
+    "volume-2",
+    "volume-x",
+    "watch",
+    "wifi",
+    "wifi-off",
+    "wind",
+    "x",
+    "x-circle",
+    "x-square",
+    "youtube",
+    "zap",
+    "zap-off",
+    "zoom-in",
+    "zoom-out",
+];
+
+/// Colors that can be used for the branding of an action.
+pub const BRANDING_COLORS: &[&str] = &[
+    "white",
+    "black",
+    "yellow",
+    "blue",
+    "green",
+    "orange",
+    "red",
+    "purple",
+    "gray-dark",
+];
+
+/// An input of an action, provided with `with:` by the steps using it.
+///
+/// A scalar `default` such as `false` or `1` is read as a string.
+#[derive(Default, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+pub struct Input {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "deprecationMessage", skip_serializing_if = "Option::is_none")]
+    pub deprecation_message: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub default: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+}
+
+#[derive(Default, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+pub struct Output {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Value of the output (composite actions only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+#[derive(Default, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+pub struct Branding {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+/// A step of a composite action.
+#[derive(Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Step {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "if", skip_serializing_if = "Option::is_none")]
+    pub r#if: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uses: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::map",
+        skip_serializing_if = "IndexMap::is_empty"
+    )]
+    pub with: IndexMap<String, String>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::map",
+        skip_serializing_if = "IndexMap::is_empty"
+    )]
+    pub env: IndexMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_directory: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub continue_on_error: Option<String>,
+}
+
+/// Node.js runtime of a JavaScript action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeRuntime {
+    Node12,
+    Node16,
+    Node20,
+    Node24,
+}
+
+/// A JavaScript action.
+#[derive(PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct JavaScript {
+    pub using: NodeRuntime,
+    pub main: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pre: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pre_if: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_if: Option<String>,
+}
+
+/// A Docker container action.
+#[derive(PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Docker {
+    pub image: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pre_entrypoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pre_if: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entrypoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_entrypoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_if: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::vec",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub args: Vec<String>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::map",
+        skip_serializing_if = "IndexMap::is_empty"
+    )]
+    pub env: IndexMap<String, String>,
+}
+
+/// A composite action.
+#[derive(PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct Composite {
+    pub steps: Vec<Step>,
+}
+
+/// How the action is run, depending on the `using` key.
+#[derive(PartialEq, Eq, Debug)]
+pub enum Runs {
+    JavaScript(JavaScript),
+    Docker(Docker),
+    Composite(Composite),
+}
+
+impl Runs {
+    /// Value of the `using` key.
+    #[must_use]
+    pub fn using(&self) -> &'static str {
+        match self {
+            Self::JavaScript(JavaScript { using, .. }) => match using {
+                NodeRuntime::Node12 => "node12",
+                NodeRuntime::Node16 => "node16",
+                NodeRuntime::Node20 => "node20",
+                NodeRuntime::Node24 => "node24",
+            },
+            Self::Docker(_) => "docker",
+            Self::Composite(_) => "composite",
+        }
+    }
+}
+
+impl Serialize for Runs {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::Error;
+        let value = match self {
+            Self::JavaScript(runs) => serde_yaml::to_value(runs),
+            Self::Docker(runs) => serde_yaml::to_value(runs),
+            Self::Composite(runs) => serde_yaml::to_value(runs),
+        }
+        .map_err(S::Error::custom)?;
+        let serde_yaml::Value::Mapping(fields) = value else {
+            return Err(S::Error::custom("runs must serialize to a mapping"));
+        };
+        // `using` always comes first
+        let using = serde_yaml::Value::from("using");
+        let mut mapping = serde_yaml::Mapping::new();
+        mapping.insert(using.clone(), self.using().into());
+        mapping.extend(fields.into_iter().filter(|(key, _)| *key != using));
+        mapping.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Runs {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let mut mapping = serde_yaml::Mapping::deserialize(deserializer)?;
+        let using = mapping
+            .get("using")
+            .and_then(serde_yaml::Value::as_str)
+            .ok_or_else(|| D::Error::missing_field("using"))?
+            .to_string();
+        let value = |mapping| serde_yaml::Value::Mapping(mapping);
+        match using.as_str() {
+            "docker" => {
+                mapping.remove("using");
+                serde_yaml::from_value(value(mapping))
+                    .map(Self::Docker)
+                    .map_err(D::Error::custom)
+            }
+            "composite" => {
+                mapping.remove("using");
+                serde_yaml::from_value(value(mapping))
+                    .map(Self::Composite)
+                    .map_err(D::Error::custom)
+            }
+            _ => serde_yaml::from_value(value(mapping))
+                .map(Self::JavaScript)
+                .map_err(D::Error::custom),
+        }
+    }
+}
+
+#[derive(Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct Manifest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branding: Option<Branding>,
+
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub inputs: IndexMap<String, Input>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub outputs: IndexMap<String, Output>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runs: Option<Runs>,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq, Clone)]
+pub enum ValidationError {
+    #[error("invalid branding icon {0:?}")]
+    BrandingIcon(String),
+    #[error("invalid branding color {0:?}, must be one of {BRANDING_COLORS:?}")]
+    BrandingColor(String),
+    #[error("output {0:?} of a composite action requires a `value`")]
+    MissingOutputValue(String),
+}
+
+impl Manifest {
+    /// Parses a manifest from a YAML string.
+    ///
+    /// # Errors
+    /// If the string is not a valid action manifest.
+    pub fn from_yaml(yaml: &str) -> Result<Self, serde_yaml::Error> {
+        serde_yaml::from_str(yaml)
+    }
+
+    /// Parses a manifest from a reader.
+    ///
+    /// # Errors
+    /// If the content is not a valid action manifest.
+    pub fn from_reader(reader: impl std::io::Read) -> Result<Self, serde_yaml::Error> {
+        serde_yaml::from_reader(reader)
+    }
+
+    /// Serializes the manifest to YAML.
+    ///
+    /// # Errors
+    /// If the manifest cannot be serialized.
+    pub fn to_yaml(&self) -> Result<String, serde_yaml::Error> {
+        serde_yaml::to_string(self)
+    }
+
+    /// Validates the manifest against the constraints of GitHub
+    /// that are not enforced when parsing.
+    ///
+    /// The branding icon is checked last, so that callers can tolerate
+    /// [`ValidationError::BrandingIcon`] for icons added by GitHub since.
+    ///
+    /// # Errors
+    /// If the branding is invalid or an output of a composite action has no value.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let branding = self.branding.as_ref();
+        if let Some(color) = branding.and_then(|branding| branding.color.as_ref())
+            && !BRANDING_COLORS.contains(&color.as_str())
+        {
+            return Err(ValidationError::BrandingColor(color.clone()));
+        }
+        if let Some(Runs::Composite(_)) = &self.runs
+            && let Some((name, _)) = self
+                .outputs
+                .iter()
+                .find(|(_, output)| output.value.is_none())
+        {
+            return Err(ValidationError::MissingOutputValue(name.clone()));
+        }
+        if let Some(icon) = branding.and_then(|branding| branding.icon.as_ref())
+            && !BRANDING_ICONS.contains(&icon.as_str())
+        {
+            return Err(ValidationError::BrandingIcon(icon.clone()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Docker, JavaScript, Manifest, NodeRuntime, Runs, ValidationError};
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    const COMPOSITE: &str = r#"name: composite
+description: A composite action
+branding:
+  icon: box
+  color: orange
+inputs:
+  version:
+    description: Version
+    required: true
+  depth:
+    description: Fetch depth
+    default: '0'
+outputs:
+  random:
+    description: Random number
+    value: ${{ steps.random.outputs.number }}
+runs:
+  using: composite
+  steps:
+  - id: random
+    run: echo "number=$RANDOM" >> "$GITHUB_OUTPUT"
+    shell: bash
+  - uses: actions/checkout@v4
+    with:
+      lfs: 'true'
+      fetch-depth: ${{ inputs.depth }}
+    continue-on-error: 'true'
+"#;
+
+    #[test]
+    fn test_round_trip_composite() -> color_eyre::eyre::Result<()> {
+        let manifest = Manifest::from_yaml(COMPOSITE)?;
+        manifest.validate()?;
+        let Some(Runs::Composite(runs)) = &manifest.runs else {
+            panic!("expected composite action, got {:?}", manifest.runs);
+        };
+        sim_assert_eq!(runs.steps.len(), 2);
+        sim_assert_eq!(runs.steps[1].with["lfs"], "true");
+        sim_assert_eq!(manifest.to_yaml()?, COMPOSITE);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_scalars() -> color_eyre::eyre::Result<()> {
+        let manifest = Manifest::from_yaml(
+            "name: test\ninputs:\n  depth:\n    default: 0\nruns:\n  using: docker\n  image: Dockerfile\n  args: [1, true]\n",
+        )?;
+        sim_assert_eq!(manifest.inputs["depth"].default.as_deref(), Some("0"));
+        sim_assert_eq!(
+            manifest.runs,
+            Some(Runs::Docker(Docker {
+                image: "Dockerfile".into(),
+                pre_entrypoint: None,
+                pre_if: None,
+                entrypoint: None,
+                post_entrypoint: None,
+                post_if: None,
+                args: vec!["1".into(), "true".into()],
+                env: [].into_iter().collect(),
+            }))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_javascript() -> color_eyre::eyre::Result<()> {
+        let manifest = Manifest::from_yaml(
+            "name: test\nruns:\n  using: node20\n  main: index.js\n  post: cleanup.js\n",
+        )?;
+        sim_assert_eq!(
+            manifest.runs,
+            Some(Runs::JavaScript(JavaScript {
+                using: NodeRuntime::Node20,
+                main: "index.js".into(),
+                pre: None,
+                pre_if: None,
+                post: Some("cleanup.js".into()),
+                post_if: None,
+            }))
+        );
+        assert!(Manifest::from_yaml("name: test\nruns:\n  using: node20\n").is_err());
+        assert!(Manifest::from_yaml("name: test\nruns:\n  using: java\n").is_err());
+        assert!(Manifest::from_yaml("name: test\nruns:\n  using: docker\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_branding() -> color_eyre::eyre::Result<()> {
+        let manifest = Manifest::from_yaml("name: test\nbranding:\n  icon: coffee\n")?;
+        sim_assert_eq!(
+            manifest.validate(),
+            Err(ValidationError::BrandingIcon("coffee".into()))
+        );
+        let manifest = Manifest::from_yaml("name: test\nbranding:\n  color: pink\n")?;
+        sim_assert_eq!(
+            manifest.validate(),
+            Err(ValidationError::BrandingColor("pink".into()))
+        );
+        let manifest =
+            Manifest::from_yaml("name: test\nbranding:\n  icon: coffee\n  color: pink\n")?;
+        sim_assert_eq!(
+            manifest.validate(),
+            Err(ValidationError::BrandingColor("pink".into()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_composite_outputs() -> color_eyre::eyre::Result<()> {
+        let manifest = Manifest::from_yaml(
+            "name: test\noutputs:\n  out:\n    description: missing value\nruns:\n  using: composite\n  steps: []\n",
+        )?;
+        sim_assert_eq!(
+            manifest.validate(),
+            Err(ValidationError::MissingOutputValue("out".into()))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,557 @@
+//! Expressions of workflow and action files (`${{ <expression> }}`).
+//!
+//! See <https://docs.github.com/en/actions/writing-workflows/choosing-what-your-workflow-does/evaluate-expressions-in-workflows-and-actions>.
+//!
+//! ```
+//! use action_model::expression::{Expr, Value};
+//!
+//! let context: Value = serde_json::json!({
+//!     "github": { "event_name": "push", "ref": "refs/heads/main" }
+//! })
+//! .into();
+//! let condition = Expr::parse_condition("${{ startsWith(github.ref, 'refs/heads/') }}").unwrap();
+//! assert!(condition.evaluate(&context).unwrap().is_truthy());
+//! ```
+
+mod eval;
+mod lexer;
+mod parser;
+
+pub use eval::{EvalError, Host, Status};
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// Value of an expression, or of the context it is evaluated in.
+///
+/// Strings are compared case-insensitively and values of different types are
+/// compared as numbers, following the loose semantics of the expression language.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    #[default]
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(IndexMap<String, Value>),
+}
+
+/// Largest integer that is represented exactly by a `f64`.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Null => serializer.serialize_unit(),
+            Self::Bool(value) => serializer.serialize_bool(*value),
+            #[allow(clippy::cast_possible_truncation)]
+            Self::Number(value) if value.fract() == 0.0 && value.abs() <= MAX_SAFE_INTEGER => {
+                serializer.serialize_i64(*value as i64)
+            }
+            Self::Number(value) => serializer.serialize_f64(*value),
+            Self::String(value) => serializer.serialize_str(value),
+            Self::Array(values) => values.serialize(serializer),
+            Self::Object(values) => values.serialize(serializer),
+        }
+    }
+}
+
+impl Value {
+    /// Whether the value is truthy: everything except `false`, `0`, `NaN`, `''` and `null`.
+    #[must_use]
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Self::Null => false,
+            Self::Bool(value) => *value,
+            Self::Number(value) => *value != 0.0 && !value.is_nan(),
+            Self::String(value) => !value.is_empty(),
+            Self::Array(_) | Self::Object(_) => true,
+        }
+    }
+
+    /// Converts the value to a number, as done when comparing values of different types.
+    ///
+    /// `null` is `0`, booleans are `0` or `1`, strings are parsed (the empty string is `0`)
+    /// and anything else is `NaN`.
+    #[must_use]
+    pub fn to_number(&self) -> f64 {
+        match self {
+            Self::Null => 0.0,
+            Self::Bool(value) => f64::from(u8::from(*value)),
+            Self::Number(value) => *value,
+            Self::String(value) if value.trim().is_empty() => 0.0,
+            Self::String(value) => lexer::parse_number(value).unwrap_or(f64::NAN),
+            Self::Array(_) | Self::Object(_) => f64::NAN,
+        }
+    }
+
+    /// Property `key` of an object, looked up case-insensitively.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        let Self::Object(values) = self else {
+            return None;
+        };
+        values.get(key).or_else(|| {
+            values
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                .map(|(_, value)| value)
+        })
+    }
+}
+
+/// Formats the value as a string, as done by `format()` and string interpolation.
+///
+/// `null` is the empty string, arrays and objects are `Array` and `Object`.
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => Ok(()),
+            Self::Bool(value) => write!(f, "{value}"),
+            Self::Number(value) if value.is_nan() => write!(f, "NaN"),
+            Self::Number(value) if value.is_infinite() => {
+                write!(f, "{}Infinity", if *value < 0.0 { "-" } else { "" })
+            }
+            Self::Number(value) if value.fract() == 0.0 && value.abs() < 1e15 => {
+                write!(f, "{value:.0}")
+            }
+            Self::Number(value) => write!(f, "{value}"),
+            Self::String(value) => write!(f, "{value}"),
+            Self::Array(_) => write!(f, "Array"),
+            Self::Object(_) => write!(f, "Object"),
+        }
+    }
+}
+
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Self::Null,
+            serde_json::Value::Bool(value) => Self::Bool(value),
+            serde_json::Value::Number(value) => Self::Number(value.as_f64().unwrap_or(f64::NAN)),
+            serde_json::Value::String(value) => Self::String(value),
+            serde_json::Value::Array(values) => {
+                Self::Array(values.into_iter().map(Into::into).collect())
+            }
+            serde_json::Value::Object(values) => Self::Object(
+                values
+                    .into_iter()
+                    .map(|(key, value)| (key, value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for Value
+where
+    K: Into<String>,
+    V: Into<Value>,
+{
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Self::Object(
+            iter.into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    And,
+    Or,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Syntax tree of an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    /// A named context such as `github` or `steps`.
+    Context(String),
+    /// Property dereference: `expr.name`.
+    Property(Box<Expr>, String),
+    /// Index access: `expr[index]`.
+    Index(Box<Expr>, Box<Expr>),
+    /// Object filter: `expr.*` or `expr[*]`.
+    Wildcard(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// Call of a function, with the name spelled as documented (e.g. `startsWith`).
+    Call {
+        name: String,
+        args: Vec<Expr>,
+    },
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq, Clone)]
+pub enum ParseError {
+    #[error("unexpected character {ch:?} at position {position}")]
+    UnexpectedChar { ch: char, position: usize },
+    #[error("unterminated string starting at position {position}")]
+    UnterminatedString { position: usize },
+    #[error("invalid number {text:?} at position {position}")]
+    InvalidNumber { text: String, position: usize },
+    #[error("unexpected token `{found}` at position {position}")]
+    UnexpectedToken { found: String, position: usize },
+    #[error("unexpected end of expression")]
+    UnexpectedEnd,
+    #[error("unknown function {name:?} at position {position}")]
+    UnknownFunction { name: String, position: usize },
+    #[error(
+        "{name}() takes {} arguments but {found} were given at position {position}",
+        arity(*.min, *.max)
+    )]
+    Arity {
+        name: String,
+        min: usize,
+        max: usize,
+        found: usize,
+        position: usize,
+    },
+    #[error("expression at position {position} is nested too deeply")]
+    TooDeep { position: usize },
+    #[error("unclosed `${{{{` at position {position}")]
+    UnclosedTemplate { position: usize },
+}
+
+fn arity(min: usize, max: usize) -> String {
+    match (min, max) {
+        (min, max) if min == max => min.to_string(),
+        (min, usize::MAX) => format!("at least {min}"),
+        (min, max) => format!("{min} to {max}"),
+    }
+}
+
+impl ParseError {
+    /// Shifts the position of the error by `offset` bytes.
+    #[must_use]
+    fn offset(mut self, offset: usize) -> Self {
+        match &mut self {
+            Self::UnexpectedChar { position, .. }
+            | Self::UnterminatedString { position }
+            | Self::InvalidNumber { position, .. }
+            | Self::UnexpectedToken { position, .. }
+            | Self::UnknownFunction { position, .. }
+            | Self::Arity { position, .. }
+            | Self::TooDeep { position }
+            | Self::UnclosedTemplate { position } => *position += offset,
+            Self::UnexpectedEnd => {}
+        }
+        self
+    }
+}
+
+impl Expr {
+    /// Parses an expression, without the surrounding `${{ }}`.
+    ///
+    /// Function names and their number of arguments are checked while parsing.
+    ///
+    /// # Errors
+    /// If the expression is invalid.
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        parser::parse(source)
+    }
+
+    /// Parses the `if` condition of a job or step.
+    ///
+    /// The surrounding `${{ }}` is optional and, as done by the runner,
+    /// the condition is wrapped in `success() && (...)` unless it calls a status function.
+    ///
+    /// # Errors
+    /// If the condition is invalid.
+    pub fn parse_condition(source: &str) -> Result<Self, ParseError> {
+        let trimmed = source.trim();
+        let expr = match trimmed
+            .strip_prefix("${{")
+            .and_then(|inner| inner.strip_suffix("}}"))
+        {
+            Some(inner) => {
+                let offset = source.find("${{").unwrap_or(0) + 3;
+                Self::parse(inner).map_err(|err| err.offset(offset))?
+            }
+            None => Self::parse(source)?,
+        };
+        if expr.calls_status_function() {
+            return Ok(expr);
+        }
+        let success = Self::Call {
+            name: "success".to_string(),
+            args: Vec::new(),
+        };
+        Ok(Self::Binary(
+            BinaryOp::And,
+            Box::new(success),
+            Box::new(expr),
+        ))
+    }
+
+    /// Whether the expression calls `success()`, `always()`, `cancelled()` or `failure()`.
+    #[must_use]
+    pub fn calls_status_function(&self) -> bool {
+        let mut found = false;
+        self.walk(&mut |expr| {
+            if let Self::Call { name, .. } = expr {
+                found |= parser::STATUS_FUNCTIONS.contains(&name.as_str());
+            }
+        });
+        found
+    }
+
+    /// Names of the contexts used by the expression, e.g. `github` and `steps`.
+    #[must_use]
+    pub fn contexts(&self) -> Vec<&str> {
+        let mut contexts: Vec<&str> = Vec::new();
+        self.walk(&mut |expr| {
+            if let Self::Context(name) = expr
+                && !contexts.iter().any(|c| c.eq_ignore_ascii_case(name))
+            {
+                contexts.push(name);
+            }
+        });
+        contexts
+    }
+
+    /// Visits the expression and all of its sub-expressions.
+    pub fn walk<'a>(&'a self, visit: &mut impl FnMut(&'a Self)) {
+        visit(self);
+        match self {
+            Self::Property(expr, _) | Self::Wildcard(expr) | Self::Not(expr) => expr.walk(visit),
+            Self::Index(left, right) | Self::Binary(_, left, right) => {
+                left.walk(visit);
+                right.walk(visit);
+            }
+            Self::Call { args, .. } => args.iter().for_each(|arg| arg.walk(visit)),
+            Self::Null | Self::Bool(_) | Self::Number(_) | Self::String(_) | Self::Context(_) => {}
+        }
+    }
+
+    /// Evaluates the expression in `context`, an object of named contexts.
+    ///
+    /// Status functions assume a successful job and `hashFiles()` is not supported,
+    /// use [`Expr::evaluate_with`] to provide them.
+    ///
+    /// # Errors
+    /// If a function fails, e.g. `fromJSON()` with invalid JSON.
+    pub fn evaluate(&self, context: &Value) -> Result<Value, EvalError> {
+        self.evaluate_with(context, &())
+    }
+
+    /// Evaluates the expression in `context`, with the job status and files of `host`.
+    ///
+    /// # Errors
+    /// If a function fails, e.g. `fromJSON()` with invalid JSON.
+    pub fn evaluate_with(&self, context: &Value, host: &dyn Host) -> Result<Value, EvalError> {
+        eval::Evaluator { context, host }.evaluate(self)
+    }
+}
+
+/// Part of a [`Template`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Literal(String),
+    Expression(Expr),
+}
+
+/// A string with embedded `${{ }}` expressions, such as the value of `with` or `run`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Template(pub Vec<Segment>);
+
+/// Finds the end of the expression starting at `source`, skipping over string literals.
+fn expression_end(source: &str) -> Option<usize> {
+    let mut in_string = false;
+    let mut chars = source.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\'' => in_string = !in_string,
+            '}' if !in_string && chars.next_if(|(_, c)| *c == '}').is_some() => {
+                return Some(index);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+impl Template {
+    /// Parses a string with embedded expressions.
+    ///
+    /// # Errors
+    /// If an expression is invalid or not closed.
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let mut segments = Vec::new();
+        let mut offset = 0;
+        while let Some(start) = source[offset..].find("${{") {
+            let start = offset + start;
+            if start > offset {
+                segments.push(Segment::Literal(source[offset..start].to_string()));
+            }
+            let inner = start + 3;
+            let end = expression_end(&source[inner..])
+                .ok_or(ParseError::UnclosedTemplate { position: start })?;
+            let expr = Expr::parse(&source[inner..inner + end]).map_err(|err| err.offset(inner))?;
+            segments.push(Segment::Expression(expr));
+            offset = inner + end + 2;
+        }
+        if offset < source.len() {
+            segments.push(Segment::Literal(source[offset..].to_string()));
+        }
+        Ok(Self(segments))
+    }
+
+    /// Expressions embedded in the template.
+    pub fn expressions(&self) -> impl Iterator<Item = &Expr> {
+        self.0.iter().filter_map(|segment| match segment {
+            Segment::Expression(expr) => Some(expr),
+            Segment::Literal(_) => None,
+        })
+    }
+
+    /// Evaluates the template in `context`.
+    ///
+    /// A template that only consists of one expression keeps the type of its value,
+    /// otherwise the values are interpolated into a string.
+    ///
+    /// # Errors
+    /// If an expression fails to evaluate.
+    pub fn evaluate_with(&self, context: &Value, host: &dyn Host) -> Result<Value, EvalError> {
+        if let [Segment::Expression(expr)] = self.0.as_slice() {
+            return expr.evaluate_with(context, host);
+        }
+        let mut result = String::new();
+        for segment in &self.0 {
+            match segment {
+                Segment::Literal(literal) => result.push_str(literal),
+                Segment::Expression(expr) => {
+                    result.push_str(&expr.evaluate_with(context, host)?.to_string());
+                }
+            }
+        }
+        Ok(Value::String(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BinaryOp, Expr, ParseError, Segment, Template, Value};
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    #[test]
+    fn test_parse_condition() -> color_eyre::eyre::Result<()> {
+        sim_assert_eq!(
+            Expr::parse_condition("${{ github.ref == 'main' }}")?,
+            Expr::parse("success() && github.ref == 'main'")?
+        );
+        sim_assert_eq!(
+            Expr::parse_condition("always() && steps.a.outcome")?,
+            Expr::parse("always() && steps.a.outcome")?
+        );
+        sim_assert_eq!(
+            Expr::parse_condition("  ${{ a b }}"),
+            Err(ParseError::UnexpectedToken {
+                found: "b".into(),
+                position: 8
+            })
+        );
+        let condition = Expr::parse_condition("github.event_name == 'push'")?;
+        let Expr::Binary(BinaryOp::And, _, _) = condition else {
+            panic!("expected success() && condition, got {condition:?}");
+        };
+        Ok(())
+    }
+
+    #[test]
+    fn test_contexts() -> color_eyre::eyre::Result<()> {
+        let expr = Expr::parse("format('{0}-{1}', github.sha, matrix.os) || GITHUB.ref")?;
+        sim_assert_eq!(expr.contexts(), ["github", "matrix"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_template() -> color_eyre::eyre::Result<()> {
+        let template = Template::parse("v${{ inputs.version }}-${{ '}}' }}")?;
+        sim_assert_eq!(
+            template.0,
+            [
+                Segment::Literal("v".into()),
+                Segment::Expression(Expr::parse("inputs.version")?),
+                Segment::Literal("-".into()),
+                Segment::Expression(Expr::String("}}".into())),
+            ]
+        );
+        let context: Value = [("inputs", [("version", 1.0)].into_iter().collect::<Value>())]
+            .into_iter()
+            .collect();
+        sim_assert_eq!(
+            template.evaluate_with(&context, &())?,
+            Value::String("v1-}}".into())
+        );
+        sim_assert_eq!(
+            Template::parse("${{ inputs.version }}")?.evaluate_with(&context, &())?,
+            Value::Number(1.0)
+        );
+        sim_assert_eq!(
+            Template::parse("a ${{ b"),
+            Err(ParseError::UnclosedTemplate { position: 2 })
+        );
+        sim_assert_eq!(
+            Template::parse("a ${{ b c }}"),
+            Err(ParseError::UnexpectedToken {
+                found: "c".into(),
+                position: 8
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_value_conversions() {
+        sim_assert_eq!(Value::from("").to_number(), 0.0);
+        sim_assert_eq!(Value::from(" 0x10 ").to_number(), 16.0);
+        assert!(Value::from("abc").to_number().is_nan());
+        sim_assert_eq!(Value::Number(3.0).to_string(), "3");
+        sim_assert_eq!(Value::Number(-0.5).to_string(), "-0.5");
+        sim_assert_eq!(Value::Null.to_string(), "");
+        sim_assert_eq!(Value::Array(vec![]).to_string(), "Array");
+        assert!(!Value::Number(f64::NAN).is_truthy());
+        assert!(Value::Object(Default::default()).is_truthy());
+    }
+}
@@ -0,0 +1,530 @@
+use super::parser::FUNCTIONS;
+use super::{BinaryOp, Expr, Value};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+/// Status of the current job, as checked by the status functions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Status {
+    #[default]
+    Success,
+    Failure,
+    Cancelled,
+}
+
+/// Provides the parts of an evaluation that depend on the runner rather than on the context.
+pub trait Host {
+    /// Status of the job, for `success()`, `failure()` and `cancelled()`.
+    fn status(&self) -> Status {
+        Status::Success
+    }
+
+    /// Hash of the files matching `patterns`, for `hashFiles()`.
+    ///
+    /// # Errors
+    /// If the files cannot be hashed.
+    fn hash_files(&self, patterns: &[String]) -> Result<String, EvalError> {
+        let _ = patterns;
+        Err(EvalError::Unsupported("hashFiles"))
+    }
+}
+
+/// A successful job without access to files.
+impl Host for () {}
+
+#[derive(thiserror::Error, Debug)]
+pub enum EvalError {
+    #[error("invalid format string {format:?}: {reason}")]
+    Format {
+        format: String,
+        reason: &'static str,
+    },
+    #[error("fromJSON() failed to parse its argument")]
+    FromJson(#[source] serde_json::Error),
+    #[error("toJSON() failed to serialize its argument")]
+    ToJson(#[source] serde_json::Error),
+    #[error("{0}() is not supported")]
+    Unsupported(&'static str),
+    #[error("hashFiles() failed: {0}")]
+    HashFiles(String),
+    #[error("unknown function {name:?}")]
+    UnknownFunction { name: String },
+    #[error(
+        "{name}() takes {} arguments but {found} were given",
+        super::arity(*.min, *.max)
+    )]
+    Arity {
+        name: String,
+        min: usize,
+        max: usize,
+        found: usize,
+    },
+}
+
+/// Result of evaluating an expression.
+///
+/// Object filters (`.*`) produce a filtered array, which subsequent
+/// property dereferences apply to element-wise.
+enum Eval<'a> {
+    Value(Cow<'a, Value>),
+    Filtered(Vec<Cow<'a, Value>>),
+}
+
+impl<'a> Eval<'a> {
+    fn into_value(self) -> Cow<'a, Value> {
+        match self {
+            Self::Value(value) => value,
+            Self::Filtered(values) => Cow::Owned(Value::Array(
+                values.into_iter().map(Cow::into_owned).collect(),
+            )),
+        }
+    }
+}
+
+fn lookup<'v>(value: &'v Value, key: &Value) -> Option<&'v Value> {
+    match value {
+        Value::Array(values) => {
+            let index = key.to_number();
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            (index.is_finite() && index >= 0.0)
+                .then(|| values.get(index.trunc() as usize))
+                .flatten()
+        }
+        Value::Object(_) => value.get(&key.to_string()),
+        _ => None,
+    }
+}
+
+fn child<'a>(value: Cow<'a, Value>, key: &Value) -> Option<Cow<'a, Value>> {
+    match value {
+        Cow::Borrowed(value) => lookup(value, key).map(Cow::Borrowed),
+        Cow::Owned(value) => lookup(&value, key).cloned().map(Cow::Owned),
+    }
+}
+
+fn children(value: Cow<'_, Value>) -> Vec<Cow<'_, Value>> {
+    match value {
+        Cow::Borrowed(Value::Array(values)) => values.iter().map(Cow::Borrowed).collect(),
+        Cow::Borrowed(Value::Object(values)) => values.values().map(Cow::Borrowed).collect(),
+        Cow::Owned(Value::Array(values)) => values.into_iter().map(Cow::Owned).collect(),
+        Cow::Owned(Value::Object(values)) => values.into_values().map(Cow::Owned).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Loose equality: strings ignore case and values of different types are compared as numbers.
+///
+/// Arrays and objects are only equal to the same instance of the context.
+pub fn loose_eq(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Null, Value::Null) => true,
+        (Value::Bool(left), Value::Bool(right)) => left == right,
+        (Value::String(left), Value::String(right)) => left.to_uppercase() == right.to_uppercase(),
+        (Value::Array(_) | Value::Object(_), _) | (_, Value::Array(_) | Value::Object(_)) => {
+            std::ptr::eq(left, right)
+        }
+        _ => left.to_number() == right.to_number(),
+    }
+}
+
+fn loose_cmp(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::String(left), Value::String(right)) => {
+            Some(left.to_uppercase().cmp(&right.to_uppercase()))
+        }
+        (Value::Array(_) | Value::Object(_), _) | (_, Value::Array(_) | Value::Object(_)) => None,
+        _ => left.to_number().partial_cmp(&right.to_number()),
+    }
+}
+
+/// Implements `format()`: `{N}` is replaced by argument `N`, `{{` and `}}` are escaped braces.
+fn format(format: &str, args: &[Value]) -> Result<String, EvalError> {
+    let error = |reason| EvalError::Format {
+        format: format.to_string(),
+        reason,
+    };
+    let mut result = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.next_if_eq(&'{').is_some() => result.push('{'),
+            '{' => {
+                let mut index = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) if c.is_ascii_digit() => index.push(c),
+                        _ => return Err(error("expected `{N}`")),
+                    }
+                }
+                let arg = index
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| args.get(index))
+                    .ok_or_else(|| error("argument index out of range"))?;
+                result.push_str(&arg.to_string());
+            }
+            '}' if chars.next_if_eq(&'}').is_some() => result.push('}'),
+            '}' => return Err(error("unescaped `}`")),
+            c => result.push(c),
+        }
+    }
+    Ok(result)
+}
+
+pub struct Evaluator<'a> {
+    pub context: &'a Value,
+    pub host: &'a dyn Host,
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn evaluate(&self, expr: &Expr) -> Result<Value, EvalError> {
+        Ok(self.eval(expr)?.into_value().into_owned())
+    }
+
+    fn value(&self, expr: &Expr) -> Result<Cow<'a, Value>, EvalError> {
+        Ok(self.eval(expr)?.into_value())
+    }
+
+    fn eval(&self, expr: &Expr) -> Result<Eval<'a>, EvalError> {
+        let owned = |value: Value| Ok(Eval::Value(Cow::Owned(value)));
+        match expr {
+            Expr::Null => owned(Value::Null),
+            Expr::Bool(value) => owned(Value::Bool(*value)),
+            Expr::Number(value) => owned(Value::Number(*value)),
+            Expr::String(value) => owned(Value::String(value.clone())),
+            Expr::Context(name) => Ok(Eval::Value(
+                self.context
+                    .get(name)
+                    .map_or(Cow::Owned(Value::Null), Cow::Borrowed),
+            )),
+            Expr::Property(expr, name) => self.lookup(expr, &Value::String(name.clone())),
+            Expr::Index(expr, index) => {
+                let index = self.value(index)?;
+                self.lookup(expr, &index)
+            }
+            Expr::Wildcard(expr) => Ok(Eval::Filtered(match self.eval(expr)? {
+                Eval::Value(value) => children(value),
+                Eval::Filtered(values) => values.into_iter().flat_map(children).collect(),
+            })),
+            Expr::Not(expr) => owned(Value::Bool(!self.value(expr)?.is_truthy())),
+            Expr::Binary(op, left, right) => {
+                let left = self.eval(left)?;
+                match op {
+                    BinaryOp::And | BinaryOp::Or => {
+                        let left = left.into_value();
+                        if left.is_truthy() == (*op == BinaryOp::And) {
+                            self.eval(right)
+                        } else {
+                            Ok(Eval::Value(left))
+                        }
+                    }
+                    op => {
+                        let left = left.into_value();
+                        let right = self.value(right)?;
+                        let ordering = loose_cmp(&left, &right);
+                        owned(Value::Bool(match op {
+                            BinaryOp::Eq => loose_eq(&left, &right),
+                            BinaryOp::Ne => !loose_eq(&left, &right),
+                            BinaryOp::Lt => ordering == Some(Ordering::Less),
+                            BinaryOp::Le => {
+                                matches!(ordering, Some(Ordering::Less | Ordering::Equal))
+                            }
+                            BinaryOp::Gt => ordering == Some(Ordering::Greater),
+                            BinaryOp::Ge => {
+                                matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+                            }
+                            BinaryOp::And | BinaryOp::Or => unreachable!(),
+                        }))
+                    }
+                }
+            }
+            Expr::Call { name, args } => owned(self.call(name, args)?),
+        }
+    }
+
+    fn lookup(&self, expr: &Expr, key: &Value) -> Result<Eval<'a>, EvalError> {
+        Ok(match self.eval(expr)? {
+            Eval::Value(value) => Eval::Value(child(value, key).unwrap_or(Cow::Owned(Value::Null))),
+            Eval::Filtered(values) => Eval::Filtered(
+                values
+                    .into_iter()
+                    .filter_map(|value| child(value, key))
+                    .collect(),
+            ),
+        })
+    }
+
+    fn call(&self, name: &str, args: &[Expr]) -> Result<Value, EvalError> {
+        // calls are checked by the parser, but expressions can also be built by hand
+        let Some(&(function, min, max)) = FUNCTIONS
+            .iter()
+            .find(|(function, ..)| function.eq_ignore_ascii_case(name))
+        else {
+            return Err(EvalError::UnknownFunction {
+                name: name.to_string(),
+            });
+        };
+        if args.len() < min || args.len() > max {
+            return Err(EvalError::Arity {
+                name: function.to_string(),
+                min,
+                max,
+                found: args.len(),
+            });
+        }
+        let args = args
+            .iter()
+            .map(|arg| self.value(arg))
+            .collect::<Result<Vec<_>, _>>()?;
+        let arg = |index: usize| args.get(index).map_or(&Value::Null, AsRef::as_ref);
+        let string = |index: usize| args.get(index).map(ToString::to_string).unwrap_or_default();
+        let status = || self.host.status();
+        Ok(match function {
+            "contains" => Value::Bool(match arg(0) {
+                Value::Array(values) => values.iter().any(|value| loose_eq(value, arg(1))),
+                _ => string(0).to_uppercase().contains(&string(1).to_uppercase()),
+            }),
+            "startsWith" => Value::Bool(
+                string(0)
+                    .to_uppercase()
+                    .starts_with(&string(1).to_uppercase()),
+            ),
+            "endsWith" => Value::Bool(
+                string(0)
+                    .to_uppercase()
+                    .ends_with(&string(1).to_uppercase()),
+            ),
+            "format" => {
+                let values: Vec<Value> = args
+                    .iter()
+                    .skip(1)
+                    .map(|arg| arg.as_ref().clone())
+                    .collect();
+                Value::String(format(&string(0), &values)?)
+            }
+            "join" => {
+                let separator = if args.len() > 1 {
+                    string(1)
+                } else {
+                    ",".to_string()
+                };
+                Value::String(match arg(0) {
+                    Value::Array(values) => values
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(&separator),
+                    value => value.to_string(),
+                })
+            }
+            "toJSON" => {
+                Value::String(serde_json::to_string_pretty(arg(0)).map_err(EvalError::ToJson)?)
+            }
+            "fromJSON" => serde_json::from_str(&string(0)).map_err(EvalError::FromJson)?,
+            "hashFiles" => {
+                let patterns: Vec<String> = (0..args.len()).map(string).collect();
+                Value::String(self.host.hash_files(&patterns)?)
+            }
+            "success" => Value::Bool(status() == Status::Success),
+            "failure" => Value::Bool(status() == Status::Failure),
+            "cancelled" => Value::Bool(status() == Status::Cancelled),
+            "always" => Value::Bool(true),
+            other => {
+                return Err(EvalError::UnknownFunction {
+                    name: other.to_string(),
+                });
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EvalError, Host, Status};
+    use crate::expression::{Expr, Value};
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    fn context() -> Value {
+        serde_json::json!({
+            "github": {
+                "event_name": "pull_request",
+                "ref": "refs/heads/main",
+                "event": {
+                    "issue": {
+                        "labels": [
+                            { "name": "bug", "color": "red" },
+                            { "name": "help wanted" },
+                            { "color": "blue" }
+                        ]
+                    }
+                }
+            },
+            "steps": {
+                "build": { "outputs": { "version": "1.2.0", "count": "3" } }
+            },
+            "matrix": { "os": "ubuntu-latest", "version": 16 },
+            "env": { "EMPTY": "" }
+        })
+        .into()
+    }
+
+    fn eval(source: &str) -> Result<Value, EvalError> {
+        Expr::parse(source).unwrap().evaluate(&context())
+    }
+
+    #[test]
+    fn test_literals_and_operators() -> color_eyre::eyre::Result<()> {
+        sim_assert_eq!(eval("1 == '1'")?, Value::Bool(true));
+        sim_assert_eq!(eval("'ABC' == 'abc'")?, Value::Bool(true));
+        sim_assert_eq!(eval("null == 0")?, Value::Bool(true));
+        sim_assert_eq!(eval("true == 1")?, Value::Bool(true));
+        sim_assert_eq!(eval("'' == 0")?, Value::Bool(true));
+        sim_assert_eq!(eval("NaN == NaN")?, Value::Bool(false));
+        sim_assert_eq!(eval("'a' < 'B'")?, Value::Bool(true));
+        sim_assert_eq!(eval("steps.build.outputs.count > 2")?, Value::Bool(true));
+        sim_assert_eq!(eval("github.event == github.event")?, Value::Bool(true));
+        sim_assert_eq!(
+            eval("fromJSON('{}') == fromJSON('{}')")?,
+            Value::Bool(false)
+        );
+        sim_assert_eq!(eval("!env.EMPTY")?, Value::Bool(true));
+        sim_assert_eq!(eval("env.EMPTY || 'default'")?, Value::from("default"));
+        sim_assert_eq!(eval("matrix.os && matrix.version")?, Value::Number(16.0));
+        sim_assert_eq!(eval("github.missing.deep")?, Value::Null);
+        sim_assert_eq!(eval("GITHUB.EVENT_NAME")?, Value::from("pull_request"));
+        sim_assert_eq!(eval("github['ref']")?, Value::from("refs/heads/main"));
+        sim_assert_eq!(
+            eval("github.event.issue.labels[1].name")?,
+            Value::from("help wanted")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_object_filter() -> color_eyre::eyre::Result<()> {
+        sim_assert_eq!(
+            eval("github.event.issue.labels.*.name")?,
+            Value::Array(vec!["bug".into(), "help wanted".into()])
+        );
+        sim_assert_eq!(
+            eval("contains(github.event.issue.labels.*.name, 'BUG')")?,
+            Value::Bool(true)
+        );
+        sim_assert_eq!(eval("join(steps.*.outputs.version)")?, Value::from("1.2.0"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_functions() -> color_eyre::eyre::Result<()> {
+        sim_assert_eq!(eval("contains('Hello world', 'WORLD')")?, Value::Bool(true));
+        sim_assert_eq!(
+            eval("startsWith(github.ref, 'refs/HEADS/')")?,
+            Value::Bool(true)
+        );
+        sim_assert_eq!(eval("endsWith('Hello world', 'x')")?, Value::Bool(false));
+        sim_assert_eq!(
+            eval("format('{{Hello {0} {1} {0}!}}', 'Mona', matrix.version)")?,
+            Value::from("{Hello Mona 16 Mona!}")
+        );
+        sim_assert_eq!(
+            eval("join(fromJSON('[\"a\", 1, true, null]'), ', ')")?,
+            Value::from("a, 1, true, ")
+        );
+        sim_assert_eq!(eval("join('abc')")?, Value::from("abc"));
+        sim_assert_eq!(
+            eval("toJSON(matrix)")?,
+            Value::from("{\n  \"os\": \"ubuntu-latest\",\n  \"version\": 16\n}")
+        );
+        sim_assert_eq!(
+            eval("fromJSON('{\"include\": [{\"os\": \"linux\"}]}').include[0].os")?,
+            Value::from("linux")
+        );
+        sim_assert_eq!(
+            eval("success() && !failure() && always()")?,
+            Value::Bool(true)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_errors() {
+        assert!(matches!(
+            eval("format('{0')"),
+            Err(EvalError::Format { .. })
+        ));
+        assert!(matches!(
+            eval("format('{1}', 'a')"),
+            Err(EvalError::Format { .. })
+        ));
+        assert!(matches!(eval("fromJSON('{')"), Err(EvalError::FromJson(_))));
+        assert!(matches!(
+            eval("hashFiles('**/Cargo.lock')"),
+            Err(EvalError::Unsupported("hashFiles"))
+        ));
+
+        let call = |name: &str, args: Vec<Expr>| {
+            Expr::Call {
+                name: name.to_string(),
+                args,
+            }
+            .evaluate(&context())
+        };
+        assert!(matches!(
+            call("contains", vec![]),
+            Err(EvalError::Arity {
+                min: 2,
+                max: 2,
+                found: 0,
+                ..
+            })
+        ));
+        assert!(matches!(
+            call("toJSON", vec![Expr::Null, Expr::Null]),
+            Err(EvalError::Arity { found: 2, .. })
+        ));
+        assert!(matches!(
+            call("unknown", vec![]),
+            Err(EvalError::UnknownFunction { ref name }) if name == "unknown"
+        ));
+        sim_assert_eq!(
+            call("TOJSON", vec![Expr::Bool(true)]).unwrap(),
+            Value::String("true".to_string())
+        );
+    }
+
+    #[test]
+    fn test_host() -> color_eyre::eyre::Result<()> {
+        struct Failed;
+
+        impl Host for Failed {
+            fn status(&self) -> Status {
+                Status::Failure
+            }
+
+            fn hash_files(&self, patterns: &[String]) -> Result<String, EvalError> {
+                Ok(patterns.join("+"))
+            }
+        }
+
+        let evaluate = |source: &str| {
+            Expr::parse_condition(source)?
+                .evaluate_with(&context(), &Failed)
+                .map_err(color_eyre::eyre::Report::from)
+        };
+        sim_assert_eq!(
+            evaluate("github.event_name == 'pull_request'")?,
+            Value::Bool(false)
+        );
+        sim_assert_eq!(
+            evaluate("failure() && github.event_name == 'pull_request'")?,
+            Value::Bool(true)
+        );
+        sim_assert_eq!(evaluate("cancelled()")?, Value::Bool(false));
+        sim_assert_eq!(
+            Expr::parse("hashFiles('a', 'b')")?.evaluate_with(&context(), &Failed)?,
+            Value::from("a+b")
+        );
+        Ok(())
+    }
+}
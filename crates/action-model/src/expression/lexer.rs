@@ -0,0 +1,236 @@
+use super::ParseError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Ident(String),
+    Dot,
+    Star,
+    Comma,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Not,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+impl std::fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Bool(value) => write!(f, "{value}"),
+            Self::Number(value) => write!(f, "{value}"),
+            Self::String(value) => write!(f, "'{}'", value.replace('\'', "''")),
+            Self::Ident(name) => write!(f, "{name}"),
+            Self::Dot => write!(f, "."),
+            Self::Star => write!(f, "*"),
+            Self::Comma => write!(f, ","),
+            Self::LParen => write!(f, "("),
+            Self::RParen => write!(f, ")"),
+            Self::LBracket => write!(f, "["),
+            Self::RBracket => write!(f, "]"),
+            Self::Not => write!(f, "!"),
+            Self::Lt => write!(f, "<"),
+            Self::Le => write!(f, "<="),
+            Self::Gt => write!(f, ">"),
+            Self::Ge => write!(f, ">="),
+            Self::Eq => write!(f, "=="),
+            Self::Ne => write!(f, "!="),
+            Self::And => write!(f, "&&"),
+            Self::Or => write!(f, "||"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// Byte offset of the token in the expression.
+    pub position: usize,
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Parses a number literal the way the expression language does:
+/// decimal, exponent and `0x` hexadecimal notations, with an optional sign.
+pub fn parse_number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let value = if let Some(hex) = unsigned
+        .strip_prefix("0x")
+        .or_else(|| unsigned.strip_prefix("0X"))
+    {
+        u32::from_str_radix(hex, 16).map(f64::from).ok()?
+    } else if unsigned == "Infinity" {
+        f64::INFINITY
+    } else if unsigned.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        unsigned.parse().ok()?
+    } else {
+        return None;
+    };
+    Some(if negative { -value } else { value })
+}
+
+/// Splits an expression into tokens.
+pub fn tokenize(source: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        let mut next_is = |expected: char| chars.next_if(|(_, c)| *c == expected).is_some();
+        let kind = match c {
+            c if c.is_whitespace() => continue,
+            '.' => TokenKind::Dot,
+            '*' => TokenKind::Star,
+            ',' => TokenKind::Comma,
+            '(' => TokenKind::LParen,
+            ')' => TokenKind::RParen,
+            '[' => TokenKind::LBracket,
+            ']' => TokenKind::RBracket,
+            '!' if next_is('=') => TokenKind::Ne,
+            '!' => TokenKind::Not,
+            '<' if next_is('=') => TokenKind::Le,
+            '<' => TokenKind::Lt,
+            '>' if next_is('=') => TokenKind::Ge,
+            '>' => TokenKind::Gt,
+            '=' if next_is('=') => TokenKind::Eq,
+            '&' if next_is('&') => TokenKind::And,
+            '|' if next_is('|') => TokenKind::Or,
+            '\'' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '\'')) if chars.next_if(|(_, c)| *c == '\'').is_some() => {
+                            value.push('\'');
+                        }
+                        Some((_, '\'')) => break,
+                        Some((_, c)) => value.push(c),
+                        None => return Err(ParseError::UnterminatedString { position }),
+                    }
+                }
+                TokenKind::String(value)
+            }
+            c if c.is_ascii_digit() || c == '-' || c == '+' => {
+                let mut end = position + c.len_utf8();
+                while let Some((index, c)) = chars.next_if(|(index, c)| {
+                    c.is_ascii_alphanumeric()
+                        || *c == '.'
+                        || ((*c == '-' || *c == '+')
+                            && matches!(source.as_bytes()[index - 1], b'e' | b'E'))
+                }) {
+                    end = index + c.len_utf8();
+                }
+                let text = &source[position..end];
+                let value = parse_number(text).ok_or_else(|| ParseError::InvalidNumber {
+                    text: text.to_string(),
+                    position,
+                })?;
+                TokenKind::Number(value)
+            }
+            c if is_ident_start(c) => {
+                let mut end = position + c.len_utf8();
+                while let Some((index, c)) = chars.next_if(|(_, c)| is_ident_char(*c)) {
+                    end = index + c.len_utf8();
+                }
+                match &source[position..end] {
+                    "null" => TokenKind::Null,
+                    "true" => TokenKind::Bool(true),
+                    "false" => TokenKind::Bool(false),
+                    "NaN" => TokenKind::Number(f64::NAN),
+                    "Infinity" => TokenKind::Number(f64::INFINITY),
+                    ident => TokenKind::Ident(ident.to_string()),
+                }
+            }
+            c => return Err(ParseError::UnexpectedChar { ch: c, position }),
+        };
+        tokens.push(Token { kind, position });
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TokenKind, tokenize};
+    use crate::expression::ParseError;
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    fn kinds(source: &str) -> Result<Vec<TokenKind>, ParseError> {
+        Ok(tokenize(source)?.into_iter().map(|t| t.kind).collect())
+    }
+
+    #[test]
+    fn test_tokenize() -> color_eyre::eyre::Result<()> {
+        sim_assert_eq!(
+            kinds("steps.my-step.outputs['it''s'] != 0x1F && !-2.5e-1")?,
+            [
+                TokenKind::Ident("steps".into()),
+                TokenKind::Dot,
+                TokenKind::Ident("my-step".into()),
+                TokenKind::Dot,
+                TokenKind::Ident("outputs".into()),
+                TokenKind::LBracket,
+                TokenKind::String("it's".into()),
+                TokenKind::RBracket,
+                TokenKind::Ne,
+                TokenKind::Number(31.0),
+                TokenKind::And,
+                TokenKind::Not,
+                TokenKind::Number(-0.25),
+            ]
+        );
+        sim_assert_eq!(
+            kinds("a.*.b || null")?,
+            [
+                TokenKind::Ident("a".into()),
+                TokenKind::Dot,
+                TokenKind::Star,
+                TokenKind::Dot,
+                TokenKind::Ident("b".into()),
+                TokenKind::Or,
+                TokenKind::Null,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_tokenize_errors() {
+        sim_assert_eq!(
+            kinds("'open"),
+            Err(ParseError::UnterminatedString { position: 0 })
+        );
+        sim_assert_eq!(
+            kinds("a = b"),
+            Err(ParseError::UnexpectedChar {
+                ch: '=',
+                position: 2
+            })
+        );
+        sim_assert_eq!(
+            kinds("1.2.3"),
+            Err(ParseError::InvalidNumber {
+                text: "1.2.3".into(),
+                position: 0
+            })
+        );
+    }
+}
@@ -0,0 +1,358 @@
+use super::lexer::{Token, TokenKind, tokenize};
+use super::{BinaryOp, Expr, ParseError};
+
+/// Functions of the expression language, with their minimum and maximum number of arguments.
+pub const FUNCTIONS: &[(&str, usize, usize)] = &[
+    ("contains", 2, 2),
+    ("startsWith", 2, 2),
+    ("endsWith", 2, 2),
+    ("format", 1, usize::MAX),
+    ("join", 1, 2),
+    ("toJSON", 1, 1),
+    ("fromJSON", 1, 1),
+    ("hashFiles", 1, usize::MAX),
+    ("success", 0, 0),
+    ("always", 0, 0),
+    ("cancelled", 0, 0),
+    ("failure", 0, 0),
+];
+
+/// Functions that check the status of the job.
+pub const STATUS_FUNCTIONS: &[&str] = &["success", "always", "cancelled", "failure"];
+
+/// Maximum nesting depth of an expression, to bound the recursion of the parser.
+const MAX_DEPTH: usize = 50;
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+    depth: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.index).map(|token| &token.kind)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map_or(self.end, |token| token.position)
+    }
+
+    fn next(&mut self) -> Result<Token, ParseError> {
+        let token = self
+            .tokens
+            .get(self.index)
+            .cloned()
+            .ok_or(ParseError::UnexpectedEnd)?;
+        self.index += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.peek() == Some(kind) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: &TokenKind) -> Result<(), ParseError> {
+        let token = self.next()?;
+        if token.kind == *kind {
+            Ok(())
+        } else {
+            Err(ParseError::UnexpectedToken {
+                found: token.kind.to_string(),
+                position: token.position,
+            })
+        }
+    }
+
+    fn binary(
+        &mut self,
+        operators: &[(TokenKind, BinaryOp)],
+        operand: fn(&mut Self) -> Result<Expr, ParseError>,
+    ) -> Result<Expr, ParseError> {
+        let mut left = operand(self)?;
+        'outer: loop {
+            for (token, op) in operators {
+                if self.eat(token) {
+                    let right = operand(self)?;
+                    left = Expr::Binary(*op, Box::new(left), Box::new(right));
+                    continue 'outer;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        self.binary(&[(TokenKind::Or, BinaryOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        self.binary(&[(TokenKind::And, BinaryOp::And)], Self::equality)
+    }
+
+    fn equality(&mut self) -> Result<Expr, ParseError> {
+        self.binary(
+            &[(TokenKind::Eq, BinaryOp::Eq), (TokenKind::Ne, BinaryOp::Ne)],
+            Self::comparison,
+        )
+    }
+
+    fn comparison(&mut self) -> Result<Expr, ParseError> {
+        self.binary(
+            &[
+                (TokenKind::Le, BinaryOp::Le),
+                (TokenKind::Lt, BinaryOp::Lt),
+                (TokenKind::Ge, BinaryOp::Ge),
+                (TokenKind::Gt, BinaryOp::Gt),
+            ],
+            Self::unary,
+        )
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.eat(&TokenKind::Not) {
+            let position = self.position();
+            self.enter(position)?;
+            let operand = self.unary()?;
+            self.depth -= 1;
+            return Ok(Expr::Not(Box::new(operand)));
+        }
+        self.postfix()
+    }
+
+    fn enter(&mut self, position: usize) -> Result<(), ParseError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(ParseError::TooDeep { position });
+        }
+        Ok(())
+    }
+
+    fn postfix(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.eat(&TokenKind::Dot) {
+                let token = self.next()?;
+                expr = match token.kind {
+                    TokenKind::Star => Expr::Wildcard(Box::new(expr)),
+                    TokenKind::Ident(name) => Expr::Property(Box::new(expr), name),
+                    // keywords are valid property names, e.g. `inputs.true`
+                    TokenKind::Null | TokenKind::Bool(_) => {
+                        Expr::Property(Box::new(expr), token.kind.to_string())
+                    }
+                    kind => {
+                        return Err(ParseError::UnexpectedToken {
+                            found: kind.to_string(),
+                            position: token.position,
+                        });
+                    }
+                };
+            } else if self.eat(&TokenKind::LBracket) {
+                if self.eat(&TokenKind::Star) {
+                    expr = Expr::Wildcard(Box::new(expr));
+                } else {
+                    let position = self.position();
+                    self.enter(position)?;
+                    let index = self.or()?;
+                    self.depth -= 1;
+                    expr = Expr::Index(Box::new(expr), Box::new(index));
+                }
+                self.expect(&TokenKind::RBracket)?;
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Null => Ok(Expr::Null),
+            TokenKind::Bool(value) => Ok(Expr::Bool(value)),
+            TokenKind::Number(value) => Ok(Expr::Number(value)),
+            TokenKind::String(value) => Ok(Expr::String(value)),
+            TokenKind::LParen => {
+                self.enter(token.position)?;
+                let expr = self.or()?;
+                self.depth -= 1;
+                self.expect(&TokenKind::RParen)?;
+                Ok(expr)
+            }
+            TokenKind::Ident(name) if self.eat(&TokenKind::LParen) => {
+                self.call(name, token.position)
+            }
+            TokenKind::Ident(name) => Ok(Expr::Context(name)),
+            kind => Err(ParseError::UnexpectedToken {
+                found: kind.to_string(),
+                position: token.position,
+            }),
+        }
+    }
+
+    fn call(&mut self, name: String, position: usize) -> Result<Expr, ParseError> {
+        let Some(&(function, min, max)) = FUNCTIONS
+            .iter()
+            .find(|(function, ..)| function.eq_ignore_ascii_case(&name))
+        else {
+            return Err(ParseError::UnknownFunction { name, position });
+        };
+        self.enter(position)?;
+        let mut args = Vec::new();
+        if !self.eat(&TokenKind::RParen) {
+            loop {
+                args.push(self.or()?);
+                if self.eat(&TokenKind::RParen) {
+                    break;
+                }
+                self.expect(&TokenKind::Comma)?;
+            }
+        }
+        self.depth -= 1;
+        if args.len() < min || args.len() > max {
+            return Err(ParseError::Arity {
+                name: function.to_string(),
+                min,
+                max,
+                found: args.len(),
+                position,
+            });
+        }
+        Ok(Expr::Call {
+            name: function.to_string(),
+            args,
+        })
+    }
+}
+
+/// Parses an expression, without the surrounding `${{ }}`.
+pub fn parse(source: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        index: 0,
+        depth: 0,
+        end: source.len(),
+    };
+    if parser.tokens.is_empty() {
+        return Err(ParseError::UnexpectedEnd);
+    }
+    let expr = parser.or()?;
+    match parser.tokens.get(parser.index) {
+        None => Ok(expr),
+        Some(token) => Err(ParseError::UnexpectedToken {
+            found: token.kind.to_string(),
+            position: token.position,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use crate::expression::{BinaryOp, Expr, ParseError};
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    fn context(path: &[&str]) -> Expr {
+        let mut expr = Expr::Context(path[0].to_string());
+        for name in &path[1..] {
+            expr = Expr::Property(Box::new(expr), (*name).to_string());
+        }
+        expr
+    }
+
+    #[test]
+    fn test_parse_precedence() -> color_eyre::eyre::Result<()> {
+        sim_assert_eq!(
+            parse("!a || b.c == 'x' && d[0] < 2")?,
+            Expr::Binary(
+                BinaryOp::Or,
+                Box::new(Expr::Not(Box::new(context(&["a"])))),
+                Box::new(Expr::Binary(
+                    BinaryOp::And,
+                    Box::new(Expr::Binary(
+                        BinaryOp::Eq,
+                        Box::new(context(&["b", "c"])),
+                        Box::new(Expr::String("x".into())),
+                    )),
+                    Box::new(Expr::Binary(
+                        BinaryOp::Lt,
+                        Box::new(Expr::Index(
+                            Box::new(context(&["d"])),
+                            Box::new(Expr::Number(0.0))
+                        )),
+                        Box::new(Expr::Number(2.0)),
+                    )),
+                )),
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_calls() -> color_eyre::eyre::Result<()> {
+        sim_assert_eq!(
+            parse("CONTAINS(github.event.issue.labels.*.name, 'bug')")?,
+            Expr::Call {
+                name: "contains".into(),
+                args: vec![
+                    Expr::Property(
+                        Box::new(Expr::Wildcard(Box::new(context(&[
+                            "github", "event", "issue", "labels"
+                        ])))),
+                        "name".into()
+                    ),
+                    Expr::String("bug".into()),
+                ],
+            }
+        );
+        sim_assert_eq!(
+            parse("success()")?,
+            Expr::Call {
+                name: "success".into(),
+                args: vec![]
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        sim_assert_eq!(
+            parse("unknown(1)"),
+            Err(ParseError::UnknownFunction {
+                name: "unknown".into(),
+                position: 0
+            })
+        );
+        sim_assert_eq!(
+            parse("startsWith('a')"),
+            Err(ParseError::Arity {
+                name: "startsWith".into(),
+                min: 2,
+                max: 2,
+                found: 1,
+                position: 0
+            })
+        );
+        sim_assert_eq!(
+            parse("a b"),
+            Err(ParseError::UnexpectedToken {
+                found: "b".into(),
+                position: 2
+            })
+        );
+        sim_assert_eq!(parse("(a"), Err(ParseError::UnexpectedEnd));
+        sim_assert_eq!(parse(""), Err(ParseError::UnexpectedEnd));
+        assert!(matches!(
+            parse(&format!("{}a{}", "(".repeat(100), ")".repeat(100))),
+            Err(ParseError::TooDeep { .. })
+        ));
+    }
+}
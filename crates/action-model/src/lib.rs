@@ -1,15 +1,5 @@
-#[must_use]
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub mod action;
+pub mod expression;
+pub mod matrix;
+mod scalar;
+pub mod workflow;
@@ -0,0 +1,389 @@
+//! Expansion of a job strategy matrix into the jobs it creates.
+//!
+//! See <https://docs.github.com/en/actions/writing-workflows/choosing-what-your-workflow-does/running-variations-of-jobs-in-a-workflow>.
+
+use crate::workflow::{Matrix, MatrixConfig, Strategy};
+use indexmap::IndexMap;
+
+/// Maximum number of jobs a matrix can generate per workflow run.
+pub const MAX_JOBS: usize = 256;
+
+/// Values of the matrix variables for one job.
+pub type Combination = IndexMap<String, serde_yaml::Value>;
+
+/// Jobs created by a strategy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expansion {
+    /// Combinations in the order the jobs are created.
+    pub combinations: Vec<Combination>,
+    /// Whether in-progress and queued jobs are cancelled when one of them fails (`fail-fast`).
+    pub fail_fast: bool,
+    /// Maximum number of jobs running at the same time, `None` if unlimited (`max-parallel`).
+    pub max_parallel: Option<usize>,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq, Clone)]
+pub enum MatrixError {
+    #[error("{0} contains an expression that must be evaluated first")]
+    Unevaluated(String),
+    #[error("matrix variable {0:?} must be a sequence of values")]
+    NotASequence(String),
+    #[error("matrix variable {0:?} does not contain any values")]
+    EmptyVariable(String),
+    #[error("matrix exclude key {0:?} does not match any variable of the matrix")]
+    UnknownExcludeKey(String),
+    #[error("matrix must define at least one variable or include")]
+    Empty,
+    #[error("matrix generates {0} jobs, the maximum is {MAX_JOBS}")]
+    TooManyJobs(usize),
+    #[error("invalid {field} {value:?}")]
+    InvalidStrategy { field: &'static str, value: String },
+}
+
+fn is_expression(value: &str) -> bool {
+    value.contains("${{")
+}
+
+/// Whether `combination` has the same values as `partial` for all keys of `partial` in `keys`.
+fn matches<'a>(
+    combination: &Combination,
+    partial: &'a Combination,
+    mut keys: impl Iterator<Item = &'a String>,
+) -> bool {
+    keys.all(|key| combination.get(key) == partial.get(key))
+}
+
+impl MatrixConfig {
+    /// Expands the matrix into its combinations, in the order GitHub creates the jobs.
+    ///
+    /// 1. The cartesian product of the variables, varying the last variable fastest.
+    /// 2. Combinations matching an `exclude` entry are removed.
+    /// 3. Each `include` entry is added to all combinations whose original variables
+    ///    it does not overwrite, or appended as a new combination if there are none.
+    ///
+    /// # Errors
+    /// If a variable is not a non-empty sequence, an exclude key is unknown,
+    /// or the matrix generates more than [`MAX_JOBS`] jobs.
+    pub fn expand(&self) -> Result<Vec<Combination>, MatrixError> {
+        let mut combinations: Vec<Combination> = Vec::new();
+        if !self.variables.is_empty() {
+            combinations.push(Combination::new());
+        }
+        for (name, values) in &self.variables {
+            let values = match values {
+                serde_yaml::Value::Sequence(values) => values,
+                serde_yaml::Value::String(value) if is_expression(value) => {
+                    return Err(MatrixError::Unevaluated(format!(
+                        "matrix variable {name:?}"
+                    )));
+                }
+                _ => return Err(MatrixError::NotASequence(name.clone())),
+            };
+            if values.is_empty() {
+                return Err(MatrixError::EmptyVariable(name.clone()));
+            }
+            let count = combinations.len() * values.len();
+            if count > MAX_JOBS {
+                return Err(MatrixError::TooManyJobs(count));
+            }
+            combinations = combinations
+                .into_iter()
+                .flat_map(|combination| {
+                    values.iter().map(move |value| {
+                        let mut combination = combination.clone();
+                        combination.insert(name.clone(), value.clone());
+                        combination
+                    })
+                })
+                .collect();
+        }
+
+        for exclude in &self.exclude {
+            if let Some(key) = exclude
+                .keys()
+                .find(|key| !self.variables.contains_key(*key))
+            {
+                return Err(MatrixError::UnknownExcludeKey(key.clone()));
+            }
+            combinations.retain(|combination| !matches(combination, exclude, exclude.keys()));
+        }
+
+        let original = combinations.len();
+        for include in &self.include {
+            let mut included = false;
+            for combination in &mut combinations[..original] {
+                let original_keys = include
+                    .keys()
+                    .filter(|key| self.variables.contains_key(*key));
+                if matches(combination, include, original_keys) {
+                    for (key, value) in include {
+                        combination.insert(key.clone(), value.clone());
+                    }
+                    included = true;
+                }
+            }
+            if !included {
+                combinations.push(include.clone());
+            }
+        }
+
+        if combinations.is_empty() && self.variables.is_empty() {
+            return Err(MatrixError::Empty);
+        }
+        if combinations.len() > MAX_JOBS {
+            return Err(MatrixError::TooManyJobs(combinations.len()));
+        }
+        Ok(combinations)
+    }
+}
+
+impl Strategy {
+    /// Expands the strategy into the jobs it creates.
+    ///
+    /// A strategy without a matrix creates a single job without variables.
+    ///
+    /// # Errors
+    /// If the matrix is invalid or `fail-fast` or `max-parallel` are not evaluated.
+    pub fn expand(&self) -> Result<Expansion, MatrixError> {
+        let combinations = match &self.matrix {
+            None => vec![Combination::new()],
+            Some(Matrix::Expression(_)) => {
+                return Err(MatrixError::Unevaluated("matrix".to_string()));
+            }
+            Some(Matrix::Config(matrix)) => matrix.expand()?,
+        };
+        let invalid = |field: &'static str, value: &str| {
+            if is_expression(value) {
+                MatrixError::Unevaluated(field.to_string())
+            } else {
+                MatrixError::InvalidStrategy {
+                    field,
+                    value: value.to_string(),
+                }
+            }
+        };
+        let fail_fast = match self.fail_fast.as_deref() {
+            None | Some("true") => true,
+            Some("false") => false,
+            Some(other) => return Err(invalid("fail-fast", other)),
+        };
+        let max_parallel = self
+            .max_parallel
+            .as_deref()
+            .map(|value| {
+                value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|max| *max > 0)
+                    .ok_or_else(|| invalid("max-parallel", value))
+            })
+            .transpose()?;
+        Ok(Expansion {
+            combinations,
+            fail_fast,
+            max_parallel,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Combination, Expansion, MatrixError};
+    use crate::workflow::{MatrixConfig, Strategy};
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    fn expand(yaml: &str) -> Result<Vec<Combination>, MatrixError> {
+        serde_yaml::from_str::<MatrixConfig>(yaml).unwrap().expand()
+    }
+
+    fn combinations(yaml: &str) -> Vec<Combination> {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_single_and_multi_dimension() -> color_eyre::eyre::Result<()> {
+        sim_assert_eq!(
+            expand("version: [10, 12, 14]\nos: [ubuntu-latest, windows-latest]\n")?,
+            combinations(
+                r"
+- {version: 10, os: ubuntu-latest}
+- {version: 10, os: windows-latest}
+- {version: 12, os: ubuntu-latest}
+- {version: 12, os: windows-latest}
+- {version: 14, os: ubuntu-latest}
+- {version: 14, os: windows-latest}
+"
+            )
+        );
+        sim_assert_eq!(
+            expand("version: [10, 12, 14]\n")?,
+            combinations("[{version: 10}, {version: 12}, {version: 14}]")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_include() -> color_eyre::eyre::Result<()> {
+        let yaml = r"
+fruit: [apple, pear]
+animal: [cat, dog]
+include:
+  - color: green
+  - color: pink
+    animal: cat
+  - fruit: apple
+    shape: circle
+  - fruit: banana
+  - fruit: banana
+    animal: cat
+";
+        sim_assert_eq!(
+            expand(yaml)?,
+            combinations(
+                r"
+- {fruit: apple, animal: cat, color: pink, shape: circle}
+- {fruit: apple, animal: dog, color: green, shape: circle}
+- {fruit: pear, animal: cat, color: pink}
+- {fruit: pear, animal: dog, color: green}
+- {fruit: banana}
+- {fruit: banana, animal: cat}
+"
+            )
+        );
+
+        let yaml = r"
+os: [windows-latest, ubuntu-latest]
+node: [14, 16]
+include:
+  - os: windows-latest
+    node: 16
+    npm: 6
+";
+        sim_assert_eq!(
+            expand(yaml)?,
+            combinations(
+                r"
+- {os: windows-latest, node: 14}
+- {os: windows-latest, node: 16, npm: 6}
+- {os: ubuntu-latest, node: 14}
+- {os: ubuntu-latest, node: 16}
+"
+            )
+        );
+
+        let yaml = r"
+include:
+  - site: production
+    datacenter: site-a
+  - site: staging
+    datacenter: site-b
+";
+        sim_assert_eq!(
+            expand(yaml)?,
+            combinations(
+                r"
+- {site: production, datacenter: site-a}
+- {site: staging, datacenter: site-b}
+"
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_exclude() -> color_eyre::eyre::Result<()> {
+        let yaml = r"
+os: [macos-latest, windows-latest]
+version: [12, 14, 16]
+environment: [staging, production]
+exclude:
+  - os: macos-latest
+    version: 12
+    environment: production
+  - os: windows-latest
+    version: 16
+";
+        sim_assert_eq!(
+            expand(yaml)?,
+            combinations(
+                r"
+- {os: macos-latest, version: 12, environment: staging}
+- {os: macos-latest, version: 14, environment: staging}
+- {os: macos-latest, version: 14, environment: production}
+- {os: macos-latest, version: 16, environment: staging}
+- {os: macos-latest, version: 16, environment: production}
+- {os: windows-latest, version: 12, environment: staging}
+- {os: windows-latest, version: 12, environment: production}
+- {os: windows-latest, version: 14, environment: staging}
+- {os: windows-latest, version: 14, environment: production}
+"
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() {
+        sim_assert_eq!(
+            expand("os: [linux]\nexclude: [{arch: x86}]\n"),
+            Err(MatrixError::UnknownExcludeKey("arch".into()))
+        );
+        sim_assert_eq!(
+            expand("os: []\n"),
+            Err(MatrixError::EmptyVariable("os".into()))
+        );
+        sim_assert_eq!(
+            expand("os: linux\n"),
+            Err(MatrixError::NotASequence("os".into()))
+        );
+        sim_assert_eq!(
+            expand("os: ${{ fromJSON(inputs.os) }}\n"),
+            Err(MatrixError::Unevaluated("matrix variable \"os\"".into()))
+        );
+        sim_assert_eq!(expand("{}"), Err(MatrixError::Empty));
+        let values: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let values = values.join(", ");
+        sim_assert_eq!(
+            expand(&format!("a: [{values}]\nb: [{values}]\n")),
+            Err(MatrixError::TooManyJobs(400))
+        );
+    }
+
+    #[test]
+    fn test_strategy() -> color_eyre::eyre::Result<()> {
+        let strategy: Strategy =
+            serde_yaml::from_str("fail-fast: false\nmax-parallel: 2\nmatrix:\n  os: [a, b]\n")?;
+        sim_assert_eq!(
+            strategy.expand()?,
+            Expansion {
+                combinations: combinations("[{os: a}, {os: b}]"),
+                fail_fast: false,
+                max_parallel: Some(2),
+            }
+        );
+        let strategy: Strategy = serde_yaml::from_str("{}")?;
+        sim_assert_eq!(
+            strategy.expand()?,
+            Expansion {
+                combinations: vec![Combination::new()],
+                fail_fast: true,
+                max_parallel: None,
+            }
+        );
+        let strategy: Strategy =
+            serde_yaml::from_str("matrix: ${{ fromJSON(needs.a.outputs.m) }}")?;
+        sim_assert_eq!(
+            strategy.expand(),
+            Err(MatrixError::Unevaluated("matrix".into()))
+        );
+        let strategy: Strategy = serde_yaml::from_str("max-parallel: 0")?;
+        sim_assert_eq!(
+            strategy.expand(),
+            Err(MatrixError::InvalidStrategy {
+                field: "max-parallel",
+                value: "0".into()
+            })
+        );
+        Ok(())
+    }
+}
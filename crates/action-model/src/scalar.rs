@@ -0,0 +1,68 @@
+//! Lenient deserialization of YAML scalars into strings.
+//!
+//! GitHub accepts numbers and booleans wherever a string is expected,
+//! e.g. `default: 0` or `with: { lfs: true }`.
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Scalar {
+    String(String),
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+}
+
+impl From<Scalar> for String {
+    fn from(value: Scalar) -> Self {
+        match value {
+            Scalar::String(value) => value,
+            Scalar::Bool(value) => value.to_string(),
+            Scalar::Int(value) => value.to_string(),
+            Scalar::Float(value) => value.to_string(),
+        }
+    }
+}
+
+pub(crate) fn option<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value: Option<Scalar> = Deserialize::deserialize(deserializer)?;
+    Ok(value.map(Into::into))
+}
+
+pub(crate) fn vec<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let values: Vec<Scalar> = Deserialize::deserialize(deserializer)?;
+    Ok(values.into_iter().map(Into::into).collect())
+}
+
+pub(crate) fn map<'de, D>(deserializer: D) -> Result<IndexMap<String, String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let values: IndexMap<String, Scalar> = Deserialize::deserialize(deserializer)?;
+    Ok(values.into_iter().map(|(k, v)| (k, v.into())).collect())
+}
+
+/// Deserializes a single string or a sequence of strings.
+pub(crate) fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(Scalar),
+        Many(Vec<Scalar>),
+    }
+    Ok(match Deserialize::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value.into()],
+        OneOrMany::Many(values) => values.into_iter().map(Into::into).collect(),
+    })
+}
@@ -0,0 +1,1131 @@
+//! Model of workflow files (`.github/workflows/*.yml`).
+//!
+//! [`Document::parse`] keeps the location of every key in the source,
+//! so that the errors found by [`Workflow::validate`] can point at the offending line.
+
+use crate::scalar;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Position in a YAML document.
+///
+/// Both the line and the column are 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Workflow {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_name: Option<String>,
+    pub on: Triggers,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Permissions>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::map",
+        skip_serializing_if = "IndexMap::is_empty"
+    )]
+    pub env: IndexMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub defaults: Option<Defaults>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<Concurrency>,
+    pub jobs: IndexMap<String, Job>,
+}
+
+/// Events that trigger a workflow (`on`).
+///
+/// The short forms `on: push` and `on: [push, pull_request]` are normalized
+/// into events without configuration.
+#[derive(Default, PartialEq, Eq, Debug, Serialize)]
+#[serde(transparent)]
+pub struct Triggers(pub IndexMap<String, Trigger>);
+
+#[derive(PartialEq, Eq, Debug, Serialize)]
+#[serde(untagged)]
+pub enum Trigger {
+    Event(EventFilter),
+    Schedule(Vec<Schedule>),
+    WorkflowDispatch(WorkflowDispatch),
+    WorkflowCall(WorkflowCall),
+}
+
+impl Trigger {
+    fn from_value(event: &str, value: serde_yaml::Value) -> Result<Self, serde_yaml::Error> {
+        let value = match value {
+            serde_yaml::Value::Null => serde_yaml::Value::Mapping(serde_yaml::Mapping::new()),
+            value => value,
+        };
+        Ok(match event {
+            "schedule" => Self::Schedule(serde_yaml::from_value(value)?),
+            "workflow_dispatch" => Self::WorkflowDispatch(serde_yaml::from_value(value)?),
+            "workflow_call" => Self::WorkflowCall(serde_yaml::from_value(value)?),
+            _ => Self::Event(serde_yaml::from_value(value)?),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Triggers {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            One(String),
+            Many(Vec<String>),
+            Map(IndexMap<String, serde_yaml::Value>),
+        }
+
+        let events: Vec<(String, serde_yaml::Value)> = match Repr::deserialize(deserializer)? {
+            Repr::One(event) => vec![(event, serde_yaml::Value::Null)],
+            Repr::Many(events) => events
+                .into_iter()
+                .map(|event| (event, serde_yaml::Value::Null))
+                .collect(),
+            Repr::Map(events) => events.into_iter().collect(),
+        };
+        events
+            .into_iter()
+            .map(|(event, value)| {
+                let trigger = Trigger::from_value(&event, value)
+                    .map_err(|err| D::Error::custom(format!("on.{event}: {err}")))?;
+                Ok((event, trigger))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Filters of a webhook event such as `push` or `pull_request`.
+#[derive(Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct EventFilter {
+    #[serde(
+        default,
+        deserialize_with = "scalar::one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub types: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branches: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branches_ignore: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags_ignore: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths_ignore: Vec<String>,
+    /// Workflows that trigger a `workflow_run` event.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub workflows: Vec<String>,
+}
+
+#[derive(PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct Schedule {
+    pub cron: String,
+}
+
+#[derive(Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct WorkflowDispatch {
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub inputs: IndexMap<String, WorkflowInput>,
+}
+
+/// Interface of a reusable workflow (`on.workflow_call`).
+#[derive(Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct WorkflowCall {
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub inputs: IndexMap<String, WorkflowInput>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub outputs: IndexMap<String, WorkflowOutput>,
+    #[serde(
+        default,
+        deserialize_with = "nullable_map",
+        skip_serializing_if = "IndexMap::is_empty"
+    )]
+    pub secrets: IndexMap<String, WorkflowSecret>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputType {
+    String,
+    Boolean,
+    Number,
+    Choice,
+    Environment,
+}
+
+/// Input of a `workflow_dispatch` or `workflow_call` trigger.
+#[derive(Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct WorkflowInput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub default: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<InputType>,
+    /// Options of a `choice` input.
+    #[serde(
+        default,
+        deserialize_with = "scalar::vec",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub options: Vec<String>,
+}
+
+#[derive(Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct WorkflowOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub value: String,
+}
+
+#[derive(Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct WorkflowSecret {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+}
+
+/// Deserializes a map whose values may be left empty, e.g. `secrets: { token: }`.
+fn nullable_map<'de, D, T>(deserializer: D) -> Result<IndexMap<String, T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    let values: IndexMap<String, Option<T>> = Deserialize::deserialize(deserializer)?;
+    Ok(values
+        .into_iter()
+        .map(|(key, value)| (key, value.unwrap_or_default()))
+        .collect())
+}
+
+/// Permissions of the `GITHUB_TOKEN`.
+#[derive(PartialEq, Eq, Debug)]
+pub enum Permissions {
+    ReadAll,
+    WriteAll,
+    Scopes(IndexMap<String, Permission>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+    Read,
+    Write,
+    None,
+}
+
+impl Serialize for Permissions {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::ReadAll => serializer.serialize_str("read-all"),
+            Self::WriteAll => serializer.serialize_str("write-all"),
+            Self::Scopes(scopes) => scopes.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Permissions {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            All(String),
+            Scopes(IndexMap<String, Permission>),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::All(all) if all == "read-all" => Ok(Self::ReadAll),
+            Repr::All(all) if all == "write-all" => Ok(Self::WriteAll),
+            Repr::All(other) => Err(D::Error::custom(format!(
+                "invalid permissions {other:?}, expected `read-all`, `write-all` or a map of scopes"
+            ))),
+            Repr::Scopes(scopes) => Ok(Self::Scopes(scopes)),
+        }
+    }
+}
+
+#[derive(Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct Defaults {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run: Option<RunDefaults>,
+}
+
+#[derive(Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RunDefaults {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_directory: Option<String>,
+}
+
+/// Concurrency group of a workflow or job.
+///
+/// The short form `concurrency: group` only sets the group.
+#[derive(Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", from = "ConcurrencyRepr")]
+pub struct Concurrency {
+    pub group: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel_in_progress: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ConcurrencyRepr {
+    Group(String),
+    #[serde(rename_all = "kebab-case")]
+    Config {
+        group: String,
+        #[serde(default, deserialize_with = "scalar::option")]
+        cancel_in_progress: Option<String>,
+    },
+}
+
+impl From<ConcurrencyRepr> for Concurrency {
+    fn from(value: ConcurrencyRepr) -> Self {
+        match value {
+            ConcurrencyRepr::Group(group) => Self {
+                group,
+                cancel_in_progress: None,
+            },
+            ConcurrencyRepr::Config {
+                group,
+                cancel_in_progress,
+            } => Self {
+                group,
+                cancel_in_progress,
+            },
+        }
+    }
+}
+
+/// A job of a workflow, either running steps on a runner or calling a reusable workflow.
+#[derive(Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Job {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub needs: Vec<String>,
+    #[serde(
+        rename = "if",
+        default,
+        deserialize_with = "scalar::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub r#if: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runs_on: Option<RunsOn>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Environment>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Permissions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<Concurrency>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::map",
+        skip_serializing_if = "IndexMap::is_empty"
+    )]
+    pub outputs: IndexMap<String, String>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::map",
+        skip_serializing_if = "IndexMap::is_empty"
+    )]
+    pub env: IndexMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub defaults: Option<Defaults>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timeout_minutes: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<Strategy>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub continue_on_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub services: IndexMap<String, serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<Step>,
+
+    /// Reusable workflow called by this job.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uses: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::map",
+        skip_serializing_if = "IndexMap::is_empty"
+    )]
+    pub with: IndexMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<Secrets>,
+}
+
+/// Runner of a job.
+#[derive(PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum RunsOn {
+    Label(String),
+    Labels(Vec<String>),
+    Group {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        group: Option<String>,
+        #[serde(
+            default,
+            deserialize_with = "scalar::one_or_many",
+            skip_serializing_if = "Vec::is_empty"
+        )]
+        labels: Vec<String>,
+    },
+}
+
+/// Deployment environment of a job.
+#[derive(PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Environment {
+    Name(String),
+    Config {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
+}
+
+/// Secrets passed to a reusable workflow.
+#[derive(PartialEq, Eq, Debug)]
+pub enum Secrets {
+    Inherit,
+    Map(IndexMap<String, String>),
+}
+
+impl Serialize for Secrets {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Inherit => serializer.serialize_str("inherit"),
+            Self::Map(secrets) => secrets.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Secrets {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Inherit(String),
+            Map(IndexMap<String, String>),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Inherit(inherit) if inherit == "inherit" => Ok(Self::Inherit),
+            Repr::Inherit(other) => Err(D::Error::custom(format!(
+                "invalid secrets {other:?}, expected `inherit` or a map of secrets"
+            ))),
+            Repr::Map(secrets) => Ok(Self::Map(secrets)),
+        }
+    }
+}
+
+#[derive(Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Strategy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<Matrix>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub fail_fast: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_parallel: Option<String>,
+}
+
+/// Matrix of a job strategy.
+#[derive(PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Matrix {
+    /// A matrix computed by an expression, e.g. `${{ fromJSON(needs.setup.outputs.matrix) }}`.
+    Expression(String),
+    Config(MatrixConfig),
+}
+
+#[derive(Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct MatrixConfig {
+    /// Variables of the matrix, each with a sequence of values (or an expression).
+    #[serde(flatten)]
+    pub variables: IndexMap<String, serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<IndexMap<String, serde_yaml::Value>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<IndexMap<String, serde_yaml::Value>>,
+}
+
+/// A step of a job.
+#[derive(Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Step {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(
+        rename = "if",
+        default,
+        deserialize_with = "scalar::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub r#if: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uses: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::map",
+        skip_serializing_if = "IndexMap::is_empty"
+    )]
+    pub with: IndexMap<String, String>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::map",
+        skip_serializing_if = "IndexMap::is_empty"
+    )]
+    pub env: IndexMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_directory: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub continue_on_error: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "scalar::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timeout_minutes: Option<String>,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq, Clone)]
+pub enum ValidationError {
+    #[error("job {job:?} needs undefined job {need:?}")]
+    UndefinedNeeds { job: String, need: String },
+    #[error("jobs {} depend on each other", .jobs.join(" -> "))]
+    DependencyCycle { jobs: Vec<String> },
+    #[error("output {output:?} of job {job:?} references unknown step {step:?}")]
+    UnknownStepId {
+        job: String,
+        output: String,
+        step: String,
+    },
+}
+
+impl ValidationError {
+    /// Path of the key in the document that caused the error, e.g. `["jobs", "build", "needs"]`.
+    #[must_use]
+    pub fn path(&self) -> Vec<&str> {
+        match self {
+            Self::UndefinedNeeds { job, .. } => vec!["jobs", job, "needs"],
+            Self::DependencyCycle { jobs } => vec!["jobs", &jobs[0], "needs"],
+            Self::UnknownStepId { job, output, .. } => vec!["jobs", job, "outputs", output],
+        }
+    }
+}
+
+/// Collects the ids of the steps referenced as `steps.<id>` in the expressions of `value`.
+fn referenced_steps(value: &str) -> Vec<&str> {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+    let mut steps = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${{") {
+        let expression = &rest[start + 3..];
+        let end = expression.find("}}").unwrap_or(expression.len());
+        let expression = &expression[..end];
+        for (index, _) in expression.match_indices("steps.") {
+            if expression[..index].ends_with(|c: char| is_ident(c) || c == '.') {
+                continue;
+            }
+            let id = &expression[index + "steps.".len()..];
+            let id = &id[..id.find(|c: char| !is_ident(c)).unwrap_or(id.len())];
+            if !id.is_empty() {
+                steps.push(id);
+            }
+        }
+        rest = &rest[start + 3 + end..];
+    }
+    steps
+}
+
+impl Workflow {
+    /// Parses a workflow from a YAML string.
+    ///
+    /// Use [`Document::parse`] to also keep the locations of the keys.
+    ///
+    /// # Errors
+    /// If the string is not a valid workflow.
+    pub fn from_yaml(yaml: &str) -> Result<Self, serde_yaml::Error> {
+        serde_yaml::from_str(yaml)
+    }
+
+    /// Serializes the workflow to YAML.
+    ///
+    /// # Errors
+    /// If the workflow cannot be serialized.
+    pub fn to_yaml(&self) -> Result<String, serde_yaml::Error> {
+        serde_yaml::to_string(self)
+    }
+
+    /// Finds semantic errors that are not caught when parsing:
+    /// undefined or cyclic `needs` and job outputs referencing unknown steps.
+    #[must_use]
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        for (id, job) in &self.jobs {
+            for need in &job.needs {
+                if !self.jobs.contains_key(need) {
+                    errors.push(ValidationError::UndefinedNeeds {
+                        job: id.clone(),
+                        need: need.clone(),
+                    });
+                }
+            }
+        }
+        errors.extend(self.dependency_cycles());
+        for (id, job) in &self.jobs {
+            if job.uses.is_some() {
+                continue;
+            }
+            let step_ids: HashSet<&str> =
+                job.steps.iter().filter_map(|s| s.id.as_deref()).collect();
+            for (output, value) in &job.outputs {
+                for step in referenced_steps(value) {
+                    if !step_ids.contains(step) {
+                        errors.push(ValidationError::UnknownStepId {
+                            job: id.clone(),
+                            output: output.clone(),
+                            step: step.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    fn dependency_cycles(&self) -> Vec<ValidationError> {
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        enum State {
+            Visiting,
+            Done,
+        }
+
+        fn visit<'a>(
+            workflow: &'a Workflow,
+            job: &'a str,
+            states: &mut HashMap<&'a str, State>,
+            stack: &mut Vec<&'a str>,
+            cycles: &mut Vec<ValidationError>,
+        ) {
+            match states.get(job) {
+                Some(State::Done) => return,
+                Some(State::Visiting) => {
+                    let start = stack.iter().position(|j| *j == job).unwrap_or(0);
+                    let mut jobs: Vec<String> =
+                        stack[start..].iter().map(ToString::to_string).collect();
+                    jobs.push(job.to_string());
+                    cycles.push(ValidationError::DependencyCycle { jobs });
+                    return;
+                }
+                None => {}
+            }
+            let Some(needs) = workflow.jobs.get(job).map(|job| &job.needs) else {
+                return;
+            };
+            states.insert(job, State::Visiting);
+            stack.push(job);
+            for need in needs {
+                visit(workflow, need, states, stack, cycles);
+            }
+            stack.pop();
+            states.insert(job, State::Done);
+        }
+
+        let mut states = HashMap::new();
+        let mut cycles = Vec::new();
+        for job in self.jobs.keys() {
+            visit(self, job, &mut states, &mut Vec::new(), &mut cycles);
+        }
+        cycles
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ParseError {
+    #[error("{location}: {message}")]
+    Syntax { location: Location, message: String },
+    #[error("{}{source}", .location.map(|l| format!("{l}: ")).unwrap_or_default())]
+    Invalid {
+        location: Option<Location>,
+        #[source]
+        source: serde_yaml::Error,
+    },
+}
+
+/// Locations of the keys and sequence items of a YAML document.
+///
+/// Paths are the keys and sequence indices joined by `.`, e.g. `jobs.build.steps.0`.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Spans(HashMap<String, Location>);
+
+impl Spans {
+    /// Collects the locations of a YAML document.
+    ///
+    /// # Errors
+    /// If the document is not valid YAML.
+    pub fn from_yaml(yaml: &str) -> Result<Self, ParseError> {
+        let mut collector = SpanCollector::default();
+        yaml_rust2::parser::Parser::new_from_str(yaml)
+            .load(&mut collector, false)
+            .map_err(|err| ParseError::Syntax {
+                location: Location {
+                    line: err.marker().line(),
+                    column: err.marker().col() + 1,
+                },
+                message: err.info().to_string(),
+            })?;
+        Ok(Self(collector.spans))
+    }
+
+    /// Location of the key or sequence item at `path`.
+    #[must_use]
+    pub fn get(&self, path: &[&str]) -> Option<Location> {
+        self.0.get(&path.join(".")).copied()
+    }
+
+    /// Location of the key or sequence item at `path`, or of its closest ancestor.
+    #[must_use]
+    pub fn nearest(&self, path: &[&str]) -> Option<Location> {
+        (0..=path.len())
+            .rev()
+            .find_map(|len| self.get(&path[..len]))
+    }
+}
+
+enum Frame {
+    Mapping { key: Option<String> },
+    Sequence { index: usize },
+}
+
+#[derive(Default)]
+struct SpanCollector {
+    stack: Vec<Frame>,
+    spans: HashMap<String, Location>,
+}
+
+impl SpanCollector {
+    fn path(&self) -> String {
+        let segments: Vec<String> = self
+            .stack
+            .iter()
+            .map(|frame| match frame {
+                Frame::Mapping { key } => key.clone().unwrap_or_default(),
+                Frame::Sequence { index } => index.to_string(),
+            })
+            .collect();
+        segments.join(".")
+    }
+
+    fn record(&mut self, mark: yaml_rust2::scanner::Marker) {
+        let location = Location {
+            line: mark.line(),
+            column: mark.col() + 1,
+        };
+        self.spans.entry(self.path()).or_insert(location);
+    }
+
+    /// Called when a node starts. Returns `true` if the node is a mapping key.
+    fn start_node(&mut self, mark: yaml_rust2::scanner::Marker) -> bool {
+        match self.stack.last() {
+            Some(Frame::Mapping { key: None }) => true,
+            Some(Frame::Sequence { .. }) => {
+                self.record(mark);
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Called when a value node ends.
+    fn end_node(&mut self) {
+        match self.stack.last_mut() {
+            Some(Frame::Mapping { key }) => *key = None,
+            Some(Frame::Sequence { index }) => *index += 1,
+            None => {}
+        }
+    }
+}
+
+impl yaml_rust2::parser::MarkedEventReceiver for SpanCollector {
+    fn on_event(&mut self, event: yaml_rust2::Event, mark: yaml_rust2::scanner::Marker) {
+        use yaml_rust2::Event;
+        match event {
+            Event::Scalar(value, ..) => {
+                if self.start_node(mark) {
+                    if let Some(Frame::Mapping { key }) = self.stack.last_mut() {
+                        *key = Some(value);
+                    }
+                    self.record(mark);
+                } else {
+                    self.end_node();
+                }
+            }
+            Event::Alias(_) if !self.start_node(mark) => self.end_node(),
+            Event::MappingStart(..) | Event::SequenceStart(..) => {
+                // complex keys are not supported and recorded under an empty key
+                if self.start_node(mark)
+                    && let Some(Frame::Mapping { key }) = self.stack.last_mut()
+                {
+                    *key = Some(String::new());
+                }
+                self.stack
+                    .push(if matches!(event, Event::MappingStart(..)) {
+                        Frame::Mapping { key: None }
+                    } else {
+                        Frame::Sequence { index: 0 }
+                    });
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.stack.pop();
+                self.end_node();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A semantic error with its location in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub error: ValidationError,
+    pub location: Option<Location>,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.location {
+            Some(location) => write!(f, "{location}: {}", self.error),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
+/// A workflow parsed together with the locations of its keys.
+#[derive(Debug)]
+pub struct Document {
+    pub workflow: Workflow,
+    pub spans: Spans,
+}
+
+impl Document {
+    /// Parses a workflow file.
+    ///
+    /// # Errors
+    /// If the string is not valid YAML or not a valid workflow.
+    pub fn parse(yaml: &str) -> Result<Self, ParseError> {
+        let spans = Spans::from_yaml(yaml)?;
+        let workflow = serde_yaml::from_str(yaml).map_err(|source| ParseError::Invalid {
+            location: source.location().map(|location| Location {
+                line: location.line(),
+                column: location.column(),
+            }),
+            source,
+        })?;
+        Ok(Self { workflow, spans })
+    }
+
+    /// Validates the workflow, locating each error in the source.
+    #[must_use]
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        self.workflow
+            .validate()
+            .into_iter()
+            .map(|error| {
+                let mut path = error.path();
+                let index;
+                if let ValidationError::UndefinedNeeds { job, need } = &error
+                    && let Some(position) =
+                        self.workflow.jobs[job].needs.iter().position(|n| n == need)
+                {
+                    index = position.to_string();
+                    path.push(&index);
+                }
+                let location = self.spans.nearest(&path);
+                Diagnostic { error, location }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Concurrency, Document, Location, Matrix, ParseError, Permission, Permissions, RunsOn,
+        Secrets, Trigger, ValidationError, Workflow, referenced_steps,
+    };
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    const WORKFLOW: &str = r#"name: ci
+on:
+  push:
+    branches: [main]
+    tags: ['v*']
+  pull_request:
+  schedule:
+    - cron: '0 0 * * 1'
+  workflow_call:
+    inputs:
+      toolchain:
+        type: string
+        default: stable
+    outputs:
+      version:
+        value: ${{ jobs.build.outputs.version }}
+    secrets:
+      token:
+permissions:
+  contents: read
+  pull-requests: write
+concurrency: ci-${{ github.ref }}
+jobs:
+  build:
+    runs-on: ${{ matrix.os }}
+    strategy:
+      fail-fast: false
+      matrix:
+        os: [ubuntu-latest, macos-latest]
+        include:
+          - os: windows-latest
+            experimental: true
+    outputs:
+      version: ${{ steps.version.outputs.version }}
+    steps:
+      - uses: actions/checkout@v4
+        with:
+          fetch-depth: 0
+      - id: version
+        run: echo "version=1" >> "$GITHUB_OUTPUT"
+  release:
+    needs: build
+    uses: ./.github/workflows/release.yml
+    secrets: inherit
+"#;
+
+    #[test]
+    fn test_parse_workflow() -> color_eyre::eyre::Result<()> {
+        let document = Document::parse(WORKFLOW)?;
+        let workflow = &document.workflow;
+        sim_assert_eq!(
+            workflow.on.0.keys().collect::<Vec<_>>(),
+            ["push", "pull_request", "schedule", "workflow_call"]
+        );
+        let Some(Trigger::Event(push)) = workflow.on.0.get("push") else {
+            panic!("expected push event, got {:?}", workflow.on.0.get("push"));
+        };
+        sim_assert_eq!(push.branches, ["main"]);
+        let Some(Trigger::WorkflowCall(call)) = workflow.on.0.get("workflow_call") else {
+            panic!("expected workflow_call");
+        };
+        sim_assert_eq!(call.inputs["toolchain"].default.as_deref(), Some("stable"));
+        sim_assert_eq!(call.secrets["token"].required, None);
+        sim_assert_eq!(
+            workflow.permissions,
+            Some(Permissions::Scopes(
+                [
+                    ("contents".to_string(), Permission::Read),
+                    ("pull-requests".to_string(), Permission::Write),
+                ]
+                .into_iter()
+                .collect()
+            ))
+        );
+        sim_assert_eq!(
+            workflow.concurrency,
+            Some(Concurrency {
+                group: "ci-${{ github.ref }}".into(),
+                cancel_in_progress: None,
+            })
+        );
+
+        let build = &workflow.jobs["build"];
+        sim_assert_eq!(
+            build.runs_on,
+            Some(RunsOn::Label("${{ matrix.os }}".into()))
+        );
+        let strategy = build.strategy.as_ref().unwrap();
+        sim_assert_eq!(strategy.fail_fast.as_deref(), Some("false"));
+        let Some(Matrix::Config(matrix)) = &strategy.matrix else {
+            panic!("expected matrix config, got {:?}", strategy.matrix);
+        };
+        sim_assert_eq!(matrix.variables.keys().collect::<Vec<_>>(), ["os"]);
+        sim_assert_eq!(matrix.include.len(), 1);
+        sim_assert_eq!(build.steps[0].with["fetch-depth"], "0");
+
+        let release = &workflow.jobs["release"];
+        sim_assert_eq!(release.needs, ["build"]);
+        sim_assert_eq!(release.secrets, Some(Secrets::Inherit));
+
+        sim_assert_eq!(document.validate(), vec![]);
+        sim_assert_eq!(Workflow::from_yaml(&workflow.to_yaml()?)?, *workflow);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_short_forms() -> color_eyre::eyre::Result<()> {
+        let workflow = Workflow::from_yaml("on: push\npermissions: read-all\njobs: {}\n")?;
+        sim_assert_eq!(workflow.on.0.keys().collect::<Vec<_>>(), ["push"]);
+        sim_assert_eq!(workflow.permissions, Some(Permissions::ReadAll));
+
+        let workflow = Workflow::from_yaml("on: [push, pull_request]\njobs: {}\n")?;
+        sim_assert_eq!(
+            workflow.on.0.keys().collect::<Vec<_>>(),
+            ["push", "pull_request"]
+        );
+        assert!(Workflow::from_yaml("on: push\npermissions: read\njobs: {}\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() -> color_eyre::eyre::Result<()> {
+        let yaml = r#"on: push
+jobs:
+  a:
+    needs: [c]
+    runs-on: ubuntu-latest
+    steps: []
+  b:
+    needs: [missing, a]
+    runs-on: ubuntu-latest
+    outputs:
+      out: ${{ steps.nope.outputs.value }}
+    steps:
+      - id: yes
+        run: true
+  c:
+    needs: a
+    runs-on: ubuntu-latest
+    steps: []
+"#;
+        let document = Document::parse(yaml)?;
+        let diagnostics: Vec<String> = document
+            .validate()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        sim_assert_eq!(
+            diagnostics,
+            [
+                r#"8:13: job "b" needs undefined job "missing""#,
+                r#"4:5: jobs a -> c -> a depend on each other"#,
+                r#"11:7: output "out" of job "b" references unknown step "nope""#,
+            ]
+        );
+        sim_assert_eq!(
+            document.workflow.validate()[1],
+            ValidationError::DependencyCycle {
+                jobs: vec!["a".into(), "c".into(), "a".into()]
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let err = Document::parse("on: push\njobs:\n  build:\n    needs: {a: b}\n").unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::Invalid {
+                location: Some(Location { line: 4, .. }),
+                ..
+            }
+        ));
+        let err = Document::parse("on: push\njobs: [\n").unwrap_err();
+        assert!(matches!(err, ParseError::Syntax { .. }), "{err:?}");
+    }
+
+    #[test]
+    fn test_referenced_steps() {
+        sim_assert_eq!(
+            referenced_steps("${{ steps.a.outputs.x }}-${{ steps.b-1.outcome || needs.steps.c }}"),
+            ["a", "b-1"]
+        );
+        sim_assert_eq!(referenced_steps("steps.a"), Vec::<&str>::new());
+    }
+}